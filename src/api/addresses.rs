@@ -4,11 +4,33 @@ use axum::{
     Json,
 };
 use duckdb::params;
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use crate::models::{AddressInfo, Balance, BalanceResponse, PaginatedResponse, Pagination, TokenBalance, TransactionSummary};
+use crate::models::{
+    AddressInfo, AddressSummary, Balance, BalanceHistory, BalanceResponse, Counterparty, HistoricalBalance,
+    PaginatedResponse, Pagination, RichListEntry, TokenBalance, TokenFlow, TransactionSummary,
+};
+use crate::utils::{ergo_tree, format_token_amount};
 use crate::AppState;
 
+/// Name, decimals and type of a token
+type TokenInfo = (Option<String>, Option<i32>, Option<String>);
+
+/// Reject malformed addresses, and addresses of the other network, with a 400
+pub(crate) fn check_address(state: &AppState, address: &str) -> Result<(), (StatusCode, String)> {
+    let decoded = ergo_tree::decode_address(address)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid address: {}", e)))?;
+    if decoded.network != state.config.network {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Invalid address: not a {} address", state.config.network),
+        ));
+    }
+    Ok(())
+}
+
 /// GET /api/v1/addresses/:address - Get address info
 #[utoipa::path(
     get,
@@ -19,6 +41,7 @@ use crate::AppState;
     ),
     responses(
         (status = 200, description = "Address information with balance", body = AddressInfo),
+        (status = 400, description = "Invalid address"),
         (status = 404, description = "Address not found"),
         (status = 500, description = "Internal server error")
     )
@@ -27,6 +50,8 @@ pub async fn get_address(
     State(state): State<Arc<AppState>>,
     Path(address): Path<String>,
 ) -> Result<Json<AddressInfo>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
     // Get address stats
     let stats = state
         .db
@@ -45,40 +70,7 @@ pub async fn get_address(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    // Calculate current balance from unspent boxes
-    let nano_ergs: i64 = state
-        .db
-        .query_one(
-            "SELECT COALESCE(SUM(value), 0) FROM boxes
-             WHERE address = ? AND spent_tx_id IS NULL",
-            [&address],
-            |row| row.get(0),
-        )
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .unwrap_or(0);
-
-    // Get token balances
-    let tokens = state
-        .db
-        .query_all(
-            "SELECT ba.token_id, SUM(ba.amount) as total, t.name, t.decimals, t.token_type
-             FROM box_assets ba
-             JOIN boxes b ON ba.box_id = b.box_id
-             LEFT JOIN tokens t ON ba.token_id = t.token_id
-             WHERE b.address = ? AND b.spent_tx_id IS NULL
-             GROUP BY ba.token_id, t.name, t.decimals, t.token_type
-             ORDER BY total DESC",
-            [&address],
-            |row| {
-                Ok(TokenBalance {
-                    token_id: row.get(0)?,
-                    amount: row.get(1)?,
-                    name: row.get(2)?,
-                    decimals: row.get(3)?,
-                    token_type: row.get(4)?,
-                })
-            },
-        )
+    let balance = get_confirmed_balance(&state, &address)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     let (tx_count, _, first_seen, last_seen) = stats.unwrap_or((0, 0, None, None));
@@ -86,26 +78,220 @@ pub async fn get_address(
     Ok(Json(AddressInfo {
         address,
         tx_count,
-        balance: Balance {
-            nano_ergs,
-            tokens,
-        },
+        balance,
         first_seen_height: first_seen,
         last_seen_height: last_seen,
     }))
 }
 
+/// GET /api/v1/addresses/richList - Addresses ranked by confirmed balance
+#[utoipa::path(
+    get,
+    path = "/addresses/richList",
+    tag = "addresses",
+    params(
+        ("offset" = Option<i64>, Query, description = "Pagination offset"),
+        ("limit" = Option<i64>, Query, description = "Results per page")
+    ),
+    responses(
+        (status = 200, description = "Addresses by balance, largest first", body = PaginatedRichList)
+    )
+)]
+pub async fn get_rich_list(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<Pagination>,
+) -> Result<Json<PaginatedResponse<RichListEntry>>, (StatusCode, String)> {
+    let total: i64 = state
+        .db
+        .query_one("SELECT COUNT(*) FROM address_stats WHERE balance > 0", [], |row| row.get(0))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or(0);
+
+    let offset = params.offset;
+    let items = state
+        .db
+        .query_all(
+            "SELECT address, balance, tx_count
+             FROM address_stats
+             WHERE balance > 0
+             ORDER BY balance DESC, address
+             LIMIT ? OFFSET ?",
+            params![params.limit.min(500), params.offset],
+            |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+            },
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .enumerate()
+        .map(|(i, (address, balance, tx_count))| RichListEntry {
+            rank: offset + i as i64 + 1,
+            address,
+            balance,
+            tx_count,
+        })
+        .collect();
+
+    Ok(Json(PaginatedResponse::new(items, total)))
+}
+
 /// GET /api/v1/addresses/:address/balance/total - Get total balance
 pub async fn get_balance_total(
     State(state): State<Arc<AppState>>,
     Path(address): Path<String>,
 ) -> Result<Json<BalanceResponse>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
+    let confirmed = get_confirmed_balance(&state, &address)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let unconfirmed = get_unconfirmed_balance(&state, &address)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(BalanceResponse {
+        confirmed,
+        unconfirmed,
+    }))
+}
+
+/// Map a `(token_id, amount, name, decimals, token_type)` row
+pub(crate) fn token_balance_from_row(row: &duckdb::Row) -> duckdb::Result<TokenBalance> {
+    let amount: i64 = row.get(1)?;
+    let decimals: Option<i32> = row.get(3)?;
+    Ok(TokenBalance {
+        token_id: row.get(0)?,
+        amount,
+        adjusted_amount: format_token_amount(amount, decimals),
+        name: row.get(2)?,
+        decimals,
+        token_type: row.get(4)?,
+    })
+}
+
+/// Confirmed balance from the incrementally maintained address_stats and token_holders
+pub(crate) fn get_confirmed_balance(state: &Arc<AppState>, address: &str) -> anyhow::Result<Balance> {
+    let nano_ergs: i64 = state
+        .db
+        .query_one(
+            "SELECT balance FROM address_stats WHERE address = ?",
+            [address],
+            |row| row.get(0),
+        )?
+        .unwrap_or(0);
+
+    let tokens = state.db.query_all(
+        "SELECT th.token_id, th.amount, t.name, t.decimals, t.token_type
+         FROM token_holders th
+         LEFT JOIN tokens t ON th.token_id = t.token_id
+         WHERE th.address = ?
+         ORDER BY th.amount DESC",
+        [address],
+        token_balance_from_row,
+    )?;
+
+    Ok(Balance { nano_ergs, tokens })
+}
+
+/// Net change the mempool makes to an address: received outputs minus spent inputs
+fn get_unconfirmed_balance(state: &Arc<AppState>, address: &str) -> anyhow::Result<Balance> {
+    let nano_ergs: i64 = state
+        .db
+        .query_one(
+            "SELECT
+                 COALESCE((SELECT SUM(value) FROM mempool_outputs WHERE address = ?), 0)
+               - COALESCE((SELECT SUM(value) FROM mempool_inputs WHERE address = ?), 0)",
+            params![address, address],
+            |row| row.get(0),
+        )?
+        .unwrap_or(0);
+
+    // Spent boxes may be confirmed or themselves unconfirmed (chained transactions)
+    let tokens = state.db.query_all(
+        "SELECT d.token_id, SUM(d.amount) AS total, t.name, t.decimals, t.token_type
+         FROM (
+             SELECT ma.token_id, ma.amount
+             FROM mempool_assets ma
+             JOIN mempool_outputs mo ON ma.box_id = mo.box_id
+             WHERE mo.address = ?
+             UNION ALL
+             SELECT ba.token_id, -ba.amount
+             FROM mempool_inputs mi
+             JOIN box_assets ba ON ba.box_id = mi.box_id
+             WHERE mi.address = ?
+             UNION ALL
+             SELECT ma.token_id, -ma.amount
+             FROM mempool_inputs mi
+             JOIN mempool_assets ma ON ma.box_id = mi.box_id
+             WHERE mi.address = ?
+         ) d
+         LEFT JOIN tokens t ON d.token_id = t.token_id
+         GROUP BY d.token_id, t.name, t.decimals, t.token_type
+         HAVING SUM(d.amount) <> 0
+         ORDER BY total DESC",
+        params![address, address, address],
+        token_balance_from_row,
+    )?;
+
+    Ok(Balance { nano_ergs, tokens })
+}
+
+/// GET /api/v1/addresses/:address/balance/confirmed - Get confirmed balance
+pub async fn get_balance_confirmed(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+) -> Result<Json<BalanceResponse>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
+    let confirmed = get_confirmed_balance(&state, &address)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(BalanceResponse {
+        confirmed,
+        unconfirmed: Balance {
+            nano_ergs: 0,
+            tokens: Vec::new(),
+        },
+    }))
+}
+
+/// GET /api/v1/addresses/:address/balance/atHeight/:height - Confirmed balance after a block
+#[utoipa::path(
+    get,
+    path = "/addresses/{address}/balance/atHeight/{height}",
+    tag = "addresses",
+    params(
+        ("address" = String, Path, description = "Ergo address"),
+        ("height" = i64, Path, description = "Block height")
+    ),
+    responses(
+        (status = 200, description = "Balance including the block at the height", body = HistoricalBalance),
+        (status = 400, description = "Invalid address or height not indexed yet")
+    )
+)]
+pub async fn get_balance_at_height(
+    State(state): State<Arc<AppState>>,
+    Path((address, height)): Path<(String, i64)>,
+) -> Result<Json<HistoricalBalance>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
+    let tip = state
+        .db
+        .get_sync_height()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if height < 0 || height > tip {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Height must be between 0 and the indexed height {}", tip),
+        ));
+    }
+
+    // Boxes created at or before the height and not yet spent by it
     let nano_ergs: i64 = state
         .db
         .query_one(
             "SELECT COALESCE(SUM(value), 0) FROM boxes
-             WHERE address = ? AND spent_tx_id IS NULL",
-            [&address],
+             WHERE address = ? AND settlement_height <= ?
+               AND (spent_height IS NULL OR spent_height > ?)",
+            params![address, height, height],
             |row| row.get(0),
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
@@ -114,42 +300,383 @@ pub async fn get_balance_total(
     let tokens = state
         .db
         .query_all(
-            "SELECT ba.token_id, SUM(ba.amount) as total, t.name, t.decimals, t.token_type
-             FROM box_assets ba
-             JOIN boxes b ON ba.box_id = b.box_id
+            "SELECT ba.token_id, SUM(ba.amount) AS total, t.name, t.decimals, t.token_type
+             FROM boxes b
+             JOIN box_assets ba ON ba.box_id = b.box_id
              LEFT JOIN tokens t ON ba.token_id = t.token_id
-             WHERE b.address = ? AND b.spent_tx_id IS NULL
+             WHERE b.address = ? AND b.settlement_height <= ?
+               AND (b.spent_height IS NULL OR b.spent_height > ?)
              GROUP BY ba.token_id, t.name, t.decimals, t.token_type
              ORDER BY total DESC",
+            params![address, height, height],
+            token_balance_from_row,
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let timestamp = state
+        .db
+        .query_one(
+            "SELECT timestamp FROM blocks WHERE height = ? AND main_chain = TRUE",
+            [height],
+            |row| row.get(0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(HistoricalBalance {
+        height,
+        timestamp,
+        nano_ergs,
+        tokens,
+    }))
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryInterval {
+    #[default]
+    Daily,
+    Weekly,
+}
+
+const DAY_MS: i64 = 86_400_000;
+/// 1970-01-05, the first Monday after the epoch; weekly buckets start on Mondays
+const WEEK_ORIGIN_MS: i64 = 4 * DAY_MS;
+
+impl HistoryInterval {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HistoryInterval::Daily => "daily",
+            HistoryInterval::Weekly => "weekly",
+        }
+    }
+
+    /// Bucket length and the timestamp buckets are aligned to
+    fn bucket(&self) -> (i64, i64) {
+        match self {
+            HistoryInterval::Daily => (DAY_MS, 0),
+            HistoryInterval::Weekly => (7 * DAY_MS, WEEK_ORIGIN_MS),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct BalanceHistoryQuery {
+    #[serde(default)]
+    pub interval: HistoryInterval,
+    /// Only return buckets starting at or after this timestamp (ms)
+    pub from: Option<i64>,
+    /// Only return buckets starting before this timestamp (ms)
+    pub to: Option<i64>,
+}
+
+/// GET /api/v1/addresses/:address/balance/history - Confirmed balance over time
+///
+/// Balances are rebuilt from box creation and spend heights. Each item is the
+/// balance at the end of a bucket in which it changed; buckets without activity
+/// are left out, so the balance carries forward until the next item.
+#[utoipa::path(
+    get,
+    path = "/addresses/{address}/balance/history",
+    tag = "addresses",
+    params(
+        ("address" = String, Path, description = "Ergo address"),
+        ("interval" = Option<String>, Query, description = "daily (default) or weekly"),
+        ("from" = Option<i64>, Query, description = "Earliest bucket start, ms timestamp"),
+        ("to" = Option<i64>, Query, description = "Buckets must start before this ms timestamp")
+    ),
+    responses(
+        (status = 200, description = "Balance at the end of each bucket with activity", body = BalanceHistory),
+        (status = 400, description = "Invalid address or interval")
+    )
+)]
+pub async fn get_balance_history(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+    Query(query): Query<BalanceHistoryQuery>,
+) -> Result<Json<BalanceHistory>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
+    let (bucket_ms, origin_ms) = query.interval.bucket();
+
+    // Net nanoERG change per bucket, with the last height that changed it
+    let erg_deltas = state
+        .db
+        .query_all(
+            "SELECT (bl.timestamp - ?) // ? AS bucket, MAX(d.height), SUM(d.delta)
+             FROM (
+                 SELECT settlement_height AS height, value AS delta
+                 FROM boxes WHERE address = ?
+                 UNION ALL
+                 SELECT spent_height, -value
+                 FROM boxes WHERE address = ? AND spent_height IS NOT NULL
+             ) d
+             JOIN blocks bl ON bl.height = d.height AND bl.main_chain = TRUE
+             GROUP BY bucket
+             ORDER BY bucket",
+            params![origin_ms, bucket_ms, address, address],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let token_deltas = state
+        .db
+        .query_all(
+            "SELECT (bl.timestamp - ?) // ? AS bucket, d.token_id, SUM(d.delta)
+             FROM (
+                 SELECT b.settlement_height AS height, ba.token_id, ba.amount AS delta
+                 FROM boxes b JOIN box_assets ba ON ba.box_id = b.box_id
+                 WHERE b.address = ?
+                 UNION ALL
+                 SELECT b.spent_height, ba.token_id, -ba.amount
+                 FROM boxes b JOIN box_assets ba ON ba.box_id = b.box_id
+                 WHERE b.address = ? AND b.spent_height IS NOT NULL
+             ) d
+             JOIN blocks bl ON bl.height = d.height AND bl.main_chain = TRUE
+             GROUP BY bucket, d.token_id",
+            params![origin_ms, bucket_ms, address, address],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?)),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let token_info: HashMap<String, TokenInfo> = state
+        .db
+        .query_all(
+            "SELECT token_id, name, decimals, token_type FROM tokens
+             WHERE token_id IN (
+                 SELECT DISTINCT ba.token_id
+                 FROM boxes b JOIN box_assets ba ON ba.box_id = b.box_id
+                 WHERE b.address = ?
+             )",
+            [&address],
+            |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?, row.get(3)?))),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .into_iter()
+        .collect();
+
+    let mut token_changes: HashMap<i64, Vec<(String, i64)>> = HashMap::new();
+    for (bucket, token_id, delta) in token_deltas {
+        token_changes.entry(bucket).or_default().push((token_id, delta));
+    }
+
+    // Every box that moves tokens also moves nanoERG, so each bucket with token
+    // changes has a nanoERG row as well
+    let mut nano_ergs = 0;
+    let mut token_balances: HashMap<String, i64> = HashMap::new();
+    let mut items = Vec::new();
+    for (bucket, height, delta) in erg_deltas {
+        nano_ergs += delta;
+        for (token_id, delta) in token_changes.remove(&bucket).unwrap_or_default() {
+            *token_balances.entry(token_id).or_insert(0) += delta;
+        }
+
+        let timestamp = origin_ms + bucket * bucket_ms;
+        if query.from.is_some_and(|from| timestamp < from) || query.to.is_some_and(|to| timestamp >= to) {
+            continue;
+        }
+
+        let mut tokens: Vec<TokenBalance> = token_balances
+            .iter()
+            .filter(|(_, amount)| **amount != 0)
+            .map(|(token_id, &amount)| {
+                let (name, decimals, token_type) = token_info.get(token_id).cloned().unwrap_or_default();
+                TokenBalance {
+                    token_id: token_id.clone(),
+                    amount,
+                    adjusted_amount: format_token_amount(amount, decimals),
+                    name,
+                    decimals,
+                    token_type,
+                }
+            })
+            .collect();
+        tokens.sort_by(|a, b| b.amount.cmp(&a.amount).then_with(|| a.token_id.cmp(&b.token_id)));
+
+        items.push(HistoricalBalance {
+            height,
+            timestamp: Some(timestamp),
+            nano_ergs,
+            tokens,
+        });
+    }
+
+    Ok(Json(BalanceHistory {
+        interval: query.interval.as_str().to_string(),
+        items,
+    }))
+}
+
+/// GET /api/v1/addresses/:address/summary - Received/sent totals, fees and activity range
+#[utoipa::path(
+    get,
+    path = "/addresses/{address}/summary",
+    tag = "addresses",
+    params(
+        ("address" = String, Path, description = "Ergo address")
+    ),
+    responses(
+        (status = 200, description = "Totals over the address's confirmed transactions", body = AddressSummary),
+        (status = 400, description = "Invalid address")
+    )
+)]
+pub async fn get_address_summary(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+) -> Result<Json<AddressSummary>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
+    let (tx_count, total_received, total_sent, fees_paid, first_activity, last_activity, first_height, last_height) =
+        state
+            .db
+            .query_one(
+                "SELECT COUNT(*),
+                        COALESCE(SUM(value_change) FILTER (WHERE value_change > 0), 0),
+                        COALESCE(-SUM(value_change) FILTER (WHERE value_change < 0), 0),
+                        COALESCE(SUM(fee), 0),
+                        MIN(timestamp), MAX(timestamp), MIN(height), MAX(height)
+                 FROM address_activity
+                 WHERE address = ?",
+                [&address],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, i64>(3)?,
+                        row.get::<_, Option<i64>>(4)?,
+                        row.get::<_, Option<i64>>(5)?,
+                        row.get::<_, Option<i64>>(6)?,
+                        row.get::<_, Option<i64>>(7)?,
+                    ))
+                },
+            )
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .unwrap_or((0, 0, 0, 0, None, None, None, None));
+
+    let tokens = state
+        .db
+        .query_all(
+            "SELECT a.token_id, t.name, t.decimals,
+                    COALESCE(SUM(a.amount_change) FILTER (WHERE a.amount_change > 0), 0) AS received,
+                    COALESCE(-SUM(a.amount_change) FILTER (WHERE a.amount_change < 0), 0) AS sent
+             FROM address_token_activity a
+             LEFT JOIN tokens t ON t.token_id = a.token_id
+             WHERE a.address = ?
+             GROUP BY a.token_id, t.name, t.decimals
+             ORDER BY received DESC, a.token_id",
             [&address],
             |row| {
-                Ok(TokenBalance {
+                let decimals: Option<i32> = row.get(2)?;
+                let received: i64 = row.get(3)?;
+                let sent: i64 = row.get(4)?;
+                Ok(TokenFlow {
                     token_id: row.get(0)?,
-                    amount: row.get(1)?,
-                    name: row.get(2)?,
-                    decimals: row.get(3)?,
-                    token_type: row.get(4)?,
+                    name: row.get(1)?,
+                    decimals,
+                    received,
+                    sent,
+                    adjusted_received: format_token_amount(received, decimals),
+                    adjusted_sent: format_token_amount(sent, decimals),
                 })
             },
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(BalanceResponse {
-        confirmed: Balance { nano_ergs, tokens },
-        unconfirmed: Balance {
-            nano_ergs: 0,
-            tokens: vec![],
-        },
+    Ok(Json(AddressSummary {
+        address,
+        tx_count,
+        total_received,
+        total_sent,
+        fees_paid,
+        first_activity,
+        last_activity,
+        first_activity_height: first_height,
+        last_activity_height: last_height,
+        tokens,
     }))
 }
 
-/// GET /api/v1/addresses/:address/balance/confirmed - Get confirmed balance
-pub async fn get_balance_confirmed(
+/// GET /api/v1/addresses/:address/counterparties - Addresses paid by or paying this address
+///
+/// In a transaction where the address's balance went down, every address whose
+/// balance went up is a counterparty it sent to, and the other way round. The
+/// miners' fee contract is left out; fees are part of the summary.
+#[utoipa::path(
+    get,
+    path = "/addresses/{address}/counterparties",
+    tag = "addresses",
+    params(
+        ("address" = String, Path, description = "Ergo address"),
+        ("offset" = Option<i64>, Query, description = "Pagination offset"),
+        ("limit" = Option<i64>, Query, description = "Results per page (max 500)")
+    ),
+    responses(
+        (status = 200, description = "Counterparties by shared transaction count", body = PaginatedCounterparties),
+        (status = 400, description = "Invalid address")
+    )
+)]
+pub async fn get_address_counterparties(
     State(state): State<Arc<AppState>>,
     Path(address): Path<String>,
-) -> Result<Json<BalanceResponse>, (StatusCode, String)> {
-    // For now, same as total (we're not tracking mempool separately)
-    get_balance_total(State(state), Path(address)).await
+    Query(params): Query<Pagination>,
+) -> Result<Json<PaginatedResponse<Counterparty>>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+    let limit = params.limit.clamp(1, 500);
+
+    let fee_address = ergo_tree::ergo_tree_to_address(ergo_tree::MINERS_FEE_ERGO_TREE, state.config.network)
+        .unwrap_or_default();
+    let pairs = "FROM address_activity a
+                 JOIN address_activity o ON o.tx_id = a.tx_id
+                 WHERE a.address = ? AND o.address <> a.address AND o.address <> ?
+                   AND ((a.value_change < 0 AND o.value_change > 0)
+                     OR (a.value_change > 0 AND o.value_change < 0))";
+
+    let total: i64 = state
+        .db
+        .query_one(
+            &format!("SELECT COUNT(DISTINCT o.address) {}", pairs),
+            params![address, fee_address],
+            |row| row.get(0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or(0);
+
+    let items = state
+        .db
+        .query_all(
+            &format!(
+                "SELECT c.address, l.label, c.tx_count, c.sent, c.received, c.first_activity, c.last_activity
+                 FROM (
+                     SELECT o.address,
+                            COUNT(*) AS tx_count,
+                            COALESCE(SUM(o.value_change) FILTER (WHERE o.value_change > 0), 0) AS sent,
+                            COALESCE(-SUM(o.value_change) FILTER (WHERE o.value_change < 0), 0) AS received,
+                            MIN(a.timestamp) AS first_activity,
+                            MAX(a.timestamp) AS last_activity
+                     {}
+                     GROUP BY o.address
+                 ) c
+                 LEFT JOIN address_labels l ON l.address = c.address
+                 ORDER BY c.tx_count DESC, c.address
+                 LIMIT ? OFFSET ?",
+                pairs
+            ),
+            params![address, fee_address, limit, params.offset],
+            |row| {
+                Ok(Counterparty {
+                    address: row.get(0)?,
+                    label: row.get(1)?,
+                    tx_count: row.get(2)?,
+                    sent: row.get(3)?,
+                    received: row.get(4)?,
+                    first_activity: row.get(5)?,
+                    last_activity: row.get(6)?,
+                })
+            },
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
 /// GET /api/v1/addresses/:address/transactions - Get address transactions
@@ -158,6 +685,8 @@ pub async fn get_address_transactions(
     Path(address): Path<String>,
     Query(params): Query<Pagination>,
 ) -> Result<Json<PaginatedResponse<TransactionSummary>>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
     let total: i64 = state
         .db
         .query_one(
@@ -204,5 +733,5 @@ pub async fn get_address_transactions(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
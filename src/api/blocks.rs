@@ -7,6 +7,7 @@ use duckdb::params;
 use serde::Deserialize;
 use std::sync::Arc;
 
+use crate::api::addresses::check_address;
 use crate::models::{Block, BlockSummary, PaginatedResponse, Pagination};
 use crate::AppState;
 
@@ -81,7 +82,7 @@ pub async fn get_blocks(
         })
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
 /// GET /api/v1/blocks/:id - Get block by ID or height
@@ -246,6 +247,7 @@ pub async fn get_block_at_height(
     ),
     responses(
         (status = 200, description = "Blocks by miner", body = PaginatedBlocks),
+        (status = 400, description = "Invalid address"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -254,6 +256,8 @@ pub async fn get_blocks_by_miner(
     Path(address): Path<String>,
     Query(pag): Query<Pagination>,
 ) -> Result<Json<PaginatedResponse<BlockSummary>>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
     let total: i64 = state
         .db
         .query_one(
@@ -287,5 +291,5 @@ pub async fn get_blocks_by_miner(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
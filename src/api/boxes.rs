@@ -3,11 +3,14 @@ use axum::{
     http::StatusCode,
     Json,
 };
-use duckdb::params;
+use duckdb::{params, params_from_iter, types::Value};
 use serde::Deserialize;
 use std::sync::Arc;
 
-use crate::models::{BoxAsset, BoxSearchQuery, Output, PaginatedResponse, Pagination};
+use crate::api::addresses::check_address;
+use crate::api::loaders::attach_assets;
+use crate::models::{BoxSearchQuery, Output, PaginatedResponse, Pagination};
+use crate::utils::{cursor, sigma};
 use crate::AppState;
 
 #[derive(Deserialize)]
@@ -56,10 +59,13 @@ pub async fn get_box(
     params(
         ("address" = String, Path, description = "Ergo address"),
         ("offset" = Option<i64>, Query, description = "Pagination offset"),
-        ("limit" = Option<i64>, Query, description = "Results per page")
+        ("limit" = Option<i64>, Query, description = "Results per page"),
+        ("cursor" = Option<String>, Query, description = "nextCursor from the previous page; replaces offset"),
+        ("withTotal" = Option<bool>, Query, description = "Count the total (default: true with offset, false with cursor)")
     ),
     responses(
-        (status = 200, description = "Boxes by address", body = PaginatedOutputs)
+        (status = 200, description = "Boxes by address", body = PaginatedOutputs),
+        (status = 400, description = "Invalid address")
     )
 )]
 pub async fn get_boxes_by_address(
@@ -67,6 +73,8 @@ pub async fn get_boxes_by_address(
     Path(address): Path<String>,
     Query(params): Query<Pagination>,
 ) -> Result<Json<PaginatedResponse<Output>>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
     get_boxes_with_filter(&state, "address = ?", &address, &params, false).await
 }
 
@@ -78,10 +86,13 @@ pub async fn get_boxes_by_address(
     params(
         ("address" = String, Path, description = "Ergo address"),
         ("offset" = Option<i64>, Query, description = "Pagination offset"),
-        ("limit" = Option<i64>, Query, description = "Results per page")
+        ("limit" = Option<i64>, Query, description = "Results per page"),
+        ("cursor" = Option<String>, Query, description = "nextCursor from the previous page; replaces offset"),
+        ("withTotal" = Option<bool>, Query, description = "Count the total (default: true with offset, false with cursor)")
     ),
     responses(
-        (status = 200, description = "Unspent boxes by address", body = PaginatedOutputs)
+        (status = 200, description = "Unspent boxes by address", body = PaginatedOutputs),
+        (status = 400, description = "Invalid address")
     )
 )]
 pub async fn get_unspent_boxes_by_address(
@@ -89,6 +100,8 @@ pub async fn get_unspent_boxes_by_address(
     Path(address): Path<String>,
     Query(params): Query<Pagination>,
 ) -> Result<Json<PaginatedResponse<Output>>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
     get_boxes_with_filter(&state, "address = ?", &address, &params, true).await
 }
 
@@ -98,8 +111,9 @@ pub async fn get_unspent_unconfirmed_by_address(
     Path(address): Path<String>,
     Query(params): Query<Pagination>,
 ) -> Result<Json<PaginatedResponse<Output>>, (StatusCode, String)> {
-    // For now, same as unspent (mempool boxes would be added from mempool table)
-    get_boxes_with_filter(&state, "address = ?", &address, &params, true).await
+    check_address(&state, &address)?;
+
+    get_unspent_with_mempool(&state, &address, &params, false).await
 }
 
 /// GET /api/v1/boxes/unspent/all/byAddress/:address
@@ -108,7 +122,9 @@ pub async fn get_all_unspent_by_address(
     Path(address): Path<String>,
     Query(params): Query<Pagination>,
 ) -> Result<Json<PaginatedResponse<Output>>, (StatusCode, String)> {
-    get_boxes_with_filter(&state, "address = ?", &address, &params, true).await
+    check_address(&state, &address)?;
+
+    get_unspent_with_mempool(&state, &address, &params, true).await
 }
 
 /// GET /api/v1/boxes/byErgoTree/:ergoTree - Get boxes by ErgoTree
@@ -188,7 +204,7 @@ pub async fn stream_unspent_by_epochs(
 
     let min_height = (current_height - (epochs as i64 * epoch_length)).max(0);
 
-    let items = state
+    let mut result = state
         .db
         .query_all(
             "SELECT box_id, tx_id, output_index, ergo_tree, address, value,
@@ -202,12 +218,8 @@ pub async fn stream_unspent_by_epochs(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let mut result = Vec::new();
-    for output in items {
-        let box_with_assets = enrich_box_with_assets(&state, output)
+    attach_assets(&state.db, &mut result)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-        result.push(box_with_assets);
-    }
 
     Ok(Json(result))
 }
@@ -280,7 +292,7 @@ pub async fn search_unspent_union(
 // Helper functions
 
 async fn get_box_by_id(state: &Arc<AppState>, box_id: &str) -> Result<Json<Output>, (StatusCode, String)> {
-    let output = state
+    let mut output = state
         .db
         .query_one(
             "SELECT box_id, tx_id, output_index, ergo_tree, address, value,
@@ -292,10 +304,10 @@ async fn get_box_by_id(state: &Arc<AppState>, box_id: &str) -> Result<Json<Outpu
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .ok_or((StatusCode::NOT_FOUND, "Box not found".to_string()))?;
 
-    let result = enrich_box_with_assets(state, output)
+    attach_assets(&state.db, std::slice::from_mut(&mut output))
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(result))
+    Ok(Json(output))
 }
 
 async fn get_boxes_with_filter(
@@ -306,36 +318,151 @@ async fn get_boxes_with_filter(
     unspent_only: bool,
 ) -> Result<Json<PaginatedResponse<Output>>, (StatusCode, String)> {
     let spent_filter = if unspent_only { " AND spent_tx_id IS NULL" } else { "" };
+    // Boxes are ordered by (creation_height DESC, global_index DESC), so the cursor carries both
+    let after = params
+        .cursor
+        .as_deref()
+        .map(|c| {
+            cursor::decode(c, 2)
+                .and_then(|parts| Some((parts[0].parse::<i64>().ok()?, parts[1].parse::<i64>().ok()?)))
+                .ok_or((StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))
+        })
+        .transpose()?;
+
+    let total = if params.include_total() {
+        let count_sql = format!("SELECT COUNT(*) FROM boxes WHERE {}{}", filter, spent_filter);
+        let total: i64 = state
+            .db
+            .query_one(&count_sql, [value], |row| row.get(0))
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .unwrap_or(0);
+        Some(total)
+    } else {
+        None
+    };
 
-    let count_sql = format!("SELECT COUNT(*) FROM boxes WHERE {}{}", filter, spent_filter);
-    let total: i64 = state
-        .db
-        .query_one(&count_sql, [value], |row| row.get(0))
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .unwrap_or(0);
+    // Keyset paging seeks past the cursor instead of scanning `offset` rows
+    let mut args = vec![Value::Text(value.to_string())];
+    let page_filter = match after {
+        Some((creation_height, global_index)) => {
+            args.push(Value::BigInt(creation_height));
+            args.push(Value::BigInt(creation_height));
+            args.push(Value::BigInt(global_index));
+            args.push(Value::BigInt(params.limit));
+            " AND (creation_height < ? OR (creation_height = ? AND global_index < ?))
+             ORDER BY creation_height DESC, global_index DESC LIMIT ?"
+        }
+        None => {
+            args.push(Value::BigInt(params.limit));
+            args.push(Value::BigInt(params.offset));
+            " ORDER BY creation_height DESC, global_index DESC LIMIT ? OFFSET ?"
+        }
+    };
 
     let sql = format!(
         "SELECT box_id, tx_id, output_index, ergo_tree, address, value,
-                creation_height, settlement_height, additional_registers, spent_tx_id
-         FROM boxes WHERE {}{}
-         ORDER BY creation_height DESC
-         LIMIT ? OFFSET ?",
-        filter, spent_filter
+                creation_height, settlement_height, additional_registers, spent_tx_id, global_index
+         FROM boxes WHERE {}{}{}",
+        filter, spent_filter, page_filter
     );
 
     let boxes = state
         .db
-        .query_all(&sql, params![value, params.limit, params.offset], |row| box_from_row(row))
+        .query_all(&sql, params_from_iter(args), |row| Ok((box_from_row(row)?, row.get::<_, i64>(10)?)))
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let mut items = Vec::new();
-    for output in boxes {
-        let enriched = enrich_box_with_assets(state, output)
+    let next_cursor = match boxes.last() {
+        Some((last, global_index)) if boxes.len() as i64 == params.limit => Some(cursor::encode(&[
+            &last.creation_height.to_string(),
+            &global_index.to_string(),
+        ])),
+        _ => None,
+    };
+
+    let mut items: Vec<Output> = boxes.into_iter().map(|(output, _)| output).collect();
+    attach_assets(&state.db, &mut items)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-        items.push(enriched);
+
+    Ok(Json(PaginatedResponse { items, total, next_cursor }))
+}
+
+/// Decode a `global_index` cursor from the query, rejecting malformed ones with 400
+pub(crate) fn index_cursor(params: &Pagination) -> Result<Option<i64>, (StatusCode, String)> {
+    params
+        .cursor
+        .as_deref()
+        .map(|c| cursor::decode_index(c).ok_or((StatusCode::BAD_REQUEST, "Invalid cursor".to_string())))
+        .transpose()
+}
+
+/// Unspent boxes of an address as seen after the mempool is applied: mempool
+/// outputs not spent by other mempool transactions and, if `include_confirmed`,
+/// confirmed boxes that no mempool transaction spends
+async fn get_unspent_with_mempool(
+    state: &Arc<AppState>,
+    address: &str,
+    params: &Pagination,
+    include_confirmed: bool,
+) -> Result<Json<PaginatedResponse<Output>>, (StatusCode, String)> {
+    let unconfirmed = "SELECT box_id, tx_id, output_index, ergo_tree, address, value,
+                creation_height, 0 AS settlement_height, additional_registers, NULL AS spent_tx_id,
+                FALSE AS confirmed
+         FROM mempool_outputs m
+         WHERE address = ? AND box_id NOT IN (SELECT box_id FROM mempool_inputs)
+           AND NOT EXISTS (SELECT 1 FROM boxes b WHERE b.box_id = m.box_id)";
+    let confirmed = "SELECT box_id, tx_id, output_index, ergo_tree, address, value,
+                creation_height, settlement_height, additional_registers, spent_tx_id,
+                TRUE AS confirmed
+         FROM boxes
+         WHERE address = ? AND spent_tx_id IS NULL
+           AND box_id NOT IN (SELECT box_id FROM mempool_inputs)";
+
+    let union_sql = if include_confirmed {
+        format!("{} UNION ALL {}", unconfirmed, confirmed)
+    } else {
+        unconfirmed.to_string()
+    };
+
+    let total: i64 = if include_confirmed {
+        state.db.query_one(
+            &format!("SELECT COUNT(*) FROM ({})", union_sql),
+            params![address, address],
+            |row| row.get(0),
+        )
+    } else {
+        state.db.query_one(
+            &format!("SELECT COUNT(*) FROM ({})", union_sql),
+            params![address],
+            |row| row.get(0),
+        )
     }
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .unwrap_or(0);
+
+    // Unconfirmed boxes first, then newest confirmed
+    let sql = format!(
+        "SELECT * FROM ({})
+         ORDER BY confirmed ASC, settlement_height DESC, creation_height DESC
+         LIMIT ? OFFSET ?",
+        union_sql
+    );
+    let row_fn = |row: &duckdb::Row<'_>| {
+        let mut output = box_from_row(row)?;
+        output.main_chain = row.get(10)?;
+        Ok(output)
+    };
+
+    let mut items = if include_confirmed {
+        state.db.query_all(&sql, params![address, address, params.limit, params.offset], row_fn)
+    } else {
+        state.db.query_all(&sql, params![address, params.limit, params.offset], row_fn)
+    }
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    attach_assets(&state.db, &mut items)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
 async fn get_boxes_by_token_id(
@@ -371,19 +498,15 @@ async fn get_boxes_by_token_id(
         spent_filter
     );
 
-    let boxes = state
+    let mut items = state
         .db
         .query_all(&sql, params![token_id, params.limit, params.offset], |row| box_from_row(row))
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let mut items = Vec::new();
-    for output in boxes {
-        let enriched = enrich_box_with_assets(state, output)
+    attach_assets(&state.db, &mut items)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-        items.push(enriched);
-    }
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
 async fn stream_boxes(
@@ -409,7 +532,7 @@ async fn stream_boxes(
         spent_filter, extra
     );
 
-    let boxes = if let Some((_, ref val)) = extra_filter {
+    let mut result = if let Some((_, ref val)) = extra_filter {
         state
             .db
             .query_all(&sql, params![min_gix, val, limit], |row| box_from_row(row))
@@ -420,12 +543,8 @@ async fn stream_boxes(
     }
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let mut result = Vec::new();
-    for output in boxes {
-        let enriched = enrich_box_with_assets(state, output)
+    attach_assets(&state.db, &mut result)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-        result.push(enriched);
-    }
 
     Ok(Json(result))
 }
@@ -437,11 +556,44 @@ async fn search_boxes_impl(
     unspent_only: bool,
 ) -> Result<Json<PaginatedResponse<Output>>, (StatusCode, String)> {
     let mut conditions: Vec<String> = Vec::new();
-    let mut _values: Vec<String> = Vec::new();
+    let mut values: Vec<Value> = Vec::new();
 
     if let Some(ref hash) = query.ergo_tree_template_hash {
         conditions.push("ergo_tree_template_hash = ?".to_string());
-        _values.push(hash.clone());
+        values.push(Value::Text(hash.clone()));
+    }
+
+    // Registers match on either the rendered or the serialized value, like the Explorer
+    if let Some(ref registers) = query.registers {
+        for (register_id, value) in search_entries(registers, "registers")? {
+            if !matches!(register_id.as_str(), "R4" | "R5" | "R6" | "R7" | "R8" | "R9") {
+                return Err((StatusCode::BAD_REQUEST, format!("Invalid register id: {}", register_id)));
+            }
+            conditions.push(
+                "box_id IN (SELECT box_id FROM box_registers
+                            WHERE register_id = ? AND (rendered_value = ? OR serialized_value = ?))"
+                    .to_string(),
+            );
+            values.push(Value::Text(register_id));
+            values.push(Value::Text(value.clone()));
+            values.push(Value::Text(value));
+        }
+    }
+
+    if let Some(ref constants) = query.constants {
+        for (index, value) in search_entries(constants, "constants")? {
+            let index: i32 = index
+                .parse()
+                .map_err(|_| (StatusCode::BAD_REQUEST, format!("Invalid constant index: {}", index)))?;
+            conditions.push(
+                "box_id IN (SELECT box_id FROM script_constants
+                            WHERE constant_index = ? AND (rendered_value = ? OR serialized_value = ?))"
+                    .to_string(),
+            );
+            values.push(Value::Int(index));
+            values.push(Value::Text(value.clone()));
+            values.push(Value::Text(value));
+        }
     }
 
     if let Some(ref assets) = query.assets {
@@ -451,7 +603,7 @@ async fn search_boxes_impl(
                 "box_id IN (SELECT box_id FROM box_assets WHERE token_id IN ({}))",
                 placeholders.join(",")
             ));
-            _values.extend(assets.iter().cloned());
+            values.extend(assets.iter().cloned().map(Value::Text));
         }
     }
 
@@ -467,10 +619,9 @@ async fn search_boxes_impl(
 
     let count_sql = format!("SELECT COUNT(*) FROM boxes WHERE {}", where_clause);
 
-    // For simplicity, execute with no params for now (would need dynamic param handling)
     let total: i64 = state
         .db
-        .query_one(&count_sql, [], |row| row.get(0))
+        .query_one(&count_sql, params_from_iter(values.iter()), |row| row.get(0))
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .unwrap_or(0);
 
@@ -483,22 +634,43 @@ async fn search_boxes_impl(
         where_clause
     );
 
-    let boxes = state
+    values.push(Value::BigInt(params.limit));
+    values.push(Value::BigInt(params.offset));
+
+    let mut items = state
         .db
-        .query_all(&sql, params![params.limit, params.offset], |row| box_from_row(row))
+        .query_all(&sql, params_from_iter(values.iter()), box_from_row)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let mut items = Vec::new();
-    for output in boxes {
-        let enriched = enrich_box_with_assets(state, output)
+    attach_assets(&state.db, &mut items)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-        items.push(enriched);
-    }
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
-fn box_from_row(row: &duckdb::Row<'_>) -> Result<Output, duckdb::Error> {
+/// Key/value pairs of a `registers` or `constants` search object
+fn search_entries(
+    filter: &serde_json::Value,
+    field: &str,
+) -> Result<Vec<(String, String)>, (StatusCode, String)> {
+    let object = filter
+        .as_object()
+        .ok_or((StatusCode::BAD_REQUEST, format!("{} must be an object", field)))?;
+
+    object
+        .iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(s) => Ok((key.clone(), s.clone())),
+            serde_json::Value::Number(n) => Ok((key.clone(), n.to_string())),
+            _ => Err((
+                StatusCode::BAD_REQUEST,
+                format!("{}.{} must be a string", field, key),
+            )),
+        })
+        .collect()
+}
+
+pub(crate) fn box_from_row(row: &duckdb::Row<'_>) -> Result<Output, duckdb::Error> {
     Ok(Output {
         box_id: row.get(0)?,
         tx_id: row.get(1)?,
@@ -509,32 +681,10 @@ fn box_from_row(row: &duckdb::Row<'_>) -> Result<Output, duckdb::Error> {
         creation_height: row.get(6)?,
         settlement_height: row.get(7)?,
         additional_registers: row.get::<_, Option<String>>(8)?
-            .and_then(|s| serde_json::from_str(&s).ok()),
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .map(|registers| sigma::render_registers(&registers)),
         spent_tx_id: row.get(9)?,
-        assets: Vec::new(), // Will be populated by enrich_box_with_assets
+        assets: Vec::new(), // Will be populated by attach_assets
         main_chain: true,
     })
 }
-
-fn enrich_box_with_assets(state: &Arc<AppState>, mut output: Output) -> anyhow::Result<Output> {
-    let assets = state.db.query_all(
-        "SELECT ba.token_id, ba.amount, ba.asset_index, t.name, t.decimals
-         FROM box_assets ba
-         LEFT JOIN tokens t ON ba.token_id = t.token_id
-         WHERE ba.box_id = ?
-         ORDER BY ba.asset_index",
-        [&output.box_id],
-        |row| {
-            Ok(BoxAsset {
-                token_id: row.get(0)?,
-                amount: row.get(1)?,
-                index: row.get(2)?,
-                name: row.get(3)?,
-                decimals: row.get(4)?,
-            })
-        },
-    )?;
-
-    output.assets = assets;
-    Ok(output)
-}
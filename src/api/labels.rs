@@ -0,0 +1,190 @@
+//! Address labels: names for exchanges, mining pools and contracts, searchable via /search
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use duckdb::params;
+use std::sync::Arc;
+
+use crate::api::addresses::check_address;
+use crate::api::require_admin;
+use crate::models::{AddressLabel, LabelRequest, PaginatedResponse, Pagination};
+use crate::AppState;
+
+/// GET /api/v1/labels - List address labels
+#[utoipa::path(
+    get,
+    path = "/labels",
+    tag = "addresses",
+    params(
+        ("offset" = Option<i64>, Query, description = "Offset"),
+        ("limit" = Option<i64>, Query, description = "Limit (max 500)")
+    ),
+    responses(
+        (status = 200, description = "Address labels", body = PaginatedLabels)
+    )
+)]
+pub async fn get_labels(
+    State(state): State<Arc<AppState>>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<PaginatedResponse<AddressLabel>>, (StatusCode, String)> {
+    let limit = pagination.limit.min(500);
+
+    let items = state
+        .db
+        .query_all(
+            "SELECT address, label, category FROM address_labels
+             ORDER BY label, address
+             LIMIT ? OFFSET ?",
+            params![limit, pagination.offset],
+            |row| {
+                Ok(AddressLabel {
+                    address: row.get(0)?,
+                    label: row.get(1)?,
+                    category: row.get(2)?,
+                })
+            },
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let total: i64 = state
+        .db
+        .query_one("SELECT COUNT(*) FROM address_labels", [], |row| row.get(0))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or(0);
+
+    Ok(Json(PaginatedResponse::new(items, total)))
+}
+
+/// PUT /api/v1/labels/:address - Create or replace an address label
+#[utoipa::path(
+    put,
+    path = "/labels/{address}",
+    tag = "addresses",
+    params(
+        ("address" = String, Path, description = "Address to label"),
+        ("api_key" = String, Header, description = "ADMIN_API_KEY")
+    ),
+    request_body = LabelRequest,
+    responses(
+        (status = 200, description = "Stored label", body = AddressLabel),
+        (status = 400, description = "Invalid address or empty label"),
+        (status = 401, description = "Invalid api_key"),
+        (status = 403, description = "ADMIN_API_KEY is not configured")
+    )
+)]
+pub async fn put_label(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<LabelRequest>,
+) -> Result<Json<AddressLabel>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    check_address(&state, &address)?;
+    let label = request.label.trim().to_string();
+    if label.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Label must not be empty".to_string()));
+    }
+    let category = request
+        .category
+        .map(|c| c.trim().to_lowercase())
+        .filter(|c| !c.is_empty());
+    let search_text = format!("{} {}", label, category.as_deref().unwrap_or(""));
+    let now = chrono::Utc::now().timestamp();
+
+    state
+        .db
+        .execute_transaction(|conn| {
+            conn.execute(
+                "INSERT INTO address_labels (address, label, category, updated_at)
+                 VALUES (?, ?, ?, ?)
+                 ON CONFLICT (address) DO UPDATE SET
+                    label = EXCLUDED.label,
+                    category = EXCLUDED.category,
+                    updated_at = EXCLUDED.updated_at",
+                params![address, label, category, now],
+            )?;
+            conn.execute(
+                "INSERT INTO search_index (entity_type, entity_id, search_text)
+                 VALUES ('address', ?, ?)
+                 ON CONFLICT (entity_type, entity_id) DO UPDATE SET search_text = EXCLUDED.search_text",
+                params![address, search_text.trim()],
+            )?;
+            // Blocks carry the miner's name so lists don't need a join
+            conn.execute(
+                "UPDATE blocks SET miner_name = ? WHERE miner_address = ?",
+                params![label, address],
+            )?;
+            Ok(())
+        })
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    state
+        .db
+        .rebuild_search_index()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(AddressLabel {
+        address,
+        label,
+        category,
+    }))
+}
+
+/// DELETE /api/v1/labels/:address - Remove an address label
+#[utoipa::path(
+    delete,
+    path = "/labels/{address}",
+    tag = "addresses",
+    params(
+        ("address" = String, Path, description = "Labelled address"),
+        ("api_key" = String, Header, description = "ADMIN_API_KEY")
+    ),
+    responses(
+        (status = 200, description = "Label removed"),
+        (status = 401, description = "Invalid api_key"),
+        (status = 403, description = "ADMIN_API_KEY is not configured"),
+        (status = 404, description = "Address has no label")
+    )
+)]
+pub async fn delete_label(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let exists: Option<i32> = state
+        .db
+        .query_one(
+            "SELECT 1 FROM address_labels WHERE address = ?",
+            [&address],
+            |row| row.get(0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if exists.is_none() {
+        return Err((StatusCode::NOT_FOUND, "Label not found".to_string()));
+    }
+
+    state
+        .db
+        .execute_transaction(|conn| {
+            conn.execute("DELETE FROM address_labels WHERE address = ?", [&address])?;
+            conn.execute(
+                "DELETE FROM search_index WHERE entity_type = 'address' AND entity_id = ?",
+                [&address],
+            )?;
+            conn.execute(
+                "UPDATE blocks SET miner_name = NULL WHERE miner_address = ?",
+                [&address],
+            )?;
+            Ok(())
+        })
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(serde_json::json!({
+        "status": "deleted",
+        "address": address
+    })))
+}
@@ -3,8 +3,10 @@ use axum::{
     http::StatusCode,
     Json,
 };
+use duckdb::params;
 use std::sync::Arc;
 
+use crate::api::addresses::check_address;
 use crate::models::Pagination;
 use crate::AppState;
 
@@ -13,19 +15,23 @@ pub async fn get_mempool_transactions(
     State(state): State<Arc<AppState>>,
     Query(params): Query<Pagination>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let node = state
-        .sync_service
-        .get_primary_node()
-        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "No node available".to_string()))?;
-
-    let txs = node
-        .get_mempool_transactions(params.limit as i32, params.offset as i32)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let total: i64 = state
+        .db
+        .query_one("SELECT COUNT(*) FROM mempool_transactions", [], |row| row.get(0))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or(0);
+
+    let items = query_mempool_txs(
+        &state,
+        "SELECT tx_data FROM mempool_transactions
+         ORDER BY first_seen DESC
+         LIMIT ? OFFSET ?",
+        params![params.limit, params.offset],
+    )?;
 
     Ok(Json(serde_json::json!({
-        "items": txs,
-        "total": txs.len()
+        "items": items,
+        "total": total
     })))
 }
 
@@ -34,23 +40,16 @@ pub async fn get_mempool_transaction(
     State(state): State<Arc<AppState>>,
     Path(tx_id): Path<String>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let node = state
-        .sync_service
-        .get_primary_node()
-        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "No node available".to_string()))?;
-
-    // Get all mempool transactions and find the one we want
-    let txs = node
-        .get_mempool_transactions(1000, 0)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let tx = txs
-        .into_iter()
-        .find(|t| t.id == tx_id)
-        .ok_or((StatusCode::NOT_FOUND, "Transaction not found in mempool".to_string()))?;
-
-    Ok(Json(serde_json::to_value(tx).unwrap()))
+    let tx = query_mempool_txs(
+        &state,
+        "SELECT tx_data FROM mempool_transactions WHERE tx_id = ?",
+        [&tx_id],
+    )?
+    .into_iter()
+    .next()
+    .ok_or((StatusCode::NOT_FOUND, "Transaction not found in mempool".to_string()))?;
+
+    Ok(Json(tx))
 }
 
 /// GET /api/v1/mempool/transactions/byAddress/:address - Get mempool transactions for address
@@ -58,35 +57,22 @@ pub async fn get_mempool_by_address(
     State(state): State<Arc<AppState>>,
     Path(address): Path<String>,
 ) -> Result<Json<Vec<serde_json::Value>>, (StatusCode, String)> {
-    let node = state
-        .sync_service
-        .get_primary_node()
-        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "No node available".to_string()))?;
-
-    let txs = node
-        .get_mempool_transactions(1000, 0)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    // Filter transactions that involve the address
-    // This is a simplified check - a full implementation would derive addresses from ErgoTrees
-    let filtered: Vec<serde_json::Value> = txs
-        .into_iter()
-        .filter(|tx| {
-            // Check if any output goes to this address
-            tx.outputs.iter().any(|o| {
-                // Simple check: if the ergo_tree contains patterns that might match
-                // A full implementation would properly derive the address
-                o.ergo_tree.contains(&address) ||
-                crate::utils::ergo_tree::ergo_tree_to_address(&o.ergo_tree)
-                    .map(|a| a == address)
-                    .unwrap_or(false)
-            })
-        })
-        .map(|tx| serde_json::to_value(tx).unwrap())
-        .collect();
-
-    Ok(Json(filtered))
+    check_address(&state, &address)?;
+
+    // Transactions paying to the address or spending its boxes
+    let txs = query_mempool_txs(
+        &state,
+        "SELECT tx_data FROM mempool_transactions
+         WHERE tx_id IN (
+             SELECT tx_id FROM mempool_outputs WHERE address = ?
+             UNION
+             SELECT tx_id FROM mempool_inputs WHERE address = ?
+         )
+         ORDER BY first_seen DESC",
+        params![address, address],
+    )?;
+
+    Ok(Json(txs))
 }
 
 /// GET /api/v1/mempool/transactions/byErgoTree/:ergoTree - Get mempool transactions for ErgoTree
@@ -94,38 +80,43 @@ pub async fn get_mempool_by_ergo_tree(
     State(state): State<Arc<AppState>>,
     Path(ergo_tree): Path<String>,
 ) -> Result<Json<Vec<serde_json::Value>>, (StatusCode, String)> {
-    let node = state
-        .sync_service
-        .get_primary_node()
-        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "No node available".to_string()))?;
-
-    let txs = node
-        .get_mempool_transactions(1000, 0)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let filtered: Vec<serde_json::Value> = txs
-        .into_iter()
-        .filter(|tx| tx.outputs.iter().any(|o| o.ergo_tree == ergo_tree))
-        .map(|tx| serde_json::to_value(tx).unwrap())
-        .collect();
-
-    Ok(Json(filtered))
+    let txs = query_mempool_txs(
+        &state,
+        "SELECT tx_data FROM mempool_transactions
+         WHERE tx_id IN (SELECT tx_id FROM mempool_outputs WHERE ergo_tree = ?)
+         ORDER BY first_seen DESC",
+        [&ergo_tree],
+    )?;
+
+    Ok(Json(txs))
 }
 
 /// GET /api/v1/mempool/size - Get mempool size
 pub async fn get_mempool_size(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let node = state
-        .sync_service
-        .get_primary_node()
-        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "No node available".to_string()))?;
+    let size: i64 = state
+        .db
+        .query_one("SELECT COUNT(*) FROM mempool_transactions", [], |row| row.get(0))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or(0);
 
-    let size = node
-        .get_mempool_size()
-        .await
+    Ok(Json(serde_json::json!({ "size": size })))
+}
+
+/// Run a query selecting `tx_data` and parse each row back into JSON
+fn query_mempool_txs<P: duckdb::Params>(
+    state: &Arc<AppState>,
+    sql: &str,
+    params: P,
+) -> Result<Vec<serde_json::Value>, (StatusCode, String)> {
+    let rows: Vec<String> = state
+        .db
+        .query_all(sql, params, |row| row.get(0))
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(serde_json::json!({ "size": size })))
+    Ok(rows
+        .iter()
+        .filter_map(|data| serde_json::from_str(data).ok())
+        .collect())
 }
@@ -1,6 +1,8 @@
 pub mod addresses;
 pub mod blocks;
 pub mod boxes;
+pub mod labels;
+pub mod loaders;
 pub mod mempool;
 pub mod search;
 pub mod stats;
@@ -9,15 +11,76 @@ pub mod swagger;
 pub mod tokens;
 pub mod transactions;
 pub mod wallet;
+pub mod watches;
+pub mod ws;
 
 use axum::{
-    routing::{get, post},
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
     Router,
 };
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::db::{QueryDeadline, QUERY_DEADLINE};
 use crate::AppState;
 
+/// Serve a request on the blocking thread pool, since handlers query DuckDB
+/// synchronously. With `QUERY_TIMEOUT` set, reads running past it are cancelled
+/// and the request is answered with 503.
+pub async fn blocking_reads(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let deadline = QueryDeadline::new(Duration::from_secs(state.config.query_timeout));
+    let scoped = deadline.clone();
+    let enforce = state.config.query_timeout > 0;
+    let runtime = tokio::runtime::Handle::current();
+
+    let result = tokio::task::spawn_blocking(move || {
+        if enforce {
+            runtime.block_on(QUERY_DEADLINE.scope(scoped, next.run(request)))
+        } else {
+            runtime.block_on(next.run(request))
+        }
+    })
+    .await;
+
+    match result {
+        Ok(_) if deadline.timed_out() => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("Query exceeded the {}s timeout", state.config.query_timeout),
+        )
+            .into_response(),
+        Ok(response) => response,
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Endpoints that change shared state or expose every subscriber take the
+/// ADMIN_API_KEY in an `api_key` header. Without a configured key they are off.
+pub(crate) fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<(), (StatusCode, String)> {
+    let Some(expected) = state.config.admin_api_key.as_deref().filter(|k| !k.is_empty()) else {
+        return Err((StatusCode::FORBIDDEN, "ADMIN_API_KEY is not configured".to_string()));
+    };
+    let given = headers
+        .get("api_key")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+
+    // Compare without short-circuiting on the first differing byte
+    let matches = given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0;
+    if !matches {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid api_key".to_string()));
+    }
+    Ok(())
+}
+
 /// Build the API v1 router with all endpoints
 pub fn routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
     Router::new()
@@ -37,10 +100,16 @@ pub fn routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/transactions/byInputsScriptTemplateHash/:hash", get(transactions::get_transactions_by_template))
         .route("/transactions/byGlobalIndex/stream", get(transactions::stream_transactions_by_gix))
         .route("/transactions/submit", post(transactions::submit_transaction))
+        .route("/transactions/feeEstimate", get(stats::get_fee_estimate))
         // Addresses
+        .route("/addresses/richList", get(addresses::get_rich_list))
         .route("/addresses/:address", get(addresses::get_address))
         .route("/addresses/:address/balance/total", get(addresses::get_balance_total))
         .route("/addresses/:address/balance/confirmed", get(addresses::get_balance_confirmed))
+        .route("/addresses/:address/balance/atHeight/:height", get(addresses::get_balance_at_height))
+        .route("/addresses/:address/balance/history", get(addresses::get_balance_history))
+        .route("/addresses/:address/summary", get(addresses::get_address_summary))
+        .route("/addresses/:address/counterparties", get(addresses::get_address_counterparties))
         .route("/addresses/:address/transactions", get(addresses::get_address_transactions))
         // Boxes
         .route("/boxes/:boxId", get(boxes::get_box))
@@ -68,6 +137,7 @@ pub fn routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/tokens/:tokenId", get(tokens::get_token))
         .route("/tokens/search", get(tokens::search_tokens))
         .route("/tokens/:tokenId/holders", get(tokens::get_token_holders))
+        .route("/tokens/:tokenId/burns", get(tokens::get_token_burns))
         .route("/tokens/byAddress/:address", get(tokens::get_tokens_by_address))
         // Assets (alias for tokens by address)
         .route("/assets/byAddress/:address", get(tokens::get_tokens_by_address))
@@ -81,15 +151,35 @@ pub fn routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/stats", get(stats::get_stats))
         .route("/stats/network", get(stats::get_network_stats))
         .route("/stats/tables", get(stats::get_table_sizes))
+        .route("/stats/fees", get(stats::get_fee_stats))
         // Epochs
         .route("/epochs", get(stats::get_epochs))
         .route("/epochs/:epochIndex", get(stats::get_epoch))
+        // Webhooks
+        .route("/watches", get(watches::get_watches).post(watches::create_watch))
+        .route("/watches/:watchId", get(watches::get_watch).delete(watches::delete_watch))
+        .route("/watches/:watchId/deliveries", get(watches::get_deliveries))
+        .route("/watches/deliveries/:deliveryId/retry", post(watches::retry_delivery))
         // Search
         .route("/search", get(search::search))
+        // Address labels
+        .route("/labels", get(labels::get_labels))
+        .route("/labels/:address", put(labels::put_label).delete(labels::delete_label))
         // ErgoTree utilities
         .route("/utils/ergoTreeToAddress/:ergoTree", get(search::ergo_tree_to_address))
+        .route("/utils/addressToErgoTree/:address", get(search::address_to_ergo_tree))
+        .route("/utils/validateAddress/:address", get(search::validate_address))
+        .route(
+            "/utils/ergoTree/decompile",
+            get(search::decompile_ergo_tree).post(search::decompile_ergo_tree_post),
+        )
         // Admin / repair
         .route("/repair/assets", post(status::repair_assets))
+        .route("/repair/templateHashes", post(status::repair_template_hashes))
+        .route("/repair/registers", post(status::repair_box_registers))
+        .route("/repair/tokenHolders", post(status::repair_token_holders))
+        .route("/repair/addressBalances", post(status::repair_address_balances))
+        .route("/repair/emission", post(status::repair_block_emission))
         // Wallet (proxied to node)
         .route("/wallet/status", get(wallet::get_status))
         .route("/wallet/addresses", get(wallet::get_addresses))
@@ -98,4 +188,42 @@ pub fn routes(state: Arc<AppState>) -> Router<Arc<AppState>> {
         .route("/wallet/lock", post(wallet::lock))
         .route("/wallet/transaction/generate", post(wallet::generate_transaction))
         .route("/wallet/transaction/send", post(wallet::send_transaction))
+        .route_layer(middleware::from_fn_with_state(state, blocking_reads))
+        // Live events, registered after the layer since the socket outlives the request
+        .route("/ws", get(ws::ws_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::test_fixtures::{app_state, indexed_db, SLOW_READ};
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    async fn slow_read(State(state): State<Arc<AppState>>) -> Result<(), (StatusCode, String)> {
+        state
+            .db
+            .query_one(SLOW_READ, [], |row| row.get::<_, i64>(0))
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_request_past_timeout_is_answered_with_503() {
+        let state = app_state(indexed_db(), &["--query-timeout", "1"]);
+        let app = Router::new()
+            .route("/slow", get(slow_read))
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                blocking_reads,
+            ))
+            .with_state(state);
+
+        let request = axum::http::Request::builder()
+            .uri("/slow")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
 }
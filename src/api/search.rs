@@ -3,40 +3,59 @@ use axum::{
     http::StatusCode,
     Json,
 };
-use duckdb::params;
+use duckdb::{params_from_iter, types::Value};
 use serde::Deserialize;
 use std::sync::Arc;
 
-use crate::models::{AddressInfo, Balance, BlockSummary, SearchResult, TokenSummary, TransactionSummary};
-use crate::utils::ergo_tree;
+use crate::api::addresses::check_address;
+use crate::models::{
+    AddressInfo, Balance, BlockSummary, PaginatedResponse, SearchResult, TokenSummary,
+    TransactionSummary,
+};
+use crate::utils::ergo_tree::{self, decompile};
 use crate::AppState;
 
 #[derive(Deserialize)]
 pub struct SearchQuery {
     #[serde(rename = "query")]
     pub q: String,
+    #[serde(default)]
+    pub offset: i64,
+    #[serde(default = "default_search_limit")]
+    pub limit: i64,
+}
+
+fn default_search_limit() -> i64 {
+    20
 }
 
 /// GET /api/v1/search - Universal search
+///
+/// Exact matches on heights and ids come first; token names and descriptions
+/// and address labels follow, ranked by BM25 relevance plus a prefix bonus.
 #[utoipa::path(
     get,
     path = "/search",
     tag = "search",
     params(
-        ("query" = String, Query, description = "Search query (block height, ID, tx ID, address, token name)")
+        ("query" = String, Query, description = "Search query (block height, ID, tx ID, address, token name, address label)"),
+        ("offset" = Option<i64>, Query, description = "Offset"),
+        ("limit" = Option<i64>, Query, description = "Limit (max 100)")
     ),
     responses(
-        (status = 200, description = "Search results", body = Vec<SearchResult>)
+        (status = 200, description = "Ranked search results", body = PaginatedSearchResults)
     )
 )]
 pub async fn search(
     State(state): State<Arc<AppState>>,
     Query(params): Query<SearchQuery>,
-) -> Result<Json<Vec<SearchResult>>, (StatusCode, String)> {
+) -> Result<Json<PaginatedResponse<SearchResult>>, (StatusCode, String)> {
     let query = params.q.trim();
+    let offset = params.offset.max(0);
+    let limit = params.limit.clamp(1, 100);
 
     if query.is_empty() {
-        return Ok(Json(Vec::new()));
+        return Ok(Json(PaginatedResponse::new(Vec::new(), 0)));
     }
 
     let mut results = Vec::new();
@@ -51,6 +70,8 @@ pub async fn search(
                 transaction: None,
                 address: None,
                 token: None,
+                label: None,
+                score: None,
             });
         }
     }
@@ -65,6 +86,8 @@ pub async fn search(
                 transaction: None,
                 address: None,
                 token: None,
+                label: None,
+                score: None,
             });
         }
 
@@ -77,6 +100,8 @@ pub async fn search(
                 transaction: Some(tx),
                 address: None,
                 token: None,
+                label: None,
+                score: None,
             });
         }
 
@@ -89,6 +114,8 @@ pub async fn search(
                 transaction: None,
                 address: None,
                 token: Some(token),
+                label: None,
+                score: None,
             });
         }
 
@@ -103,48 +130,77 @@ pub async fn search(
                     transaction: Some(tx),
                     address: None,
                     token: None,
+                    label: None,
+                    score: None,
                 });
             }
         }
     }
 
-    // Try to match as address (starts with 9 for mainnet P2PK)
-    if query.starts_with('9') || query.starts_with('2') || query.starts_with('3') {
-        if ergo_tree::validate_address(query) {
-            if let Some(addr_info) = find_address(&state, query).await? {
-                results.push(SearchResult {
-                    entity_type: "address".to_string(),
-                    entity_id: query.to_string(),
-                    block: None,
-                    transaction: None,
-                    address: Some(addr_info),
-                    token: None,
-                });
-            }
+    // Try to match as an address of the indexed network
+    if check_address(&state, query).is_ok() {
+        if let Some(addr_info) = find_address(&state, query).await? {
+            results.push(SearchResult {
+                entity_type: "address".to_string(),
+                entity_id: query.to_string(),
+                block: None,
+                transaction: None,
+                address: Some(addr_info),
+                token: None,
+                label: find_label(&state, query).await?,
+                score: None,
+            });
         }
     }
 
-    // Search tokens by name
-    let tokens = search_tokens_by_name(&state, query, 5).await?;
-    for token in tokens {
-        results.push(SearchResult {
-            entity_type: "token".to_string(),
-            entity_id: token.id.clone(),
-            block: None,
-            transaction: None,
-            address: None,
-            token: Some(token),
-        });
+    // Exact matches occupy the first positions; text matches page after them
+    let exact_count = results.len() as i64;
+    let mut items: Vec<SearchResult> = results
+        .into_iter()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .collect();
+
+    let text_offset = (offset - exact_count).max(0);
+    let text_limit = limit - items.len() as i64;
+    let (hits, text_total) = search_documents(&state, query, text_offset, text_limit).await?;
+
+    for (entity_type, entity_id, score) in hits {
+        let result = match entity_type.as_str() {
+            "token" => find_token_by_id(&state, &entity_id).await?.map(|token| SearchResult {
+                entity_type: entity_type.clone(),
+                entity_id: entity_id.clone(),
+                block: None,
+                transaction: None,
+                address: None,
+                token: Some(token),
+                label: None,
+                score: Some(score),
+            }),
+            "address" => Some(SearchResult {
+                entity_type: entity_type.clone(),
+                entity_id: entity_id.clone(),
+                block: None,
+                transaction: None,
+                address: find_address(&state, &entity_id).await?,
+                token: None,
+                label: find_label(&state, &entity_id).await?,
+                score: Some(score),
+            }),
+            _ => None,
+        };
+        items.extend(result);
     }
 
-    Ok(Json(results))
+    Ok(Json(PaginatedResponse::new(items, exact_count + text_total)))
 }
 
 /// GET /api/v1/utils/ergoTreeToAddress/:ergoTree - Convert ErgoTree to address
 pub async fn ergo_tree_to_address(
+    State(state): State<Arc<AppState>>,
     Path(ergo_tree_hex): Path<String>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    let address = ergo_tree::ergo_tree_to_address(&ergo_tree_hex)
+    let address = ergo_tree::ergo_tree_to_address(&ergo_tree_hex, state.config.network)
         .ok_or((StatusCode::BAD_REQUEST, "Invalid ErgoTree".to_string()))?;
 
     Ok(Json(serde_json::json!({
@@ -152,6 +208,116 @@ pub async fn ergo_tree_to_address(
     })))
 }
 
+/// GET /api/v1/utils/addressToErgoTree/:address - ErgoTree of an address, including P2SH
+#[utoipa::path(
+    get,
+    path = "/utils/addressToErgoTree/{address}",
+    tag = "search",
+    params(
+        ("address" = String, Path, description = "Ergo address")
+    ),
+    responses(
+        (status = 200, description = "ErgoTree hex"),
+        (status = 400, description = "Invalid address")
+    )
+)]
+pub async fn address_to_ergo_tree(
+    Path(address): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let ergo_tree = ergo_tree::address_to_ergo_tree(&address)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid address: {}", e)))?;
+
+    Ok(Json(serde_json::json!({
+        "address": address,
+        "ergoTree": ergo_tree
+    })))
+}
+
+/// GET /api/v1/utils/validateAddress/:address - Check base58, checksum, prefix and network
+#[utoipa::path(
+    get,
+    path = "/utils/validateAddress/{address}",
+    tag = "search",
+    params(
+        ("address" = String, Path, description = "Ergo address")
+    ),
+    responses(
+        (status = 200, description = "Validity, network and type of the address, or the reason it is invalid")
+    )
+)]
+pub async fn validate_address(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+) -> Json<serde_json::Value> {
+    let decoded = ergo_tree::decode_address(&address).ok();
+    let error = check_address(&state, &address).err().map(|(_, e)| e);
+
+    Json(serde_json::json!({
+        "address": address,
+        "isValid": error.is_none(),
+        "network": decoded.as_ref().map(|d| d.network.as_str()),
+        "addressType": decoded.as_ref().map(|d| d.address_type.as_str()),
+        "error": error
+    }))
+}
+
+/// An ErgoTree lives in a box, and boxes are at most 4096 bytes
+const MAX_DECOMPILE_HEX_LEN: usize = 2 * 4096;
+
+#[derive(Deserialize)]
+pub struct DecompileRequest {
+    #[serde(rename = "ergoTree")]
+    pub ergo_tree: String,
+}
+
+/// GET /api/v1/utils/ergoTree/decompile?ergoTree=... - Decompile an ErgoTree
+///
+/// Returns the header, the segregated constants, the expression tree as JSON
+/// and an ErgoScript-like rendering of it.
+#[utoipa::path(
+    get,
+    path = "/utils/ergoTree/decompile",
+    tag = "search",
+    params(
+        ("ergoTree" = String, Query, description = "ErgoTree hex")
+    ),
+    responses(
+        (status = 200, description = "Header, constants, expression tree and pseudocode"),
+        (status = 400, description = "ErgoTree could not be deserialized or is longer than a box")
+    )
+)]
+pub async fn decompile_ergo_tree(
+    State(state): State<Arc<AppState>>,
+    Query(request): Query<DecompileRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    decompile_response(&state, &request.ergo_tree)
+}
+
+/// POST /api/v1/utils/ergoTree/decompile - Decompile an ErgoTree sent as `{"ergoTree": "..."}`,
+/// for trees too long for a query string
+pub async fn decompile_ergo_tree_post(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<DecompileRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    decompile_response(&state, &request.ergo_tree)
+}
+
+fn decompile_response(
+    state: &AppState,
+    ergo_tree: &str,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let ergo_tree = ergo_tree.trim();
+    if ergo_tree.len() > MAX_DECOMPILE_HEX_LEN {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("ErgoTree exceeds {} hex characters", MAX_DECOMPILE_HEX_LEN),
+        ));
+    }
+    decompile::decompile_hex(ergo_tree, state.config.network)
+        .map(|tree| Json(tree.to_json()))
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))
+}
+
 // Helper functions
 
 async fn find_block_by_height(
@@ -276,32 +442,21 @@ async fn find_address(
     let stats = state
         .db
         .query_one(
-            "SELECT tx_count, first_seen_height, last_seen_height
+            "SELECT tx_count, balance, first_seen_height, last_seen_height
              FROM address_stats WHERE address = ?",
             [address],
             |row| {
                 Ok((
                     row.get::<_, i64>(0)?,
-                    row.get::<_, Option<i64>>(1)?,
+                    row.get::<_, i64>(1)?,
                     row.get::<_, Option<i64>>(2)?,
+                    row.get::<_, Option<i64>>(3)?,
                 ))
             },
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    if let Some((tx_count, first_seen, last_seen)) = stats {
-        // Get balance
-        let nano_ergs: i64 = state
-            .db
-            .query_one(
-                "SELECT COALESCE(SUM(value), 0) FROM boxes
-                 WHERE address = ? AND spent_tx_id IS NULL",
-                [address],
-                |row| row.get(0),
-            )
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-            .unwrap_or(0);
-
+    if let Some((tx_count, nano_ergs, first_seen, last_seen)) = stats {
         return Ok(Some(AddressInfo {
             address: address.to_string(),
             tx_count,
@@ -351,30 +506,102 @@ async fn find_address(
     Ok(None)
 }
 
-async fn search_tokens_by_name(
+async fn find_label(
+    state: &Arc<AppState>,
+    address: &str,
+) -> Result<Option<String>, (StatusCode, String)> {
+    state
+        .db
+        .query_one(
+            "SELECT label FROM address_labels WHERE address = ?",
+            [address],
+            |row| row.get(0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Escape LIKE wildcards so user input only ever matches literally
+fn like_escape(s: &str) -> String {
+    s.to_lowercase()
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Rank search_index documents against the query. BM25 relevance comes from the
+/// fts extension when it is loaded; on top of that a document whose text starts
+/// with the query gets a full bonus, one with a word starting with it half, and
+/// a plain substring match a small one (the only signal without fts).
+/// Returns one page of (entity_type, entity_id, score) and the total hit count.
+async fn search_documents(
     state: &Arc<AppState>,
     query: &str,
+    offset: i64,
     limit: i64,
-) -> Result<Vec<TokenSummary>, (StatusCode, String)> {
-    let pattern = format!("%{}%", query);
+) -> Result<(Vec<(String, String, f64)>, i64), (StatusCode, String)> {
+    let fts = state.db.fts_enabled();
+    let escaped = like_escape(query);
 
-    state
+    let mut bind: Vec<Value> = Vec::new();
+    if fts {
+        bind.push(Value::Text(query.to_string()));
+    }
+    bind.push(Value::Text(format!("{}%", escaped)));
+    bind.push(Value::Text(format!("% {}%", escaped)));
+    bind.push(Value::Text(format!("%{}%", escaped)));
+
+    let hits_cte = format!(
+        r"WITH hits AS (
+            SELECT entity_type, entity_id,
+                   {} AS bm25,
+                   CASE WHEN lower(search_text) LIKE ? ESCAPE '\' THEN 1.0
+                        WHEN lower(search_text) LIKE ? ESCAPE '\' THEN 0.5
+                        WHEN lower(search_text) LIKE ? ESCAPE '\' THEN 0.1
+                        ELSE 0.0 END AS prefix
+            FROM search_index
+        )",
+        if fts {
+            "fts_main_search_index.match_bm25(doc_id, ?)"
+        } else {
+            "CAST(NULL AS DOUBLE)"
+        }
+    );
+
+    let total: i64 = state
+        .db
+        .query_one(
+            &format!(
+                "{} SELECT COUNT(*) FROM hits WHERE bm25 IS NOT NULL OR prefix > 0",
+                hits_cte
+            ),
+            params_from_iter(bind.iter()),
+            |row| row.get(0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or(0);
+
+    if limit <= 0 {
+        return Ok((Vec::new(), total));
+    }
+
+    bind.push(Value::BigInt(limit));
+    bind.push(Value::BigInt(offset));
+
+    let hits = state
         .db
         .query_all(
-            "SELECT token_id, name, decimals, emission_amount
-             FROM tokens
-             WHERE name LIKE ?
-             ORDER BY emission_amount DESC
-             LIMIT ?",
-            params![pattern, limit],
-            |row| {
-                Ok(TokenSummary {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    decimals: row.get(2)?,
-                    emission_amount: row.get(3)?,
-                })
-            },
+            &format!(
+                "{} SELECT entity_type, entity_id, COALESCE(bm25, 0) + prefix AS score
+                 FROM hits
+                 WHERE bm25 IS NOT NULL OR prefix > 0
+                 ORDER BY score DESC, entity_type, entity_id
+                 LIMIT ? OFFSET ?",
+                hits_cte
+            ),
+            params_from_iter(bind.iter()),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         )
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok((hits, total))
 }
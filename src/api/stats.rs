@@ -4,9 +4,15 @@ use axum::{
     Json,
 };
 use duckdb::params;
+use serde::Deserialize;
 use std::sync::Arc;
 
-use crate::models::{ApiInfo, Epoch, NetworkStats, PaginatedResponse, Pagination, TableSize};
+use crate::models::{
+    ApiInfo, BlockFees, Epoch, FeeBucket, FeeEstimate, FeePercentiles, FeeStats, NetworkStats, PaginatedResponse,
+    Pagination, TableSize,
+};
+use crate::utils::emission::EmissionRules;
+use crate::utils::ergo_tree;
 use crate::AppState;
 
 /// GET /api/v1/info - Get API info
@@ -25,12 +31,63 @@ pub async fn get_info(
 
     Ok(Json(ApiInfo {
         version: env!("CARGO_PKG_VERSION").to_string(),
-        network: state.config.network.clone(),
+        network: state.config.network.to_string(),
         indexed_height: sync_status.local_height,
         node_height: sync_status.node_height,
     }))
 }
 
+/// Supply after the indexed tip and network averages over the recent block window
+struct ChainStats {
+    difficulty: i64,
+    hashrate: f64,
+    block_time_avg: Option<f64>,
+    supply: i64,
+    circulating_supply: i64,
+}
+
+fn chain_stats(state: &AppState) -> Result<ChainStats, (StatusCode, String)> {
+    let internal = |e: anyhow::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+
+    let tip = state.db.get_sync_height().map_err(internal)?;
+    let difficulty = state
+        .db
+        .query_one(
+            "SELECT difficulty FROM blocks WHERE height = ? AND main_chain = TRUE",
+            [tip],
+            |row| row.get(0),
+        )
+        .map_err(internal)?
+        .unwrap_or(0);
+    let window = state.db.get_block_window_stats(tip).map_err(internal)?;
+
+    // block_emission is filled during sync; until the startup backfill reaches the tip
+    // the same figures come straight from the emission rules
+    let rules = EmissionRules::for_network(state.config.network);
+    let (supply, circulating_supply) = state
+        .db
+        .query_one(
+            "SELECT supply, circulating_supply FROM block_emission WHERE height = ?",
+            [tip],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(internal)?
+        .unwrap_or_else(|| {
+            (
+                rules.issued_coins_after_height(tip),
+                rules.circulating_supply_after_height(tip),
+            )
+        });
+
+    Ok(ChainStats {
+        difficulty,
+        hashrate: window.map_or(0.0, |w| w.hashrate),
+        block_time_avg: window.map(|w| w.block_time_avg),
+        supply,
+        circulating_supply,
+    })
+}
+
 /// GET /api/v1/stats - Get explorer statistics
 #[utoipa::path(
     get,
@@ -49,28 +106,7 @@ pub async fn get_stats(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     let sync_status = state.sync_service.get_status().await;
-
-    // Get latest network stats
-    let network = state
-        .db
-        .query_one(
-            "SELECT difficulty, hashrate, block_time_avg, total_coins
-             FROM network_stats
-             ORDER BY timestamp DESC
-             LIMIT 1",
-            [],
-            |row| {
-                Ok((
-                    row.get::<_, i64>(0)?,
-                    row.get::<_, f64>(1)?,
-                    row.get::<_, Option<f64>>(2)?,
-                    row.get::<_, i64>(3)?,
-                ))
-            },
-        )
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let (difficulty, hashrate, block_time, total_coins) = network.unwrap_or((0, 0.0, None, 0));
+    let chain = chain_stats(&state)?;
 
     Ok(Json(serde_json::json!({
         "blockCount": db_stats.block_count,
@@ -82,11 +118,11 @@ pub async fn get_stats(
         "indexedHeight": sync_status.local_height,
         "nodeHeight": sync_status.node_height,
         "syncProgress": sync_status.sync_progress,
-        "difficulty": difficulty,
-        "hashrate": hashrate,
-        "blockTimeAvg": block_time,
-        "totalCoins": total_coins,
-        "circulatingSupply": total_coins
+        "difficulty": chain.difficulty,
+        "hashrate": chain.hashrate,
+        "blockTimeAvg": chain.block_time_avg,
+        "totalCoins": chain.supply,
+        "circulatingSupply": chain.circulating_supply
     })))
 }
 
@@ -107,95 +143,63 @@ pub async fn get_network_stats(
         .get_stats()
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let network = state
-        .db
-        .query_one(
-            "SELECT difficulty, hashrate, block_time_avg, total_coins
-             FROM network_stats
-             ORDER BY timestamp DESC
-             LIMIT 1",
-            [],
-            |row| {
-                Ok((
-                    row.get::<_, i64>(0)?,
-                    row.get::<_, f64>(1)?,
-                    row.get::<_, Option<f64>>(2)?,
-                    row.get::<_, i64>(3)?,
-                ))
-            },
-        )
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let (difficulty, hashrate, block_time, total_coins) = network.unwrap_or((0, 0.0, None, 0));
+    let chain = chain_stats(&state)?;
 
     Ok(Json(NetworkStats {
         version: env!("CARGO_PKG_VERSION").to_string(),
-        supply: total_coins,
+        supply: chain.supply,
         transaction_count: db_stats.tx_count,
-        circulating_supply: total_coins,
+        circulating_supply: chain.circulating_supply,
         block_count: db_stats.block_count,
-        hash_rate: hashrate,
-        difficulty,
-        block_time_avg: block_time.unwrap_or(120.0),
+        hash_rate: chain.hashrate,
+        difficulty: chain.difficulty,
+        block_time_avg: chain.block_time_avg.unwrap_or(120.0),
     }))
 }
 
+const EPOCH_COLUMNS: &str = "epoch_index, height_start, height_end, timestamp_start, timestamp_end,
+    block_count, tx_count, total_fees, total_emission, avg_difficulty, avg_block_time";
+
+fn epoch_from_row(row: &duckdb::Row) -> duckdb::Result<Epoch> {
+    Ok(Epoch {
+        index: row.get(0)?,
+        height_start: row.get(1)?,
+        height_end: row.get(2)?,
+        timestamp_start: row.get(3)?,
+        timestamp_end: row.get(4)?,
+        block_count: row.get(5)?,
+        tx_count: row.get(6)?,
+        total_fees: row.get(7)?,
+        total_emission: row.get(8)?,
+        avg_difficulty: row.get(9)?,
+        avg_block_time: row.get(10)?,
+    })
+}
+
 /// GET /api/v1/epochs - Get epochs
 pub async fn get_epochs(
     State(state): State<Arc<AppState>>,
     Query(params): Query<Pagination>,
 ) -> Result<Json<PaginatedResponse<Epoch>>, (StatusCode, String)> {
-    // Calculate epochs based on block height (1024 blocks per epoch)
-    let epoch_length = 1024i64;
-    let current_height = state
+    let items = state
         .db
-        .get_sync_height()
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let total_epochs = (current_height / epoch_length) + 1;
-
-    let mut items = Vec::new();
-    let start_epoch = (params.offset as i64).max(0);
-    let end_epoch = ((params.offset + params.limit) as i64).min(total_epochs);
-
-    for epoch_idx in start_epoch..end_epoch {
-        let height_start = epoch_idx * epoch_length;
-        let height_end = ((epoch_idx + 1) * epoch_length - 1).min(current_height);
-
-        // Get timestamps for this epoch
-        let timestamps = state
-            .db
-            .query_one(
-                "SELECT MIN(timestamp), MAX(timestamp), COUNT(*)
-                 FROM blocks
-                 WHERE height >= ? AND height <= ? AND main_chain = TRUE",
-                params![height_start, height_end],
-                |row| {
-                    Ok((
-                        row.get::<_, Option<i64>>(0)?,
-                        row.get::<_, Option<i64>>(1)?,
-                        row.get::<_, i64>(2)?,
-                    ))
-                },
-            )
+        .query_all(
+            &format!(
+                "SELECT {} FROM epochs ORDER BY epoch_index LIMIT ? OFFSET ?",
+                EPOCH_COLUMNS
+            ),
+            params![params.limit.min(500), params.offset],
+            epoch_from_row,
+        )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-        if let Some((start_ts, end_ts, block_count)) = timestamps {
-            items.push(Epoch {
-                index: epoch_idx as i32,
-                height_start,
-                height_end,
-                timestamp_start: start_ts.unwrap_or(0),
-                timestamp_end: end_ts,
-                block_count: block_count as i32,
-            });
-        }
-    }
+    let total: i64 = state
+        .db
+        .query_one("SELECT COUNT(*) FROM epochs", [], |row| row.get(0))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or(0);
 
-    Ok(Json(PaginatedResponse {
-        items,
-        total: total_epochs,
-    }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
 /// GET /api/v1/epochs/:epochIndex - Get specific epoch
@@ -203,45 +207,16 @@ pub async fn get_epoch(
     State(state): State<Arc<AppState>>,
     Path(epoch_index): Path<i32>,
 ) -> Result<Json<Epoch>, (StatusCode, String)> {
-    let epoch_length = 1024i64;
-    let current_height = state
-        .db
-        .get_sync_height()
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    let height_start = epoch_index as i64 * epoch_length;
-    let height_end = ((epoch_index as i64 + 1) * epoch_length - 1).min(current_height);
-
-    if height_start > current_height {
-        return Err((StatusCode::NOT_FOUND, "Epoch not found".to_string()));
-    }
-
-    let timestamps = state
+    state
         .db
         .query_one(
-            "SELECT MIN(timestamp), MAX(timestamp), COUNT(*)
-             FROM blocks
-             WHERE height >= ? AND height <= ? AND main_chain = TRUE",
-            params![height_start, height_end],
-            |row| {
-                Ok((
-                    row.get::<_, Option<i64>>(0)?,
-                    row.get::<_, Option<i64>>(1)?,
-                    row.get::<_, i64>(2)?,
-                ))
-            },
+            &format!("SELECT {} FROM epochs WHERE epoch_index = ?", EPOCH_COLUMNS),
+            [epoch_index],
+            epoch_from_row,
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .ok_or((StatusCode::NOT_FOUND, "Epoch not found".to_string()))?;
-
-    Ok(Json(Epoch {
-        index: epoch_index,
-        height_start,
-        height_end,
-        timestamp_start: timestamps.0.unwrap_or(0),
-        timestamp_end: timestamps.1,
-        block_count: timestamps.2 as i32,
-    }))
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Epoch not found".to_string()))
 }
 
 /// GET /api/v1/stats/tables - Get table sizes
@@ -295,3 +270,227 @@ pub async fn get_table_sizes(
 
     Ok(Json(result))
 }
+
+/// Aggregates over a `fee` column, read back by `fee_percentiles_from_row`
+const FEE_PERCENTILE_COLUMNS: &str = "COUNT(fee), MIN(fee), quantile_disc(fee, 0.25), quantile_disc(fee, 0.5),
+    quantile_disc(fee, 0.75), quantile_disc(fee, 0.9), MAX(fee), COALESCE(SUM(fee), 0)";
+
+fn fee_percentiles_from_row(row: &duckdb::Row, start: usize) -> duckdb::Result<FeePercentiles> {
+    Ok(FeePercentiles {
+        tx_count: row.get(start)?,
+        min: row.get(start + 1)?,
+        p25: row.get(start + 2)?,
+        median: row.get(start + 3)?,
+        p75: row.get(start + 4)?,
+        p90: row.get(start + 5)?,
+        max: row.get(start + 6)?,
+        total: row.get(start + 7)?,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeInterval {
+    #[default]
+    Hourly,
+    Daily,
+}
+
+impl FeeInterval {
+    fn as_str(&self) -> &'static str {
+        match self {
+            FeeInterval::Hourly => "hourly",
+            FeeInterval::Daily => "daily",
+        }
+    }
+
+    fn millis(&self) -> i64 {
+        match self {
+            FeeInterval::Hourly => 3_600_000,
+            FeeInterval::Daily => 86_400_000,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct FeeStatsQuery {
+    #[serde(default = "default_fee_blocks")]
+    pub blocks: i64,
+    #[serde(default)]
+    pub interval: FeeInterval,
+    #[serde(default = "default_fee_buckets")]
+    pub buckets: i64,
+}
+
+fn default_fee_blocks() -> i64 { 50 }
+fn default_fee_buckets() -> i64 { 24 }
+
+/// GET /api/v1/stats/fees - Fee percentiles per recent block and per time bucket
+#[utoipa::path(
+    get,
+    path = "/stats/fees",
+    tag = "stats",
+    params(
+        ("blocks" = Option<i64>, Query, description = "Recent blocks to report (default 50, max 1000)"),
+        ("interval" = Option<String>, Query, description = "hourly (default) or daily"),
+        ("buckets" = Option<i64>, Query, description = "Time buckets to report (default 24, max 720)")
+    ),
+    responses(
+        (status = 200, description = "Fee percentiles, newest first", body = FeeStats)
+    )
+)]
+pub async fn get_fee_stats(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<FeeStatsQuery>,
+) -> Result<Json<FeeStats>, (StatusCode, String)> {
+    let tip = state
+        .db
+        .get_sync_height()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let block_count = query.blocks.clamp(1, 1000);
+
+    let blocks = state
+        .db
+        .query_all(
+            &format!(
+                "SELECT b.height, b.timestamp, {}
+                 FROM blocks b
+                 LEFT JOIN transactions t ON t.block_id = b.block_id AND t.coinbase = FALSE
+                 WHERE b.main_chain = TRUE AND b.height > ?
+                 GROUP BY b.height, b.timestamp
+                 ORDER BY b.height DESC",
+                FEE_PERCENTILE_COLUMNS
+            ),
+            [tip - block_count],
+            |row| {
+                Ok(BlockFees {
+                    height: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    fees: fee_percentiles_from_row(row, 2)?,
+                })
+            },
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Buckets end at the indexed tip rather than the wall clock, so a node that is
+    // still catching up reports its most recent days
+    let bucket_ms = query.interval.millis();
+    let latest: i64 = state
+        .db
+        .query_one(
+            "SELECT COALESCE(MAX(timestamp), 0) FROM blocks WHERE main_chain = TRUE",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or(0);
+    let since = (latest / bucket_ms - (query.buckets.clamp(1, 720) - 1)) * bucket_ms;
+
+    let buckets = state
+        .db
+        .query_all(
+            &format!(
+                "SELECT (timestamp // ?) * ? AS bucket, {}
+                 FROM transactions
+                 WHERE coinbase = FALSE AND timestamp >= ?
+                 GROUP BY bucket
+                 ORDER BY bucket DESC",
+                FEE_PERCENTILE_COLUMNS
+            ),
+            params![bucket_ms, bucket_ms, since],
+            |row| {
+                Ok(FeeBucket {
+                    timestamp: row.get(0)?,
+                    fees: fee_percentiles_from_row(row, 1)?,
+                })
+            },
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(FeeStats {
+        blocks,
+        interval: query.interval.as_str().to_string(),
+        buckets,
+    }))
+}
+
+/// The node's default `minimalFeeAmount`, 0.001 ERG
+const MIN_FEE: i64 = 1_000_000;
+/// Recent blocks the fee estimate samples
+const ESTIMATE_BLOCKS: i64 = 30;
+
+/// GET /api/v1/transactions/feeEstimate - Suggested fees from recent blocks and the mempool
+///
+/// `slow` is the lower quartile of recently included fees, `standard` the median
+/// and `fast` the 90th percentile. When more transactions are waiting than a
+/// block usually takes, `standard` and `fast` also have to beat the median and
+/// 90th percentile of the mempool.
+#[utoipa::path(
+    get,
+    path = "/transactions/feeEstimate",
+    tag = "transactions",
+    responses(
+        (status = 200, description = "Suggested fees in nanoERG", body = FeeEstimate)
+    )
+)]
+pub async fn get_fee_estimate(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<FeeEstimate>, (StatusCode, String)> {
+    let tip = state
+        .db
+        .get_sync_height()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let recent = state
+        .db
+        .query_one(
+            &format!(
+                "SELECT {} FROM transactions WHERE coinbase = FALSE AND inclusion_height > ?",
+                FEE_PERCENTILE_COLUMNS
+            ),
+            [tip - ESTIMATE_BLOCKS],
+            |row| fee_percentiles_from_row(row, 0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or_default();
+
+    // Pending transactions without a fee output count with a zero fee
+    let mempool = state
+        .db
+        .query_one(
+            &format!(
+                "SELECT {} FROM (
+                     SELECT mt.tx_id, COALESCE(SUM(mo.value) FILTER (WHERE mo.ergo_tree = ?), 0) AS fee
+                     FROM mempool_transactions mt
+                     LEFT JOIN mempool_outputs mo ON mo.tx_id = mt.tx_id
+                     GROUP BY mt.tx_id
+                 )",
+                FEE_PERCENTILE_COLUMNS
+            ),
+            [ergo_tree::MINERS_FEE_ERGO_TREE],
+            |row| fee_percentiles_from_row(row, 0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or_default();
+
+    let blocks_sampled = ESTIMATE_BLOCKS.min(tip.max(0));
+    let avg_block_tx_count = recent.tx_count as f64 / blocks_sampled.max(1) as f64;
+    let congested = mempool.tx_count as f64 > avg_block_tx_count.max(1.0);
+    let pending = |fee: Option<i64>| if congested { fee.unwrap_or(0) } else { 0 };
+
+    let slow = recent.p25.unwrap_or(0).max(MIN_FEE);
+    let standard = slow.max(recent.median.unwrap_or(0)).max(pending(mempool.median));
+    let fast = standard.max(recent.p90.unwrap_or(0)).max(pending(mempool.p90));
+
+    Ok(Json(FeeEstimate {
+        slow,
+        standard,
+        fast,
+        min_fee: MIN_FEE,
+        blocks_sampled,
+        recent,
+        mempool,
+        avg_block_tx_count,
+        congested,
+    }))
+}
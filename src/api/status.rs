@@ -71,7 +71,7 @@ pub async fn get_status(
         version: env!("CARGO_PKG_VERSION").to_string(),
         uptime_seconds: start.elapsed().as_secs(),
         memory_usage_mb: get_memory_usage(),
-        network: state.config.network.clone(),
+        network: state.config.network.to_string(),
     };
 
     Ok(Json(FullStatus {
@@ -100,6 +100,96 @@ pub async fn repair_assets(
     })))
 }
 
+/// POST /repair/templateHashes - Recompute ErgoTree template hashes for stored boxes
+pub async fn repair_template_hashes(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let sync_service = state.sync_service.clone();
+
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = sync_service.repair_template_hashes() {
+            tracing::error!("Template hash repair failed: {}", e);
+        }
+    });
+
+    Ok(Json(serde_json::json!({
+        "status": "repair started",
+        "message": "Repair is running in background. Monitor progress via GET /status"
+    })))
+}
+
+/// POST /repair/registers - Rebuild decoded registers and script constants for stored boxes
+pub async fn repair_box_registers(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let sync_service = state.sync_service.clone();
+
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = sync_service.repair_box_registers() {
+            tracing::error!("Register repair failed: {}", e);
+        }
+    });
+
+    Ok(Json(serde_json::json!({
+        "status": "repair started",
+        "message": "Repair is running in background. Monitor progress via GET /status"
+    })))
+}
+
+/// POST /repair/tokenHolders - Rebuild the token_holders table from unspent boxes
+pub async fn repair_token_holders(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let sync_service = state.sync_service.clone();
+
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = sync_service.repair_token_holders() {
+            tracing::error!("Token holder repair failed: {}", e);
+        }
+    });
+
+    Ok(Json(serde_json::json!({
+        "status": "repair started",
+        "message": "Repair is running in background. Monitor progress via GET /status"
+    })))
+}
+
+/// POST /repair/addressBalances - Recompute address balances from unspent boxes
+pub async fn repair_address_balances(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let sync_service = state.sync_service.clone();
+
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = sync_service.repair_address_balances() {
+            tracing::error!("Address balance repair failed: {}", e);
+        }
+    });
+
+    Ok(Json(serde_json::json!({
+        "status": "repair started",
+        "message": "Repair is running in background. Monitor progress via GET /status"
+    })))
+}
+
+/// POST /repair/emission - Record the emission split for blocks that have none
+pub async fn repair_block_emission(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let sync_service = state.sync_service.clone();
+
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = sync_service.repair_block_emission() {
+            tracing::error!("Block emission repair failed: {}", e);
+        }
+    });
+
+    Ok(Json(serde_json::json!({
+        "status": "repair started",
+        "message": "Repair is running in background. Monitor progress via GET /status"
+    })))
+}
+
 fn get_memory_usage() -> Option<u64> {
     // Try to read from /proc/self/status on Linux
     #[cfg(target_os = "linux")]
@@ -5,7 +5,7 @@ use std::sync::Arc;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
-use crate::api::{addresses, blocks, boxes, search, stats, tokens, transactions, wallet};
+use crate::api::{addresses, blocks, boxes, labels, search, stats, tokens, transactions, wallet, watches};
 use crate::models::*;
 use crate::AppState;
 
@@ -30,9 +30,25 @@ This API provides full Explorer-compatible endpoints for querying the Ergo block
 
 ### Statistics
 - `GET /stats/tables` - Database table sizes and row counts
+- `GET /stats/fees` - Fee percentiles per recent block and per hour or day
+- `GET /transactions/feeEstimate` - Suggested slow/standard/fast fees from recent blocks and the mempool
+
+### Addresses
+- `GET /addresses/richList` - Addresses ranked by balance
+- `GET /addresses/{address}/balance/atHeight/{height}` - Confirmed balance as of a block height
+- `GET /addresses/{address}/balance/history?interval=daily|weekly` - Balance over time, per day or week
+- `GET /addresses/{address}/summary` - Received and sent totals, fees paid and first/last activity
+- `GET /addresses/{address}/counterparties` - Addresses this address paid or was paid by
+- `GET /labels` - Address labels (exchanges, pools, contracts)
+- `PUT /labels/{address}` - Create or replace an address label
+- `DELETE /labels/{address}` - Remove an address label
+
+### Search
+- `GET /search` - Ranked full-text search over token names, descriptions and address labels
 
 ### Tokens
 - `GET /tokens/{tokenId}/holders` - Token holder list with balances
+- `GET /tokens/{tokenId}/burns` - Transactions that burned the token
 
 ### Boxes (UTXOs)
 - `GET /boxes/unspent/all/byAddress/{address}` - Combined confirmed + mempool UTXOs
@@ -56,8 +72,19 @@ This API provides full Explorer-compatible endpoints for querying the Ergo block
 - `GET /boxes/unspent/byErgoTreeTemplateHash/{hash}/stream` - Stream unspent by template
 - `GET /transactions/byGlobalIndex/stream` - Stream transactions
 
+### Live Events
+- `GET /ws` - WebSocket subscriptions to new blocks, reorgs, address activity, token transfers, template-hash matches and mempool entries
+
+### Webhooks
+- `POST /watches` - Watch an address, ErgoTree template hash or token id and receive signed POSTs
+- `GET /watches/{watchId}/deliveries?status=dead` - Delivery log and dead letters
+- `POST /watches/deliveries/{deliveryId}/retry` - Requeue a dead letter
+
 ### Utilities
 - `GET /utils/ergoTreeToAddress/{ergoTree}` - Convert ErgoTree to address
+- `GET /utils/addressToErgoTree/{address}` - Convert an address (P2PK, P2SH or P2S) to its ErgoTree
+- `GET /utils/validateAddress/{address}` - Validate an address's checksum, prefix and network
+- `GET /utils/ergoTree/decompile?ergoTree=...` - Decompile an ErgoTree to pseudocode and an expression tree (also as POST with an `ergoTree` JSON field)
 
 ### Wallet Integration
 - `GET /wallet/status` - Wallet lock/unlock status
@@ -83,6 +110,7 @@ Most endpoints are public. Wallet endpoints require the node API key to be confi
         (name = "boxes", description = "Box (UTXO) operations"),
         (name = "tokens", description = "Token operations"),
         (name = "search", description = "Search functionality"),
+        (name = "webhooks", description = "Webhook watches and deliveries (🌟 EXCLUSIVE)"),
         (name = "wallet", description = "Node wallet operations (🌟 EXCLUSIVE)")
     ),
     paths(
@@ -91,6 +119,7 @@ Most endpoints are public. Wallet endpoints require the node API key to be confi
         stats::get_stats,
         stats::get_network_stats,
         stats::get_table_sizes,
+        stats::get_fee_stats,
         // Blocks
         blocks::get_blocks,
         blocks::get_block,
@@ -102,8 +131,17 @@ Most endpoints are public. Wallet endpoints require the node API key to be confi
         transactions::get_transaction,
         transactions::get_transactions_by_block,
         transactions::get_transactions_by_address,
+        stats::get_fee_estimate,
         // Addresses
         addresses::get_address,
+        addresses::get_rich_list,
+        addresses::get_balance_at_height,
+        addresses::get_balance_history,
+        addresses::get_address_summary,
+        addresses::get_address_counterparties,
+        labels::get_labels,
+        labels::put_label,
+        labels::delete_label,
         // Boxes
         boxes::get_box,
         boxes::get_boxes_by_address,
@@ -113,8 +151,19 @@ Most endpoints are public. Wallet endpoints require the node API key to be confi
         tokens::get_token,
         tokens::search_tokens,
         tokens::get_token_holders,
+        tokens::get_token_burns,
         // Search
         search::search,
+        search::address_to_ergo_tree,
+        search::validate_address,
+        search::decompile_ergo_tree,
+        // Webhooks
+        watches::get_watches,
+        watches::create_watch,
+        watches::get_watch,
+        watches::delete_watch,
+        watches::get_deliveries,
+        watches::retry_delivery,
         // Wallet
         wallet::get_status,
         wallet::get_addresses,
@@ -135,6 +184,11 @@ Most endpoints are public. Wallet endpoints require the node API key to be confi
             AddressInfo,
             Balance,
             TokenBalance,
+            HistoricalBalance,
+            BalanceHistory,
+            AddressSummary,
+            TokenFlow,
+            Counterparty,
             MempoolTransaction,
             NetworkStats,
             SearchResult,
@@ -146,13 +200,32 @@ Most endpoints are public. Wallet endpoints require the node API key to be confi
             WalletBalance,
             PaymentRequest,
             TableSize,
+            FeePercentiles,
+            BlockFees,
+            FeeBucket,
+            FeeStats,
+            FeeEstimate,
             tokens::TokenHolder,
-            tokens::PaginatedTokenHolders,
+            tokens::TokenHoldersResponse,
+            tokens::TokenDistribution,
             PaginatedBlocks,
             PaginatedTransactions,
             PaginatedOutputs,
             PaginatedTokens,
             PaginatedEpochs,
+            RichListEntry,
+            PaginatedRichList,
+            PaginatedCounterparties,
+            PaginatedSearchResults,
+            AddressLabel,
+            LabelRequest,
+            PaginatedLabels,
+            Watch,
+            WatchRequest,
+            WebhookDelivery,
+            PaginatedWebhookDeliveries,
+            TokenBurn,
+            PaginatedTokenBurns,
         )
     )
 )]
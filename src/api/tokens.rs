@@ -3,11 +3,13 @@ use axum::{
     http::StatusCode,
     Json,
 };
-use duckdb::params;
+use duckdb::{params, params_from_iter, types::Value};
 use serde::Deserialize;
 use std::sync::Arc;
 
-use crate::models::{PaginatedResponse, Pagination, Token, TokenBalance, TokenSummary};
+use crate::api::addresses::{check_address, token_balance_from_row};
+use crate::models::{PaginatedResponse, Pagination, Token, TokenBalance, TokenBurn, TokenSummary};
+use crate::utils::cursor;
 use crate::AppState;
 
 #[derive(Deserialize)]
@@ -64,7 +66,7 @@ pub async fn get_tokens(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
 /// GET /api/v1/tokens/:tokenId - Get token by ID
@@ -87,19 +89,32 @@ pub async fn get_token(
     let token = state
         .db
         .query_one(
-            "SELECT token_id, box_id, emission_amount, name, description, token_type, decimals, creation_height
-             FROM tokens WHERE token_id = ?",
+            "SELECT t.token_id, t.box_id, t.emission_amount, t.name, t.description, t.token_type,
+                    t.decimals, t.creation_height, t.asset_type, t.content_hash, t.content_url, t.cover_url,
+                    (SELECT COALESCE(SUM(amount), 0) FROM token_events
+                     WHERE token_id = t.token_id AND event_type = 'burn'),
+                    (SELECT COUNT(*) FROM token_holders WHERE token_id = t.token_id)
+             FROM tokens t WHERE t.token_id = ?",
             [&token_id],
             |row| {
+                let emission_amount: i64 = row.get(2)?;
+                let burned_amount: i64 = row.get(12)?;
                 Ok(Token {
                     id: row.get(0)?,
                     box_id: row.get(1)?,
-                    emission_amount: row.get(2)?,
+                    emission_amount,
                     name: row.get(3)?,
                     description: row.get(4)?,
                     token_type: row.get(5)?,
                     decimals: row.get(6)?,
                     creation_height: row.get(7)?,
+                    asset_type: row.get(8)?,
+                    content_hash: row.get(9)?,
+                    content_url: row.get(10)?,
+                    cover_url: row.get(11)?,
+                    burned_amount,
+                    circulating_supply: emission_amount - burned_amount,
+                    holder_count: row.get(13)?,
                 })
             },
         )
@@ -109,6 +124,59 @@ pub async fn get_token(
     Ok(Json(token))
 }
 
+/// GET /api/v1/tokens/:tokenId/burns - Transactions that burned the token, newest first
+#[utoipa::path(
+    get,
+    path = "/tokens/{tokenId}/burns",
+    tag = "tokens",
+    params(
+        ("tokenId" = String, Path, description = "Token ID"),
+        ("offset" = Option<i64>, Query, description = "Pagination offset"),
+        ("limit" = Option<i64>, Query, description = "Results per page")
+    ),
+    responses(
+        (status = 200, description = "Token burns", body = PaginatedTokenBurns)
+    )
+)]
+pub async fn get_token_burns(
+    State(state): State<Arc<AppState>>,
+    Path(token_id): Path<String>,
+    Query(params): Query<Pagination>,
+) -> Result<Json<PaginatedResponse<TokenBurn>>, (StatusCode, String)> {
+    let total: i64 = state
+        .db
+        .query_one(
+            "SELECT COUNT(*) FROM token_events WHERE token_id = ? AND event_type = 'burn'",
+            [&token_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or(0);
+
+    let items = state
+        .db
+        .query_all(
+            "SELECT e.tx_id, e.height, t.timestamp, e.amount
+             FROM token_events e
+             JOIN transactions t ON t.tx_id = e.tx_id
+             WHERE e.token_id = ? AND e.event_type = 'burn'
+             ORDER BY e.height DESC, e.tx_id
+             LIMIT ? OFFSET ?",
+            params![token_id, params.limit.min(500), params.offset],
+            |row| {
+                Ok(TokenBurn {
+                    tx_id: row.get(0)?,
+                    height: row.get(1)?,
+                    timestamp: row.get(2)?,
+                    amount: row.get(3)?,
+                })
+            },
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(PaginatedResponse::new(items, total)))
+}
+
 /// GET /api/v1/tokens/search - Search tokens by name
 #[utoipa::path(
     get,
@@ -159,7 +227,7 @@ pub async fn search_tokens(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
 /// GET /api/v1/tokens/:tokenId/holders - Get token holders
@@ -170,51 +238,120 @@ pub async fn search_tokens(
     params(
         ("tokenId" = String, Path, description = "Token ID"),
         ("offset" = Option<i64>, Query, description = "Pagination offset"),
-        ("limit" = Option<i64>, Query, description = "Results per page")
+        ("limit" = Option<i64>, Query, description = "Results per page"),
+        ("cursor" = Option<String>, Query, description = "nextCursor from the previous page; replaces offset")
     ),
     responses(
-        (status = 200, description = "Token holders", body = inline(PaginatedTokenHolders))
+        (status = 200, description = "Token holders with supply distribution", body = TokenHoldersResponse)
     )
 )]
 pub async fn get_token_holders(
     State(state): State<Arc<AppState>>,
     Path(token_id): Path<String>,
     Query(params): Query<Pagination>,
-) -> Result<Json<PaginatedResponse<TokenHolder>>, (StatusCode, String)> {
-    let total: i64 = state
+) -> Result<Json<TokenHoldersResponse>, (StatusCode, String)> {
+    let (total, circulating_supply): (i64, i64) = state
         .db
         .query_one(
-            "SELECT COUNT(DISTINCT b.address)
-             FROM boxes b
-             JOIN box_assets ba ON b.box_id = ba.box_id
-             WHERE ba.token_id = ? AND b.spent_tx_id IS NULL",
+            "SELECT COUNT(*), COALESCE(SUM(amount), 0) FROM token_holders WHERE token_id = ?",
             [&token_id],
-            |row| row.get(0),
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .unwrap_or(0);
+        .unwrap_or((0, 0));
 
-    let items = state
+    let emission_amount: Option<i64> = state
+        .db
+        .query_one(
+            "SELECT emission_amount FROM tokens WHERE token_id = ?",
+            [&token_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let share = |amount: i64| {
+        if circulating_supply > 0 {
+            amount as f64 / circulating_supply as f64 * 100.0
+        } else {
+            0.0
+        }
+    };
+
+    // Holders are ordered by (amount DESC, address), so the cursor carries both
+    let mut args = vec![Value::Text(token_id.clone())];
+    let page_filter = match params.cursor.as_deref() {
+        Some(c) => {
+            let (amount, address) = cursor::decode(c, 2)
+                .and_then(|parts| Some((parts[0].parse::<i64>().ok()?, parts[1].clone())))
+                .ok_or((StatusCode::BAD_REQUEST, "Invalid cursor".to_string()))?;
+            args.push(Value::BigInt(amount));
+            args.push(Value::BigInt(amount));
+            args.push(Value::Text(address));
+            args.push(Value::BigInt(params.limit));
+            " AND (amount < ? OR (amount = ? AND address > ?)) ORDER BY amount DESC, address LIMIT ?"
+        }
+        None => {
+            args.push(Value::BigInt(params.limit));
+            args.push(Value::BigInt(params.offset));
+            " ORDER BY amount DESC, address LIMIT ? OFFSET ?"
+        }
+    };
+
+    let items: Vec<TokenHolder> = state
         .db
         .query_all(
-            "SELECT b.address, SUM(ba.amount) as balance
-             FROM boxes b
-             JOIN box_assets ba ON b.box_id = ba.box_id
-             WHERE ba.token_id = ? AND b.spent_tx_id IS NULL
-             GROUP BY b.address
-             ORDER BY balance DESC
-             LIMIT ? OFFSET ?",
-            params![token_id, params.limit, params.offset],
+            &format!("SELECT address, amount FROM token_holders WHERE token_id = ?{}", page_filter),
+            params_from_iter(args),
             |row| {
+                let balance: i64 = row.get(1)?;
                 Ok(TokenHolder {
                     address: row.get(0)?,
-                    balance: row.get(1)?,
+                    balance,
+                    share: share(balance),
                 })
             },
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    let next_cursor = match items.last() {
+        Some(last) if items.len() as i64 == params.limit => {
+            Some(cursor::encode(&[&last.balance.to_string(), &last.address]))
+        }
+        _ => None,
+    };
+
+    // Supply held by the largest 1/10/100 holders
+    let (top1, top10, top100): (i64, i64, i64) = state
+        .db
+        .query_one(
+            "SELECT
+                 COALESCE(SUM(amount) FILTER (WHERE rn <= 1), 0),
+                 COALESCE(SUM(amount) FILTER (WHERE rn <= 10), 0),
+                 COALESCE(SUM(amount) FILTER (WHERE rn <= 100), 0)
+             FROM (
+                 SELECT amount, ROW_NUMBER() OVER (ORDER BY amount DESC) AS rn
+                 FROM token_holders
+                 WHERE token_id = ?
+             )",
+            [&token_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or((0, 0, 0));
+
+    Ok(Json(TokenHoldersResponse {
+        items,
+        total,
+        next_cursor,
+        holders: total,
+        circulating_supply,
+        emission_amount,
+        distribution: TokenDistribution {
+            top1: share(top1),
+            top10: share(top10),
+            top100: share(top100),
+        },
+    }))
 }
 
 /// GET /api/v1/tokens/byAddress/:address - Get tokens held by address
@@ -223,26 +360,18 @@ pub async fn get_tokens_by_address(
     State(state): State<Arc<AppState>>,
     Path(address): Path<String>,
 ) -> Result<Json<Vec<TokenBalance>>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+
     let tokens = state
         .db
         .query_all(
-            "SELECT ba.token_id, SUM(ba.amount) as total, t.name, t.decimals, t.token_type
-             FROM box_assets ba
-             JOIN boxes b ON ba.box_id = b.box_id
-             LEFT JOIN tokens t ON ba.token_id = t.token_id
-             WHERE b.address = ? AND b.spent_tx_id IS NULL
-             GROUP BY ba.token_id, t.name, t.decimals, t.token_type
-             ORDER BY total DESC",
+            "SELECT th.token_id, th.amount, t.name, t.decimals, t.token_type
+             FROM token_holders th
+             LEFT JOIN tokens t ON th.token_id = t.token_id
+             WHERE th.address = ?
+             ORDER BY th.amount DESC",
             [&address],
-            |row| {
-                Ok(TokenBalance {
-                    token_id: row.get(0)?,
-                    amount: row.get(1)?,
-                    name: row.get(2)?,
-                    decimals: row.get(3)?,
-                    token_type: row.get(4)?,
-                })
-            },
+            token_balance_from_row,
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
@@ -254,12 +383,92 @@ pub async fn get_tokens_by_address(
 pub struct TokenHolder {
     pub address: String,
     pub balance: i64,
+    /// Percentage of the circulating supply held
+    pub share: f64,
 }
 
-/// Paginated token holders response (for OpenAPI schema)
+/// Percentage of the circulating supply held by the largest holders
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenDistribution {
+    pub top1: f64,
+    pub top10: f64,
+    pub top100: f64,
+}
+
+/// Paginated token holders with supply statistics
 #[derive(serde::Serialize, utoipa::ToSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct PaginatedTokenHolders {
+pub struct TokenHoldersResponse {
     pub items: Vec<TokenHolder>,
     pub total: i64,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    /// Number of addresses holding a non-zero balance
+    pub holders: i64,
+    /// Amount currently held in unspent boxes
+    pub circulating_supply: i64,
+    pub emission_amount: Option<i64>,
+    pub distribution: TokenDistribution,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::test_fixtures::{app_state, block, index_blocks, output, p2pk_tree, rows, tx};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_burn_reduces_circulating_supply() {
+        let alice = p2pk_tree("11");
+        let bob = p2pk_tree("22");
+        let db = index_blocks(&[
+            block(
+                1,
+                json!([tx(
+                    "tx1",
+                    &["issuer"],
+                    vec![output(
+                        "box1",
+                        1_000,
+                        &alice,
+                        1,
+                        json!([{ "tokenId": "issuer", "amount": 100 }])
+                    )],
+                )]),
+            ),
+            // The outputs carry 60 of the 100 tokens spent, so 40 are burned
+            block(
+                2,
+                json!([tx(
+                    "tx2",
+                    &["box1"],
+                    vec![output(
+                        "box2",
+                        1_000,
+                        &bob,
+                        2,
+                        json!([{ "tokenId": "issuer", "amount": 60 }])
+                    )],
+                )]),
+            ),
+        ]);
+
+        assert_eq!(
+            rows(&db, "SELECT tx_id, token_id, height, event_type, amount FROM token_events"),
+            vec![
+                "{'tx_id': tx1, 'token_id': issuer, 'height': 1, 'event_type': mint, 'amount': 100}",
+                "{'tx_id': tx2, 'token_id': issuer, 'height': 2, 'event_type': burn, 'amount': 40}",
+            ]
+        );
+
+        let Json(token) = get_token(State(app_state(db, &[])), Path("issuer".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(token.emission_amount, 100);
+        assert_eq!(token.burned_amount, 40);
+        assert_eq!(token.circulating_supply, 60);
+        assert_eq!(token.holder_count, 1);
+    }
 }
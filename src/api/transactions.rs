@@ -3,11 +3,15 @@ use axum::{
     http::StatusCode,
     Json,
 };
-use duckdb::params;
+use duckdb::{params, params_from_iter, types::Value};
 use serde::Deserialize;
 use std::sync::Arc;
 
-use crate::models::{DataInput, Input, Output, BoxAsset, PaginatedResponse, Pagination, Transaction, TransactionSummary};
+use crate::api::addresses::check_address;
+use crate::api::boxes::index_cursor;
+use crate::api::loaders;
+use crate::models::{PaginatedResponse, Pagination, Transaction, TransactionSummary};
+use crate::utils::cursor;
 use crate::AppState;
 
 #[derive(Deserialize)]
@@ -79,7 +83,7 @@ pub async fn get_transactions(
         })
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
 /// GET /api/v1/transactions/:id - Get transaction by ID
@@ -105,7 +109,7 @@ pub async fn get_transaction(
         .db
         .query_one(
             "SELECT tx_id, block_id, inclusion_height, timestamp, index_in_block,
-                    global_index, coinbase, size
+                    global_index, coinbase, size, fee
              FROM transactions WHERE tx_id = ?",
             [&id],
             |row| {
@@ -118,47 +122,27 @@ pub async fn get_transaction(
                     row.get::<_, i64>(5)?,     // global_index
                     row.get::<_, bool>(6)?,    // coinbase
                     row.get::<_, i32>(7)?,     // size
+                    row.get::<_, i64>(8)?,     // fee
                 ))
             },
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
         .ok_or((StatusCode::NOT_FOUND, "Transaction not found".to_string()))?;
 
-    // Get inputs
-    let inputs = state
-        .db
-        .query_all(
-            "SELECT i.box_id, b.value, b.address, b.tx_id, b.output_index
-             FROM inputs i
-             LEFT JOIN boxes b ON i.box_id = b.box_id
-             WHERE i.tx_id = ?
-             ORDER BY i.input_index",
-            [&id],
-            |row| {
-                Ok(Input {
-                    box_id: row.get(0)?,
-                    value: row.get(1)?,
-                    address: row.get(2)?,
-                    tx_id: row.get::<_, Option<String>>(3)?.unwrap_or_default(),
-                    output_index: row.get::<_, Option<i32>>(4)?.unwrap_or(0),
-                })
-            },
-        )
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    // Get outputs
-    let outputs = get_outputs_for_tx(&state, &id)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-
-    // Get data inputs
-    let data_inputs = state
-        .db
-        .query_all(
-            "SELECT box_id FROM data_inputs WHERE tx_id = ? ORDER BY input_index",
-            [&id],
-            |row| Ok(DataInput { box_id: row.get(0)? }),
-        )
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    // Inputs, outputs and data inputs through the same loaders as paged listings
+    let tx_ids = [id.clone()];
+    let inputs = loaders::load_inputs(&state.db, &tx_ids)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .remove(&id)
+        .unwrap_or_default();
+    let outputs = loaders::load_outputs(&state.db, &tx_ids)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .remove(&id)
+        .unwrap_or_default();
+    let data_inputs = loaders::load_data_inputs(&state.db, &tx_ids)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .remove(&id)
+        .unwrap_or_default();
 
     Ok(Json(Transaction {
         id: tx.0,
@@ -169,6 +153,7 @@ pub async fn get_transaction(
         global_index: tx.5,
         coinbase: tx.6,
         size: tx.7,
+        fee: tx.8,
         inputs,
         outputs,
         data_inputs,
@@ -226,7 +211,7 @@ pub async fn get_transactions_by_block(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
 /// GET /api/v1/transactions/byAddress/:address - Get transactions for an address
@@ -237,10 +222,13 @@ pub async fn get_transactions_by_block(
     params(
         ("address" = String, Path, description = "Ergo address"),
         ("offset" = Option<i64>, Query, description = "Pagination offset"),
-        ("limit" = Option<i64>, Query, description = "Results per page")
+        ("limit" = Option<i64>, Query, description = "Results per page"),
+        ("cursor" = Option<String>, Query, description = "nextCursor from the previous page; replaces offset"),
+        ("withTotal" = Option<bool>, Query, description = "Count the total (default: true with offset, false with cursor)")
     ),
     responses(
-        (status = 200, description = "Address transactions", body = PaginatedTransactions)
+        (status = 200, description = "Address transactions", body = PaginatedTransactions),
+        (status = 400, description = "Invalid address")
     )
 )]
 pub async fn get_transactions_by_address(
@@ -248,54 +236,84 @@ pub async fn get_transactions_by_address(
     Path(address): Path<String>,
     Query(params): Query<Pagination>,
 ) -> Result<Json<PaginatedResponse<TransactionSummary>>, (StatusCode, String)> {
+    check_address(&state, &address)?;
+    let after = index_cursor(&params)?;
+
     // Count unique transactions for this address (input or output)
-    let total: i64 = state
-        .db
-        .query_one(
-            "SELECT COUNT(DISTINCT t.tx_id)
-             FROM transactions t
-             WHERE t.tx_id IN (
-                 SELECT tx_id FROM boxes WHERE address = ?
-                 UNION
-                 SELECT i.tx_id FROM inputs i
-                 JOIN boxes b ON i.box_id = b.box_id
-                 WHERE b.address = ?
-             )",
-            params![address, address],
-            |row| row.get(0),
-        )
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-        .unwrap_or(0);
+    let total = if params.include_total() {
+        let total: i64 = state
+            .db
+            .query_one(
+                "SELECT COUNT(DISTINCT t.tx_id)
+                 FROM transactions t
+                 WHERE t.tx_id IN (
+                     SELECT tx_id FROM boxes WHERE address = ?
+                     UNION
+                     SELECT i.tx_id FROM inputs i
+                     JOIN boxes b ON i.box_id = b.box_id
+                     WHERE b.address = ?
+                 )",
+                params![address, address],
+                |row| row.get(0),
+            )
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .unwrap_or(0);
+        Some(total)
+    } else {
+        None
+    };
+
+    let mut args = vec![Value::Text(address.clone()), Value::Text(address.clone())];
+    let page_filter = match after {
+        Some(global_index) => {
+            args.push(Value::BigInt(global_index));
+            args.push(Value::BigInt(params.limit));
+            " AND t.global_index < ? ORDER BY t.global_index DESC LIMIT ?"
+        }
+        None => {
+            args.push(Value::BigInt(params.limit));
+            args.push(Value::BigInt(params.offset));
+            " ORDER BY t.global_index DESC LIMIT ? OFFSET ?"
+        }
+    };
 
-    let items = state
+    let sql = format!(
+        "SELECT t.tx_id, t.timestamp, t.inclusion_height, t.input_count, t.output_count, t.size, t.global_index
+         FROM transactions t
+         WHERE t.tx_id IN (
+             SELECT tx_id FROM boxes WHERE address = ?
+             UNION
+             SELECT i.tx_id FROM inputs i
+             JOIN boxes b ON i.box_id = b.box_id
+             WHERE b.address = ?
+         ){}",
+        page_filter
+    );
+
+    let rows = state
         .db
-        .query_all(
-            "SELECT DISTINCT t.tx_id, t.timestamp, t.inclusion_height, t.input_count, t.output_count, t.size
-             FROM transactions t
-             WHERE t.tx_id IN (
-                 SELECT tx_id FROM boxes WHERE address = ?
-                 UNION
-                 SELECT i.tx_id FROM inputs i
-                 JOIN boxes b ON i.box_id = b.box_id
-                 WHERE b.address = ?
-             )
-             ORDER BY t.inclusion_height DESC
-             LIMIT ? OFFSET ?",
-            params![address, address, params.limit, params.offset],
-            |row| {
-                Ok(TransactionSummary {
+        .query_all(&sql, params_from_iter(args), |row| {
+            Ok((
+                TransactionSummary {
                     id: row.get(0)?,
                     timestamp: row.get(1)?,
                     inclusion_height: row.get(2)?,
                     input_count: row.get(3)?,
                     output_count: row.get(4)?,
                     size: row.get(5)?,
-                })
-            },
-        )
+                },
+                row.get::<_, i64>(6)?,
+            ))
+        })
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    let next_cursor = match rows.last() {
+        Some((_, global_index)) if rows.len() as i64 == params.limit => Some(cursor::encode_index(*global_index)),
+        _ => None,
+    };
+    let items = rows.into_iter().map(|(tx, _)| tx).collect();
+
+    Ok(Json(PaginatedResponse { items, total, next_cursor }))
 }
 
 /// GET /api/v1/transactions/byInputsScriptTemplateHash/:hash
@@ -341,7 +359,7 @@ pub async fn get_transactions_by_template(
         )
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    Ok(Json(PaginatedResponse { items, total }))
+    Ok(Json(PaginatedResponse::new(items, total)))
 }
 
 /// GET /api/v1/transactions/byGlobalIndex/stream
@@ -393,70 +411,3 @@ pub async fn submit_transaction(
 
     Ok(Json(serde_json::json!({ "id": tx_id })))
 }
-
-// Helper function to get outputs for a transaction
-fn get_outputs_for_tx(state: &Arc<AppState>, tx_id: &str) -> anyhow::Result<Vec<Output>> {
-    let outputs = state.db.query_all(
-        "SELECT box_id, tx_id, output_index, ergo_tree, address, value,
-                creation_height, settlement_height, additional_registers, spent_tx_id
-         FROM boxes WHERE tx_id = ? ORDER BY output_index",
-        [tx_id],
-        |row| {
-            Ok((
-                row.get::<_, String>(0)?,    // box_id
-                row.get::<_, String>(1)?,    // tx_id
-                row.get::<_, i32>(2)?,       // output_index
-                row.get::<_, String>(3)?,    // ergo_tree
-                row.get::<_, String>(4)?,    // address
-                row.get::<_, i64>(5)?,       // value
-                row.get::<_, i64>(6)?,       // creation_height
-                row.get::<_, i64>(7)?,       // settlement_height
-                row.get::<_, Option<String>>(8)?, // additional_registers
-                row.get::<_, Option<String>>(9)?, // spent_tx_id
-            ))
-        },
-    )?;
-
-    let mut result = Vec::new();
-    for output in outputs {
-        // Get assets for this box
-        let assets = state.db.query_all(
-            "SELECT ba.token_id, ba.amount, ba.asset_index, t.name, t.decimals
-             FROM box_assets ba
-             LEFT JOIN tokens t ON ba.token_id = t.token_id
-             WHERE ba.box_id = ?
-             ORDER BY ba.asset_index",
-            [&output.0],
-            |row| {
-                Ok(BoxAsset {
-                    token_id: row.get(0)?,
-                    amount: row.get(1)?,
-                    index: row.get(2)?,
-                    name: row.get(3)?,
-                    decimals: row.get(4)?,
-                })
-            },
-        )?;
-
-        let additional_registers = output.8
-            .as_ref()
-            .and_then(|s| serde_json::from_str(s).ok());
-
-        result.push(Output {
-            box_id: output.0,
-            tx_id: output.1,
-            index: output.2,
-            ergo_tree: output.3,
-            address: output.4,
-            value: output.5,
-            creation_height: output.6,
-            settlement_height: output.7,
-            assets,
-            additional_registers,
-            spent_tx_id: output.9,
-            main_chain: true,
-        });
-    }
-
-    Ok(result)
-}
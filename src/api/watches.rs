@@ -0,0 +1,345 @@
+//! Webhook watch registry and delivery log
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use duckdb::params;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::api::addresses::check_address;
+use crate::api::require_admin;
+use crate::models::{PaginatedResponse, Watch, WatchRequest, WebhookDelivery};
+use crate::sync::{allow_private_targets, resolve_webhook_url};
+use crate::AppState;
+
+const MAX_CONFIRMATIONS: i32 = 720;
+
+const WATCH_COLUMNS: &str = "watch_id, watch_type, target, url, confirmations, created_at";
+
+fn watch_from_row(row: &duckdb::Row) -> duckdb::Result<Watch> {
+    Ok(Watch {
+        id: row.get(0)?,
+        watch_type: row.get(1)?,
+        target: row.get(2)?,
+        url: row.get(3)?,
+        confirmations: row.get(4)?,
+        created_at: row.get(5)?,
+    })
+}
+
+fn is_hex_id(s: &str) -> bool {
+    s.len() == 64 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// GET /api/v1/watches - List registered watches
+#[utoipa::path(
+    get,
+    path = "/watches",
+    tag = "webhooks",
+    params(
+        ("api_key" = String, Header, description = "ADMIN_API_KEY")
+    ),
+    responses(
+        (status = 200, description = "Registered watches", body = Vec<Watch>),
+        (status = 401, description = "Invalid api_key"),
+        (status = 403, description = "ADMIN_API_KEY is not configured")
+    )
+)]
+pub async fn get_watches(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Watch>>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+
+    let watches = state
+        .db
+        .query_all(
+            &format!("SELECT {} FROM watches ORDER BY watch_id", WATCH_COLUMNS),
+            [],
+            watch_from_row,
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(watches))
+}
+
+/// POST /api/v1/watches - Register a watch
+#[utoipa::path(
+    post,
+    path = "/watches",
+    tag = "webhooks",
+    params(
+        ("api_key" = String, Header, description = "ADMIN_API_KEY")
+    ),
+    request_body = WatchRequest,
+    responses(
+        (status = 200, description = "Registered watch", body = Watch),
+        (status = 400, description = "Invalid watch or url resolving to a private address"),
+        (status = 401, description = "Invalid api_key"),
+        (status = 403, description = "ADMIN_API_KEY is not configured")
+    )
+)]
+pub async fn create_watch(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<WatchRequest>,
+) -> Result<Json<Watch>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let target = request.target.trim().to_string();
+    let valid_target = match request.watch_type.as_str() {
+        "address" => {
+            check_address(&state, &target)?;
+            true
+        }
+        "template" | "token" => is_hex_id(&target),
+        _ => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "watchType must be address, template or token".to_string(),
+            ))
+        }
+    };
+    if !valid_target {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid {} target", request.watch_type)));
+    }
+    resolve_webhook_url(&request.url, allow_private_targets())
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    if request.secret.len() < 16 {
+        return Err((StatusCode::BAD_REQUEST, "secret must be at least 16 characters".to_string()));
+    }
+    let confirmations = request.confirmations.unwrap_or(1);
+    if !(1..=MAX_CONFIRMATIONS).contains(&confirmations) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("confirmations must be between 1 and {}", MAX_CONFIRMATIONS),
+        ));
+    }
+
+    let watch = state
+        .db
+        .query_one(
+            &format!(
+                "INSERT INTO watches (watch_type, target, url, secret, confirmations, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?)
+                 RETURNING {}",
+                WATCH_COLUMNS
+            ),
+            params![
+                request.watch_type,
+                target,
+                request.url,
+                request.secret,
+                confirmations,
+                chrono::Utc::now().timestamp()
+            ],
+            watch_from_row,
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Watch not stored".to_string()))?;
+
+    Ok(Json(watch))
+}
+
+/// GET /api/v1/watches/:watchId - Get a watch
+#[utoipa::path(
+    get,
+    path = "/watches/{watchId}",
+    tag = "webhooks",
+    params(
+        ("watchId" = i64, Path, description = "Watch id"),
+        ("api_key" = String, Header, description = "ADMIN_API_KEY")
+    ),
+    responses(
+        (status = 200, description = "Watch", body = Watch),
+        (status = 401, description = "Invalid api_key"),
+        (status = 403, description = "ADMIN_API_KEY is not configured"),
+        (status = 404, description = "Watch not found")
+    )
+)]
+pub async fn get_watch(
+    State(state): State<Arc<AppState>>,
+    Path(watch_id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Json<Watch>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    state
+        .db
+        .query_one(
+            &format!("SELECT {} FROM watches WHERE watch_id = ?", WATCH_COLUMNS),
+            [watch_id],
+            watch_from_row,
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, "Watch not found".to_string()))
+}
+
+/// DELETE /api/v1/watches/:watchId - Remove a watch and its delivery log
+#[utoipa::path(
+    delete,
+    path = "/watches/{watchId}",
+    tag = "webhooks",
+    params(
+        ("watchId" = i64, Path, description = "Watch id"),
+        ("api_key" = String, Header, description = "ADMIN_API_KEY")
+    ),
+    responses(
+        (status = 200, description = "Watch removed"),
+        (status = 401, description = "Invalid api_key"),
+        (status = 403, description = "ADMIN_API_KEY is not configured"),
+        (status = 404, description = "Watch not found")
+    )
+)]
+pub async fn delete_watch(
+    State(state): State<Arc<AppState>>,
+    Path(watch_id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+
+    let mut removed = 0;
+    state
+        .db
+        .execute_transaction(|conn| {
+            conn.execute("DELETE FROM webhook_deliveries WHERE watch_id = ?", [watch_id])?;
+            removed = conn.execute("DELETE FROM watches WHERE watch_id = ?", [watch_id])?;
+            Ok(())
+        })
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if removed == 0 {
+        return Err((StatusCode::NOT_FOUND, "Watch not found".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "deleted",
+        "watchId": watch_id
+    })))
+}
+
+#[derive(Deserialize)]
+pub struct DeliveriesQuery {
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub offset: i64,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+fn default_limit() -> i64 {
+    20
+}
+
+/// GET /api/v1/watches/:watchId/deliveries - Delivery log; `status=dead` lists dead letters
+#[utoipa::path(
+    get,
+    path = "/watches/{watchId}/deliveries",
+    tag = "webhooks",
+    params(
+        ("watchId" = i64, Path, description = "Watch id"),
+        ("status" = Option<String>, Query, description = "pending, delivered or dead"),
+        ("offset" = Option<i64>, Query, description = "Offset"),
+        ("limit" = Option<i64>, Query, description = "Limit (max 500)"),
+        ("api_key" = String, Header, description = "ADMIN_API_KEY")
+    ),
+    responses(
+        (status = 200, description = "Deliveries, newest first", body = PaginatedWebhookDeliveries),
+        (status = 401, description = "Invalid api_key"),
+        (status = 403, description = "ADMIN_API_KEY is not configured")
+    )
+)]
+pub async fn get_deliveries(
+    State(state): State<Arc<AppState>>,
+    Path(watch_id): Path<i64>,
+    Query(query): Query<DeliveriesQuery>,
+    headers: HeaderMap,
+) -> Result<Json<PaginatedResponse<WebhookDelivery>>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let limit = query.limit.clamp(1, 500);
+
+    let items = state
+        .db
+        .query_all(
+            "SELECT delivery_id, watch_id, box_id, height, status, attempts, next_attempt_at,
+                    last_error, created_at, delivered_at
+             FROM webhook_deliveries
+             WHERE watch_id = ? AND status = COALESCE(?, status)
+             ORDER BY delivery_id DESC
+             LIMIT ? OFFSET ?",
+            params![watch_id, query.status, limit, query.offset],
+            |row| {
+                Ok(WebhookDelivery {
+                    id: row.get(0)?,
+                    watch_id: row.get(1)?,
+                    box_id: row.get(2)?,
+                    height: row.get(3)?,
+                    status: row.get(4)?,
+                    attempts: row.get(5)?,
+                    next_attempt_at: row.get(6)?,
+                    last_error: row.get(7)?,
+                    created_at: row.get(8)?,
+                    delivered_at: row.get(9)?,
+                })
+            },
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let total: i64 = state
+        .db
+        .query_one(
+            "SELECT COUNT(*) FROM webhook_deliveries WHERE watch_id = ? AND status = COALESCE(?, status)",
+            params![watch_id, query.status],
+            |row| row.get(0),
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .unwrap_or(0);
+
+    Ok(Json(PaginatedResponse::new(items, total)))
+}
+
+/// POST /api/v1/watches/deliveries/:deliveryId/retry - Requeue a dead-lettered delivery
+#[utoipa::path(
+    post,
+    path = "/watches/deliveries/{deliveryId}/retry",
+    tag = "webhooks",
+    params(
+        ("deliveryId" = i64, Path, description = "Delivery id"),
+        ("api_key" = String, Header, description = "ADMIN_API_KEY")
+    ),
+    responses(
+        (status = 200, description = "Delivery requeued"),
+        (status = 401, description = "Invalid api_key"),
+        (status = 403, description = "ADMIN_API_KEY is not configured"),
+        (status = 404, description = "No dead delivery with this id")
+    )
+)]
+pub async fn retry_delivery(
+    State(state): State<Arc<AppState>>,
+    Path(delivery_id): Path<i64>,
+    headers: HeaderMap,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    require_admin(&state, &headers)?;
+    let updated = state
+        .db
+        .execute(
+            "UPDATE webhook_deliveries
+             SET status = 'pending', attempts = 0, next_attempt_at = ?
+             WHERE delivery_id = ? AND status = 'dead'",
+            params![chrono::Utc::now().timestamp(), delivery_id],
+        )
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    if updated == 0 {
+        return Err((StatusCode::NOT_FOUND, "No dead delivery with this id".to_string()));
+    }
+
+    Ok(Json(serde_json::json!({
+        "status": "requeued",
+        "deliveryId": delivery_id
+    })))
+}
@@ -0,0 +1,112 @@
+//! WebSocket subscriptions to chain events
+//!
+//! Clients send `{"op": "subscribe", "topic": "address", "address": "9..."}` (or
+//! `"unsubscribe"`) and receive every matching event as a JSON message. Topics:
+//! `blocks`, `reorgs`, `mempool`, `address` (+`address`), `token` (+`tokenId`)
+//! and `template` (+`templateHash`).
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::sync::Topic;
+use crate::AppState;
+
+/// Subscriptions allowed per connection
+const MAX_TOPICS: usize = 100;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Op {
+    Subscribe,
+    Unsubscribe,
+}
+
+#[derive(Deserialize)]
+struct ClientMessage {
+    op: Op,
+    #[serde(flatten)]
+    topic: Topic,
+}
+
+/// GET /api/v1/ws - Upgrade to a WebSocket event subscription
+pub async fn ws_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> Response {
+    ws.on_upgrade(move |socket| handle_socket(socket, state))
+}
+
+async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut events = state.sync_service.subscribe_events();
+    let mut topics: HashSet<Topic> = HashSet::new();
+
+    loop {
+        tokio::select! {
+            msg = receiver.next() => {
+                let reply = match msg {
+                    Some(Ok(Message::Text(text))) => handle_client_message(&text, &mut topics),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    // Pings are answered by axum; binary frames are ignored
+                    Some(Ok(_)) => continue,
+                };
+                if sender.send(Message::Text(reply.to_string())).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                let text = match event {
+                    Ok(event) => {
+                        if !topics.iter().any(|topic| event.matches(topic)) {
+                            continue;
+                        }
+                        match serde_json::to_string(&*event) {
+                            Ok(text) => text,
+                            Err(_) => continue,
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        serde_json::json!({ "type": "lagged", "skipped": skipped }).to_string()
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if sender.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn handle_client_message(text: &str, topics: &mut HashSet<Topic>) -> serde_json::Value {
+    let message: ClientMessage = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(e) => {
+            return serde_json::json!({ "type": "error", "message": e.to_string() });
+        }
+    };
+
+    match message.op {
+        Op::Subscribe => {
+            if topics.len() >= MAX_TOPICS && !topics.contains(&message.topic) {
+                return serde_json::json!({
+                    "type": "error",
+                    "message": format!("At most {} subscriptions per connection", MAX_TOPICS)
+                });
+            }
+            topics.insert(message.topic);
+            serde_json::json!({ "type": "subscribed", "subscriptions": topics.len() })
+        }
+        Op::Unsubscribe => {
+            topics.remove(&message.topic);
+            serde_json::json!({ "type": "unsubscribed", "subscriptions": topics.len() })
+        }
+    }
+}
@@ -1,10 +1,12 @@
 mod schema;
 
 use anyhow::{Context, Result};
-use duckdb::{Connection, params};
+use duckdb::{Connection, InterruptHandle, params};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-pub use schema::MIGRATIONS;
+pub use schema::{BULK_LOAD_INDEXES, MIGRATIONS};
 
 /// Extension trait for optional query results
 trait OptionalExt<T> {
@@ -21,9 +23,92 @@ impl<T> OptionalExt<T> for Result<T, duckdb::Error> {
     }
 }
 
+tokio::task_local! {
+    /// Timeout for reads made while serving one API request, set by the API layer
+    pub static QUERY_DEADLINE: QueryDeadline;
+}
+
+/// Per-query timeout for the reads of one request. Tracks whether any of them
+/// was cancelled so the request can be answered with 503.
+#[derive(Clone)]
+pub struct QueryDeadline {
+    timeout: Duration,
+    timed_out: Arc<AtomicBool>,
+}
+
+impl QueryDeadline {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            timed_out: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.timed_out.load(Ordering::SeqCst)
+    }
+
+    /// Interrupt the query on `interrupt` once the timeout passes; abort the
+    /// returned task when the query finishes first
+    fn watch(&self, interrupt: Arc<InterruptHandle>, fired: Arc<AtomicBool>) -> tokio::task::JoinHandle<()> {
+        let timeout = self.timeout;
+        let timed_out = self.timed_out.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            fired.store(true, Ordering::SeqCst);
+            timed_out.store(true, Ordering::SeqCst);
+            interrupt.interrupt();
+        })
+    }
+}
+
+/// A read was cancelled after running past its request's timeout
+#[derive(Debug, thiserror::Error)]
+#[error("Query cancelled after {0:?}")]
+pub struct QueryTimeout(pub Duration);
+
+/// Pooled read connection on the writer's database instance
+pub struct ReadConnection {
+    conn: Connection,
+    /// Set when a timeout interrupted this connection; the pool then drops it
+    /// rather than hand a pending interrupt to the next query
+    interrupted: bool,
+}
+
+/// Opens read connections by cloning a connection kept aside for the purpose,
+/// so new pool connections never wait on the writer's lock
+struct ReadConnectionManager {
+    source: Mutex<Connection>,
+}
+
+impl r2d2::ManageConnection for ReadConnectionManager {
+    type Connection = ReadConnection;
+    type Error = duckdb::Error;
+
+    fn connect(&self) -> Result<ReadConnection, duckdb::Error> {
+        Ok(ReadConnection {
+            conn: self.source.lock().unwrap().try_clone()?,
+            interrupted: false,
+        })
+    }
+
+    fn is_valid(&self, conn: &mut ReadConnection) -> Result<(), duckdb::Error> {
+        conn.conn.execute_batch("SELECT 1")
+    }
+
+    fn has_broken(&self, conn: &mut ReadConnection) -> bool {
+        conn.interrupted
+    }
+}
+
+/// The writer connection serializes sync and other writes; queries run on a
+/// pool of read connections so they don't wait behind a long sync transaction
 #[derive(Clone)]
 pub struct Database {
     conn: Arc<Mutex<Connection>>,
+    readers: r2d2::Pool<ReadConnectionManager>,
+    /// Whether DuckDB's fts extension is loaded and the search index is usable
+    fts_enabled: Arc<AtomicBool>,
 }
 
 impl Database {
@@ -35,8 +120,15 @@ impl Database {
             .unwrap_or_else(|_| "4GB".to_string());
         let threads = std::env::var("DUCKDB_THREADS")
             .unwrap_or_else(|_| "4".to_string());
+        let read_connections: u32 = std::env::var("DUCKDB_READ_CONNECTIONS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(8);
 
-        tracing::info!("DuckDB config: threads={}, memory_limit={}", threads, memory_limit);
+        tracing::info!(
+            "DuckDB config: threads={}, memory_limit={}, read_connections={}",
+            threads, memory_limit, read_connections
+        );
 
         // Enable optimizations for faster writes
         conn.execute_batch(&format!(
@@ -49,11 +141,51 @@ impl Database {
             threads, memory_limit
         ))?;
 
+        // Full-text search is optional: INSTALL needs network access the first time
+        let fts_enabled = match conn.execute_batch("INSTALL fts; LOAD fts;") {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("DuckDB fts extension unavailable, search falls back to LIKE: {}", e);
+                false
+            }
+        };
+
+        // Clones share the database instance, its settings and loaded extensions
+        let readers = r2d2::Pool::builder()
+            .max_size(read_connections.max(1))
+            .test_on_check_out(false)
+            .build(ReadConnectionManager {
+                source: Mutex::new(conn.try_clone()?),
+            })
+            .context("Failed to open read connections")?;
+
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
+            readers,
+            fts_enabled: Arc::new(AtomicBool::new(fts_enabled)),
         })
     }
 
+    pub fn fts_enabled(&self) -> bool {
+        self.fts_enabled.load(Ordering::SeqCst)
+    }
+
+    /// Rebuild the BM25 index over search_index. DuckDB FTS indexes are static
+    /// snapshots, so this has to run again after documents are added.
+    pub fn rebuild_search_index(&self) -> Result<()> {
+        if !self.fts_enabled() {
+            return Ok(());
+        }
+        let conn = self.conn.lock().unwrap();
+        if let Err(e) = conn.execute_batch(
+            "PRAGMA create_fts_index('search_index', 'doc_id', 'search_text', overwrite=1)",
+        ) {
+            tracing::warn!("Failed to build full-text index, falling back to LIKE: {}", e);
+            self.fts_enabled.store(false, Ordering::SeqCst);
+        }
+        Ok(())
+    }
+
     pub fn migrate(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
 
@@ -87,6 +219,11 @@ impl Database {
             }
         }
 
+        // A bulk load that was cut short leaves its tables without secondary indexes
+        for (_, sql) in BULK_LOAD_INDEXES {
+            conn.execute_batch(sql)?;
+        }
+
         Ok(())
     }
 
@@ -102,6 +239,24 @@ impl Database {
         Ok(conn.execute_batch(sql)?)
     }
 
+    /// Drop the secondary indexes of the tables fast sync appends to
+    pub fn drop_bulk_load_indexes(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        for (name, _) in BULK_LOAD_INDEXES {
+            conn.execute_batch(&format!("DROP INDEX IF EXISTS {}", name))?;
+        }
+        Ok(())
+    }
+
+    /// Build the indexes dropped by [`drop_bulk_load_indexes`](Self::drop_bulk_load_indexes)
+    pub fn create_bulk_load_indexes(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        for (_, sql) in BULK_LOAD_INDEXES {
+            conn.execute_batch(sql)?;
+        }
+        Ok(())
+    }
+
     /// Force a checkpoint to flush data to disk and free memory
     pub fn checkpoint(&self) -> Result<()> {
         let conn = self.conn.lock().unwrap();
@@ -128,14 +283,48 @@ impl Database {
         }
     }
 
+    /// Run `f` on a pooled read connection. While serving an API request (see
+    /// [`QUERY_DEADLINE`]) the query is interrupted once it outlives the timeout.
+    fn read<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let deadline = QUERY_DEADLINE.try_with(|d| d.clone()).ok();
+
+        let mut conn = match &deadline {
+            Some(deadline) => match self.readers.get_timeout(deadline.timeout) {
+                Ok(conn) => conn,
+                Err(_) => {
+                    // Every read connection stayed busy for the whole timeout
+                    deadline.timed_out.store(true, Ordering::SeqCst);
+                    return Err(QueryTimeout(deadline.timeout).into());
+                }
+            },
+            None => self.readers.get().context("No read connection available")?,
+        };
+
+        let fired = Arc::new(AtomicBool::new(false));
+        let watchdog = deadline
+            .as_ref()
+            .map(|deadline| deadline.watch(conn.conn.interrupt_handle(), fired.clone()));
+
+        let result = f(&conn.conn);
+
+        if let Some(watchdog) = watchdog {
+            watchdog.abort();
+        }
+        if fired.load(Ordering::SeqCst) {
+            conn.interrupted = true;
+            if result.is_err() {
+                return Err(QueryTimeout(deadline.map(|d| d.timeout).unwrap_or_default()).into());
+            }
+        }
+        result
+    }
+
     pub fn query_one<T, P, F>(&self, sql: &str, params: P, f: F) -> Result<Option<T>>
     where
         P: duckdb::Params,
         F: FnOnce(&duckdb::Row<'_>) -> Result<T, duckdb::Error>,
     {
-        let conn = self.conn.lock().unwrap();
-        let result = conn.query_row(sql, params, f).optional()?;
-        Ok(result)
+        self.read(|conn| Ok(conn.query_row(sql, params, f).optional()?))
     }
 
     pub fn query_all<T, P, F>(&self, sql: &str, params: P, mut f: F) -> Result<Vec<T>>
@@ -143,14 +332,15 @@ impl Database {
         P: duckdb::Params,
         F: FnMut(&duckdb::Row<'_>) -> Result<T, duckdb::Error>,
     {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(sql)?;
-        let mut rows = stmt.query(params)?;
-        let mut results = Vec::new();
-        while let Some(row) = rows.next()? {
-            results.push(f(row)?);
-        }
-        Ok(results)
+        self.read(|conn| {
+            let mut stmt = conn.prepare(sql)?;
+            let mut rows = stmt.query(params)?;
+            let mut results = Vec::new();
+            while let Some(row) = rows.next()? {
+                results.push(f(row)?);
+            }
+            Ok(results)
+        })
     }
 
     // Sync status methods
@@ -163,46 +353,99 @@ impl Database {
         Ok(result.unwrap_or(0))
     }
 
+    /// Main-chain block ID stored at the given height, if any
+    pub fn get_block_id_at_height(&self, height: i64) -> Result<Option<String>> {
+        self.query_one(
+            "SELECT block_id FROM blocks WHERE height = ? AND main_chain = TRUE",
+            [height],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn get_block_window_stats(&self, tip_height: i64) -> Result<Option<BlockWindowStats>> {
+        self.read(|conn| block_window_stats(conn, tip_height))
+    }
+
     pub fn get_stats(&self) -> Result<DbStats> {
-        let conn = self.conn.lock().unwrap();
+        self.read(db_stats)
+    }
+}
 
-        let block_count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM blocks", [], |row| row.get(0))
-            .unwrap_or(0);
-
-        let tx_count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0))
-            .unwrap_or(0);
-
-        let box_count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM boxes", [], |row| row.get(0))
-            .unwrap_or(0);
-
-        let unspent_box_count: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM boxes WHERE spent_tx_id IS NULL",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0);
-
-        let token_count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM tokens", [], |row| row.get(0))
-            .unwrap_or(0);
-
-        let address_count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM address_stats", [], |row| row.get(0))
-            .unwrap_or(0);
-
-        Ok(DbStats {
-            block_count,
-            tx_count,
-            box_count,
-            unspent_box_count,
-            token_count,
-            address_count,
-        })
+fn db_stats(conn: &Connection) -> Result<DbStats> {
+    let block_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM blocks", [], |row| row.get(0))
+        .unwrap_or(0);
+
+    let tx_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM transactions", [], |row| row.get(0))
+        .unwrap_or(0);
+
+    let box_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM boxes", [], |row| row.get(0))
+        .unwrap_or(0);
+
+    let unspent_box_count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM boxes WHERE spent_tx_id IS NULL",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap_or(0);
+
+    let token_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM tokens", [], |row| row.get(0))
+        .unwrap_or(0);
+
+    let address_count: i64 = conn
+        .query_row("SELECT COUNT(*) FROM address_stats", [], |row| row.get(0))
+        .unwrap_or(0);
+
+    Ok(DbStats {
+        block_count,
+        tx_count,
+        box_count,
+        unspent_box_count,
+        token_count,
+        address_count,
+    })
+}
+
+/// Blocks in the rolling window behind hashrate and block time (about a day)
+pub const BLOCK_WINDOW: i64 = 720;
+
+/// Averages over the last BLOCK_WINDOW main-chain blocks up to a height
+#[derive(Debug, Clone, Copy)]
+pub struct BlockWindowStats {
+    pub avg_difficulty: f64,
+    /// Seconds
+    pub block_time_avg: f64,
+    /// Hashes per second
+    pub hashrate: f64,
+}
+
+pub fn block_window_stats(conn: &Connection, tip_height: i64) -> Result<Option<BlockWindowStats>> {
+    let (count, avg_difficulty, first_ts, last_ts): (i64, Option<f64>, Option<i64>, Option<i64>) = conn
+        .query_row(
+            "SELECT COUNT(*), AVG(difficulty), MIN(timestamp), MAX(timestamp)
+             FROM blocks
+             WHERE main_chain = TRUE AND height > ? AND height <= ?",
+            params![tip_height - BLOCK_WINDOW, tip_height],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )?;
+
+    let (Some(avg_difficulty), Some(first_ts), Some(last_ts)) = (avg_difficulty, first_ts, last_ts) else {
+        return Ok(None);
+    };
+    if count < 2 || last_ts <= first_ts {
+        return Ok(None);
     }
+
+    let block_time_avg = (last_ts - first_ts) as f64 / 1000.0 / (count - 1) as f64;
+    Ok(Some(BlockWindowStats {
+        avg_difficulty,
+        block_time_avg,
+        hashrate: avg_difficulty / block_time_avg,
+    }))
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -214,3 +457,39 @@ pub struct DbStats {
     pub token_count: i64,
     pub address_count: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::test_fixtures::SLOW_READ;
+    use std::time::Instant;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_read_past_deadline_is_interrupted() {
+        let db = Database::new(":memory:").unwrap();
+        let deadline = QueryDeadline::new(Duration::from_millis(50));
+        let scoped = deadline.clone();
+        let reader = db.clone();
+        let runtime = tokio::runtime::Handle::current();
+
+        let started = Instant::now();
+        let result = tokio::task::spawn_blocking(move || {
+            runtime.block_on(QUERY_DEADLINE.scope(scoped, async move {
+                reader.query_one(SLOW_READ, [], |row| row.get::<_, i64>(0))
+            }))
+        })
+        .await
+        .unwrap();
+
+        let err = result.unwrap_err();
+        assert!(err.downcast_ref::<QueryTimeout>().is_some(), "{}", err);
+        assert!(deadline.timed_out());
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // The interrupted connection is dropped and later reads run normally
+        let answer = db
+            .query_one("SELECT 42", [], |row| row.get::<_, i64>(0))
+            .unwrap();
+        assert_eq!(answer, Some(42));
+    }
+}
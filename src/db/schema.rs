@@ -217,4 +217,473 @@ pub const MIGRATIONS: &[(&str, &str)] = &[
         );
         "#,
     ),
+    (
+        "005_box_registers_and_constants",
+        r#"
+        -- Decoded box registers (R4-R9) for register search
+        CREATE TABLE IF NOT EXISTS box_registers (
+            box_id VARCHAR(64) NOT NULL,
+            register_id VARCHAR(2) NOT NULL,
+            serialized_value TEXT NOT NULL,
+            sigma_type TEXT,
+            rendered_value TEXT,
+            PRIMARY KEY (box_id, register_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_box_registers_rendered ON box_registers(register_id, rendered_value);
+        CREATE INDEX IF NOT EXISTS idx_box_registers_serialized ON box_registers(register_id, serialized_value);
+
+        -- Segregated ErgoTree constants per box for constant search
+        CREATE TABLE IF NOT EXISTS script_constants (
+            box_id VARCHAR(64) NOT NULL,
+            constant_index INTEGER NOT NULL,
+            serialized_value TEXT NOT NULL,
+            sigma_type TEXT NOT NULL,
+            rendered_value TEXT NOT NULL,
+            PRIMARY KEY (box_id, constant_index)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_script_constants_rendered ON script_constants(constant_index, rendered_value);
+        CREATE INDEX IF NOT EXISTS idx_script_constants_serialized ON script_constants(constant_index, serialized_value);
+        "#,
+    ),
+    (
+        "006_mempool_index",
+        r#"
+        -- Outputs of unconfirmed transactions
+        CREATE TABLE IF NOT EXISTS mempool_outputs (
+            box_id VARCHAR(64) PRIMARY KEY,
+            tx_id VARCHAR(64) NOT NULL,
+            output_index INTEGER NOT NULL,
+            ergo_tree TEXT NOT NULL,
+            ergo_tree_template_hash VARCHAR(64) NOT NULL,
+            address TEXT NOT NULL,
+            value BIGINT NOT NULL,
+            creation_height INTEGER NOT NULL,
+            additional_registers TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_mempool_outputs_tx ON mempool_outputs(tx_id);
+        CREATE INDEX IF NOT EXISTS idx_mempool_outputs_address ON mempool_outputs(address);
+        CREATE INDEX IF NOT EXISTS idx_mempool_outputs_template ON mempool_outputs(ergo_tree_template_hash);
+
+        -- Inputs of unconfirmed transactions, resolved to the spent box's owner
+        CREATE TABLE IF NOT EXISTS mempool_inputs (
+            tx_id VARCHAR(64) NOT NULL,
+            box_id VARCHAR(64) NOT NULL,
+            input_index INTEGER NOT NULL,
+            address TEXT,
+            value BIGINT,
+            PRIMARY KEY (tx_id, input_index)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_mempool_inputs_box ON mempool_inputs(box_id);
+        CREATE INDEX IF NOT EXISTS idx_mempool_inputs_address ON mempool_inputs(address);
+
+        -- Tokens in unconfirmed outputs
+        CREATE TABLE IF NOT EXISTS mempool_assets (
+            box_id VARCHAR(64) NOT NULL,
+            token_id VARCHAR(64) NOT NULL,
+            amount BIGINT NOT NULL,
+            asset_index INTEGER NOT NULL,
+            PRIMARY KEY (box_id, asset_index)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_mempool_assets_token ON mempool_assets(token_id);
+        "#,
+    ),
+    (
+        "007_backfill_token_holders",
+        r#"
+        -- token_holders is maintained incrementally from here on; seed it from the UTXO set
+        DELETE FROM token_holders;
+
+        INSERT INTO token_holders (token_id, address, amount)
+        SELECT ba.token_id, b.address, SUM(ba.amount)
+        FROM box_assets ba
+        JOIN boxes b ON ba.box_id = b.box_id
+        WHERE b.spent_tx_id IS NULL
+        GROUP BY ba.token_id, b.address
+        HAVING SUM(ba.amount) > 0;
+        "#,
+    ),
+    (
+        "008_backfill_address_balances",
+        r#"
+        -- address_stats.balance is maintained incrementally from here on; seed it from the UTXO set
+        UPDATE address_stats SET balance = 0;
+
+        UPDATE address_stats SET balance = u.total
+        FROM (
+            SELECT address, SUM(value) AS total
+            FROM boxes
+            WHERE spent_tx_id IS NULL
+            GROUP BY address
+        ) u
+        WHERE address_stats.address = u.address;
+        "#,
+    ),
+    (
+        "009_search_documents",
+        r#"
+        -- search_index was never populated; recreate it with a numeric document id for FTS
+        DROP TABLE IF EXISTS search_index;
+        CREATE SEQUENCE IF NOT EXISTS search_index_doc_seq;
+
+        CREATE TABLE IF NOT EXISTS search_index (
+            doc_id BIGINT PRIMARY KEY DEFAULT nextval('search_index_doc_seq'),
+            entity_type VARCHAR(32) NOT NULL,  -- token, address
+            entity_id TEXT NOT NULL,
+            search_text TEXT NOT NULL,
+            UNIQUE (entity_type, entity_id)
+        );
+
+        -- Human-readable names for addresses (exchanges, mining pools, contracts)
+        CREATE TABLE IF NOT EXISTS address_labels (
+            address TEXT PRIMARY KEY,
+            label TEXT NOT NULL,
+            category VARCHAR(32),
+            updated_at BIGINT NOT NULL
+        );
+
+        INSERT INTO search_index (entity_type, entity_id, search_text)
+        SELECT 'token', token_id, TRIM(COALESCE(name, '') || ' ' || COALESCE(description, ''))
+        FROM tokens
+        WHERE name IS NOT NULL OR description IS NOT NULL;
+        "#,
+    ),
+    (
+        "010_epoch_aggregates",
+        r#"
+        -- The epochs table from 003 was never filled; recreate it with per-epoch aggregates
+        DROP INDEX IF EXISTS idx_epochs_height;
+        DROP TABLE IF EXISTS epochs;
+
+        CREATE TABLE IF NOT EXISTS epochs (
+            epoch_index INTEGER PRIMARY KEY,
+            height_start INTEGER NOT NULL,
+            height_end INTEGER NOT NULL,
+            timestamp_start BIGINT NOT NULL,
+            timestamp_end BIGINT,
+            block_count INTEGER NOT NULL DEFAULT 0,
+            tx_count BIGINT NOT NULL DEFAULT 0,
+            total_fees BIGINT NOT NULL DEFAULT 0,       -- nanoERG paid to the miners' fee contract
+            total_emission BIGINT NOT NULL DEFAULT 0,   -- nanoERG released from the emission box
+            avg_difficulty DOUBLE,
+            avg_block_time DOUBLE                       -- milliseconds
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_epochs_height ON epochs(height_start, height_end);
+
+        -- Backfill from already indexed blocks (1024 blocks per epoch)
+        INSERT INTO epochs (
+            epoch_index, height_start, height_end, timestamp_start, timestamp_end, block_count,
+            tx_count, total_fees, total_emission, avg_difficulty, avg_block_time
+        )
+        SELECT b.epoch_index, b.epoch_index * 1024, b.height_end, b.timestamp_start, b.timestamp_end,
+               b.block_count, b.tx_count, COALESCE(f.total_fees, 0), COALESCE(e.total_emission, 0),
+               b.avg_difficulty, b.avg_block_time
+        FROM (
+            SELECT height // 1024 AS epoch_index,
+                   MAX(height) AS height_end,
+                   MIN(timestamp) AS timestamp_start,
+                   MAX(timestamp) AS timestamp_end,
+                   COUNT(*) AS block_count,
+                   SUM(tx_count) AS tx_count,
+                   AVG(difficulty) AS avg_difficulty,
+                   CASE WHEN COUNT(*) > 1
+                        THEN (MAX(timestamp) - MIN(timestamp)) / (COUNT(*) - 1)
+                   END AS avg_block_time
+            FROM blocks
+            WHERE main_chain = TRUE
+            GROUP BY 1
+        ) b
+        LEFT JOIN (
+            SELECT settlement_height // 1024 AS epoch_index, SUM(value) AS total_fees
+            FROM boxes
+            WHERE ergo_tree = '1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304'
+            GROUP BY 1
+        ) f ON f.epoch_index = b.epoch_index
+        LEFT JOIN (
+            SELECT t.inclusion_height // 1024 AS epoch_index,
+                   SUM(GREATEST(inp.value - out.value, 0)) AS total_emission
+            FROM transactions t
+            JOIN boxes out ON out.tx_id = t.tx_id AND out.output_index = 0
+            JOIN boxes inp ON inp.spent_tx_id = t.tx_id
+            WHERE t.index_in_block = 0
+            GROUP BY 1
+        ) e ON e.epoch_index = b.epoch_index;
+        "#,
+    ),
+    (
+        "011_webhooks",
+        r#"
+        -- Server-to-server notifications for boxes matching an address, template hash or token
+        CREATE SEQUENCE IF NOT EXISTS watch_id_seq;
+        CREATE TABLE IF NOT EXISTS watches (
+            watch_id BIGINT PRIMARY KEY DEFAULT nextval('watch_id_seq'),
+            watch_type VARCHAR(16) NOT NULL,   -- address, template, token
+            target TEXT NOT NULL,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            confirmations INTEGER NOT NULL DEFAULT 1,
+            created_at BIGINT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_watches_target ON watches(watch_type, target);
+
+        -- One row per (watch, box); rows that ran out of attempts stay as the dead-letter log
+        CREATE SEQUENCE IF NOT EXISTS webhook_delivery_id_seq;
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            delivery_id BIGINT PRIMARY KEY DEFAULT nextval('webhook_delivery_id_seq'),
+            watch_id BIGINT NOT NULL,
+            box_id VARCHAR(64) NOT NULL,
+            height INTEGER NOT NULL,
+            status VARCHAR(16) NOT NULL DEFAULT 'pending',  -- pending, delivered, dead
+            attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at BIGINT NOT NULL,
+            last_error TEXT,
+            created_at BIGINT NOT NULL,
+            delivered_at BIGINT,
+            UNIQUE (watch_id, box_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_watch ON webhook_deliveries(watch_id);
+        "#,
+    ),
+    (
+        "012_block_emission",
+        r#"
+        -- Emission split per main-chain block (nanoERG), with running totals after the block
+        CREATE TABLE IF NOT EXISTS block_emission (
+            height INTEGER PRIMARY KEY,
+            emission BIGINT NOT NULL,
+            miner_reward BIGINT NOT NULL,
+            foundation_reward BIGINT NOT NULL,
+            reemission_charge BIGINT NOT NULL,
+            reemission_payout BIGINT NOT NULL,
+            supply BIGINT NOT NULL,
+            circulating_supply BIGINT NOT NULL
+        );
+        "#,
+    ),
+    (
+        "013_token_metadata",
+        r#"
+        -- EIP-4 artwork metadata; tables with indexes can't be altered, so rebuild tokens
+        CREATE TABLE tokens_backup AS SELECT * FROM tokens;
+        DROP TABLE tokens;
+
+        CREATE TABLE tokens (
+            token_id VARCHAR(64) PRIMARY KEY,
+            box_id VARCHAR(64) NOT NULL,
+            emission_amount BIGINT NOT NULL,
+            name VARCHAR(512),
+            description TEXT,
+            token_type VARCHAR(64),
+            decimals INTEGER,
+            creation_height INTEGER NOT NULL,
+            asset_type VARCHAR(32),     -- R7: picture, audio, video, ...
+            content_hash VARCHAR(64),   -- R8: SHA-256 of the artwork
+            content_url TEXT,           -- R9: artwork link
+            cover_url TEXT              -- R9: cover image link (audio)
+        );
+
+        INSERT INTO tokens (token_id, box_id, emission_amount, name, description, token_type, decimals, creation_height)
+        SELECT token_id, box_id, emission_amount, name, description, token_type, decimals, creation_height
+        FROM tokens_backup;
+        DROP TABLE tokens_backup;
+
+        CREATE INDEX IF NOT EXISTS idx_tokens_name ON tokens(name);
+        CREATE INDEX IF NOT EXISTS idx_tokens_height ON tokens(creation_height);
+        "#,
+    ),
+    (
+        "014_token_events",
+        r#"
+        -- Net token supply changes per transaction: mints of new tokens and burns
+        -- (outputs carrying less of a token than the inputs)
+        CREATE TABLE IF NOT EXISTS token_events (
+            tx_id VARCHAR(64) NOT NULL,
+            token_id VARCHAR(64) NOT NULL,
+            height INTEGER NOT NULL,
+            event_type VARCHAR(8) NOT NULL,    -- mint, burn
+            amount BIGINT NOT NULL,            -- always positive
+            PRIMARY KEY (tx_id, token_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_token_events_token ON token_events(token_id, height);
+        CREATE INDEX IF NOT EXISTS idx_token_events_height ON token_events(height);
+
+        INSERT INTO token_events (tx_id, token_id, height, event_type, amount)
+        SELECT tx_id, token_id, MAX(height),
+               CASE WHEN SUM(amount) > 0 THEN 'mint' ELSE 'burn' END,
+               ABS(SUM(amount))
+        FROM (
+            SELECT b.tx_id, ba.token_id, b.settlement_height AS height, ba.amount
+            FROM box_assets ba
+            JOIN boxes b ON b.box_id = ba.box_id
+            UNION ALL
+            SELECT i.tx_id, ba.token_id, t.inclusion_height AS height, -ba.amount
+            FROM inputs i
+            JOIN box_assets ba ON ba.box_id = i.box_id
+            JOIN transactions t ON t.tx_id = i.tx_id
+        )
+        GROUP BY tx_id, token_id
+        HAVING SUM(amount) <> 0;
+        "#,
+    ),
+    (
+        "015_address_activity",
+        r#"
+        -- Net nanoERG change of each address in each transaction it took part in.
+        -- fee is the transaction's miners' fee for the address of its first input, else 0.
+        CREATE TABLE IF NOT EXISTS address_activity (
+            tx_id VARCHAR(64) NOT NULL,
+            address TEXT NOT NULL,
+            height INTEGER NOT NULL,
+            timestamp BIGINT NOT NULL,
+            value_change BIGINT NOT NULL,
+            fee BIGINT NOT NULL DEFAULT 0,
+            PRIMARY KEY (tx_id, address)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_address_activity_address ON address_activity(address);
+        CREATE INDEX IF NOT EXISTS idx_address_activity_height ON address_activity(height);
+
+        -- Net token change of each address in each transaction, zero changes left out
+        CREATE TABLE IF NOT EXISTS address_token_activity (
+            tx_id VARCHAR(64) NOT NULL,
+            address TEXT NOT NULL,
+            token_id VARCHAR(64) NOT NULL,
+            height INTEGER NOT NULL,
+            amount_change BIGINT NOT NULL,
+            PRIMARY KEY (tx_id, address, token_id)
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_address_token_activity_address ON address_token_activity(address);
+        CREATE INDEX IF NOT EXISTS idx_address_token_activity_height ON address_token_activity(height);
+
+        INSERT INTO address_activity (tx_id, address, height, timestamp, value_change, fee)
+        SELECT d.tx_id, d.address, t.inclusion_height, t.timestamp, SUM(d.delta),
+               CASE WHEN d.address = p.address THEN COALESCE(f.fee, 0) ELSE 0 END
+        FROM (
+            SELECT tx_id, address, value AS delta
+            FROM boxes
+            UNION ALL
+            SELECT i.tx_id, b.address, -b.value
+            FROM inputs i
+            JOIN boxes b ON b.box_id = i.box_id
+        ) d
+        JOIN transactions t ON t.tx_id = d.tx_id
+        LEFT JOIN (
+            SELECT i.tx_id, arg_min(b.address, i.input_index) AS address
+            FROM inputs i
+            JOIN boxes b ON b.box_id = i.box_id
+            GROUP BY i.tx_id
+        ) p ON p.tx_id = d.tx_id
+        LEFT JOIN (
+            SELECT tx_id, SUM(value) AS fee
+            FROM boxes
+            WHERE ergo_tree = '1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304'
+            GROUP BY tx_id
+        ) f ON f.tx_id = d.tx_id
+        GROUP BY d.tx_id, d.address, t.inclusion_height, t.timestamp, p.address, f.fee;
+
+        INSERT INTO address_token_activity (tx_id, address, token_id, height, amount_change)
+        SELECT d.tx_id, d.address, d.token_id, t.inclusion_height, SUM(d.amount)
+        FROM (
+            SELECT b.tx_id, b.address, ba.token_id, ba.amount
+            FROM box_assets ba
+            JOIN boxes b ON b.box_id = ba.box_id
+            UNION ALL
+            SELECT i.tx_id, b.address, ba.token_id, -ba.amount
+            FROM inputs i
+            JOIN boxes b ON b.box_id = i.box_id
+            JOIN box_assets ba ON ba.box_id = i.box_id
+        ) d
+        JOIN transactions t ON t.tx_id = d.tx_id
+        GROUP BY d.tx_id, d.address, d.token_id, t.inclusion_height
+        HAVING SUM(d.amount) <> 0;
+        "#,
+    ),
+    (
+        "016_transaction_fees",
+        r#"
+        -- nanoERG paid to the miners' fee contract; tables with indexes can't be altered,
+        -- so rebuild transactions
+        CREATE TABLE transactions_backup AS SELECT * FROM transactions;
+        DROP TABLE transactions;
+
+        CREATE TABLE transactions (
+            tx_id VARCHAR(64) PRIMARY KEY,
+            block_id VARCHAR(64) NOT NULL,
+            inclusion_height INTEGER NOT NULL,
+            timestamp BIGINT NOT NULL,
+            index_in_block INTEGER NOT NULL,
+            global_index BIGINT NOT NULL,
+            coinbase BOOLEAN NOT NULL DEFAULT FALSE,
+            size INTEGER NOT NULL,
+            input_count INTEGER NOT NULL,
+            output_count INTEGER NOT NULL,
+            fee BIGINT NOT NULL DEFAULT 0
+        );
+
+        INSERT INTO transactions (
+            tx_id, block_id, inclusion_height, timestamp, index_in_block, global_index,
+            coinbase, size, input_count, output_count, fee
+        )
+        SELECT t.tx_id, t.block_id, t.inclusion_height, t.timestamp, t.index_in_block, t.global_index,
+               t.coinbase, t.size, t.input_count, t.output_count, COALESCE(f.fee, 0)
+        FROM transactions_backup t
+        LEFT JOIN (
+            SELECT tx_id, SUM(value) AS fee
+            FROM boxes
+            WHERE ergo_tree = '1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304'
+            GROUP BY tx_id
+        ) f ON f.tx_id = t.tx_id;
+        DROP TABLE transactions_backup;
+
+        CREATE INDEX IF NOT EXISTS idx_tx_block ON transactions(block_id);
+        CREATE INDEX IF NOT EXISTS idx_tx_height ON transactions(inclusion_height);
+        CREATE INDEX IF NOT EXISTS idx_tx_timestamp ON transactions(timestamp);
+        CREATE INDEX IF NOT EXISTS idx_tx_global_index ON transactions(global_index);
+        "#,
+    ),
+];
+
+/// Secondary indexes of the tables fast sync appends to, as (name, SQL). They are
+/// dropped for the duration of a bulk load and built once it is done.
+pub const BULK_LOAD_INDEXES: &[(&str, &str)] = &[
+    ("idx_tx_block", "CREATE INDEX IF NOT EXISTS idx_tx_block ON transactions(block_id)"),
+    ("idx_tx_height", "CREATE INDEX IF NOT EXISTS idx_tx_height ON transactions(inclusion_height)"),
+    ("idx_tx_timestamp", "CREATE INDEX IF NOT EXISTS idx_tx_timestamp ON transactions(timestamp)"),
+    ("idx_tx_global_index", "CREATE INDEX IF NOT EXISTS idx_tx_global_index ON transactions(global_index)"),
+    ("idx_boxes_address", "CREATE INDEX IF NOT EXISTS idx_boxes_address ON boxes(address)"),
+    ("idx_boxes_ergo_tree_hash", "CREATE INDEX IF NOT EXISTS idx_boxes_ergo_tree_hash ON boxes(ergo_tree_template_hash)"),
+    ("idx_boxes_address_unspent", "CREATE INDEX IF NOT EXISTS idx_boxes_address_unspent ON boxes(address, spent_tx_id)"),
+    ("idx_boxes_creation_height", "CREATE INDEX IF NOT EXISTS idx_boxes_creation_height ON boxes(creation_height)"),
+    ("idx_boxes_global_index", "CREATE INDEX IF NOT EXISTS idx_boxes_global_index ON boxes(global_index)"),
+    ("idx_boxes_tx", "CREATE INDEX IF NOT EXISTS idx_boxes_tx ON boxes(tx_id)"),
+    ("idx_boxes_spent_tx", "CREATE INDEX IF NOT EXISTS idx_boxes_spent_tx ON boxes(spent_tx_id)"),
+    ("idx_box_assets_box", "CREATE INDEX IF NOT EXISTS idx_box_assets_box ON box_assets(box_id)"),
+    ("idx_box_assets_token", "CREATE INDEX IF NOT EXISTS idx_box_assets_token ON box_assets(token_id)"),
+    ("idx_inputs_tx", "CREATE INDEX IF NOT EXISTS idx_inputs_tx ON inputs(tx_id)"),
+    ("idx_inputs_box", "CREATE INDEX IF NOT EXISTS idx_inputs_box ON inputs(box_id)"),
+    ("idx_data_inputs_tx", "CREATE INDEX IF NOT EXISTS idx_data_inputs_tx ON data_inputs(tx_id)"),
+    ("idx_data_inputs_box", "CREATE INDEX IF NOT EXISTS idx_data_inputs_box ON data_inputs(box_id)"),
+    ("idx_address_stats_balance", "CREATE INDEX IF NOT EXISTS idx_address_stats_balance ON address_stats(balance DESC)"),
+    ("idx_token_holders_token", "CREATE INDEX IF NOT EXISTS idx_token_holders_token ON token_holders(token_id)"),
+    ("idx_token_holders_address", "CREATE INDEX IF NOT EXISTS idx_token_holders_address ON token_holders(address)"),
+    ("idx_token_holders_amount", "CREATE INDEX IF NOT EXISTS idx_token_holders_amount ON token_holders(token_id, amount DESC)"),
+    ("idx_box_registers_rendered", "CREATE INDEX IF NOT EXISTS idx_box_registers_rendered ON box_registers(register_id, rendered_value)"),
+    ("idx_box_registers_serialized", "CREATE INDEX IF NOT EXISTS idx_box_registers_serialized ON box_registers(register_id, serialized_value)"),
+    ("idx_script_constants_rendered", "CREATE INDEX IF NOT EXISTS idx_script_constants_rendered ON script_constants(constant_index, rendered_value)"),
+    ("idx_script_constants_serialized", "CREATE INDEX IF NOT EXISTS idx_script_constants_serialized ON script_constants(constant_index, serialized_value)"),
+    ("idx_address_activity_address", "CREATE INDEX IF NOT EXISTS idx_address_activity_address ON address_activity(address)"),
+    ("idx_address_activity_height", "CREATE INDEX IF NOT EXISTS idx_address_activity_height ON address_activity(height)"),
+    ("idx_address_token_activity_address", "CREATE INDEX IF NOT EXISTS idx_address_token_activity_address ON address_token_activity(address)"),
+    ("idx_address_token_activity_height", "CREATE INDEX IF NOT EXISTS idx_address_token_activity_height ON address_token_activity(height)"),
+    ("idx_token_events_token", "CREATE INDEX IF NOT EXISTS idx_token_events_token ON token_events(token_id, height)"),
+    ("idx_token_events_height", "CREATE INDEX IF NOT EXISTS idx_token_events_height ON token_events(height)"),
 ];
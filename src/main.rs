@@ -1,9 +1,11 @@
 use anyhow::Result;
 use axum::{
+    middleware,
     routing::{get, post},
     Router,
 };
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use std::sync::Arc;
 use tower_http::{
     compression::CompressionLayer,
@@ -21,6 +23,7 @@ mod utils;
 
 use db::Database;
 use sync::SyncService;
+use utils::network::Network;
 
 #[derive(Parser, Debug, Clone)]
 #[command(name = "ergo-index")]
@@ -50,6 +53,14 @@ pub struct Config {
     #[arg(long, env = "SYNC_INTERVAL", default_value = "10")]
     pub sync_interval: u64,
 
+    /// Mempool poll interval in seconds (0 disables the mempool index)
+    #[arg(long, env = "MEMPOOL_INTERVAL", default_value = "5")]
+    pub mempool_interval: u64,
+
+    /// Webhook dispatch interval in seconds (0 disables webhook delivery)
+    #[arg(long, env = "WEBHOOK_INTERVAL", default_value = "5")]
+    pub webhook_interval: u64,
+
     /// Enable embedded node mode
     #[arg(long, env = "EMBEDDED_NODE", default_value = "false")]
     pub embedded_node: bool,
@@ -58,9 +69,38 @@ pub struct Config {
     #[arg(long, env = "NODE_API_KEY")]
     pub node_api_key: Option<String>,
 
+    /// Key expected in the `api_key` header by admin routes (label edits, watch management)
+    #[arg(long, env = "ADMIN_API_KEY")]
+    pub admin_api_key: Option<String>,
+
     /// Network: mainnet or testnet
     #[arg(long, env = "NETWORK", default_value = "mainnet")]
-    pub network: String,
+    pub network: Network,
+
+    /// Seconds an API database query may run before it is cancelled with a 503 (0 disables)
+    #[arg(long, env = "QUERY_TIMEOUT", default_value = "30")]
+    pub query_timeout: u64,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Index blocks from a node block archive (newline-delimited block JSON, optionally
+    /// gzip-compressed), then continue with live sync from the node
+    Import {
+        /// Archive file
+        path: PathBuf,
+
+        /// Last height to import (default: the whole archive)
+        #[arg(long)]
+        to: Option<i64>,
+
+        /// Exit after the import instead of starting the server and live sync
+        #[arg(long)]
+        exit: bool,
+    },
 }
 
 pub struct AppState {
@@ -99,6 +139,7 @@ async fn main() -> Result<()> {
     // Initialize database
     let db = Database::new(&config.database)?;
     db.migrate()?;
+    db.rebuild_search_index()?;
     tracing::info!("Database initialized");
 
     // Initialize sync service
@@ -108,8 +149,19 @@ async fn main() -> Result<()> {
         db.clone(),
         config.sync_batch_size,
         config.node_api_key.clone(),
+        config.network,
     ));
 
+    sync_service.check_network().await?;
+
+    // Offline bootstrap; live sync continues from the imported tip
+    if let Some(Command::Import { path, to, exit }) = &config.command {
+        sync_service.import_archive(path, *to).await?;
+        if *exit {
+            return Ok(());
+        }
+    }
+
     // Start background sync
     let sync_handle = sync_service.clone();
     let sync_interval = config.sync_interval;
@@ -117,6 +169,24 @@ async fn main() -> Result<()> {
         sync_handle.run(sync_interval).await;
     });
 
+    // Start mempool indexer
+    if config.mempool_interval > 0 {
+        let mempool_handle = sync_service.clone();
+        let mempool_interval = config.mempool_interval;
+        tokio::spawn(async move {
+            mempool_handle.run_mempool(mempool_interval).await;
+        });
+    }
+
+    // Start webhook dispatcher
+    if config.webhook_interval > 0 {
+        let webhook_handle = sync_service.clone();
+        let webhook_interval = config.webhook_interval;
+        tokio::spawn(async move {
+            webhook_handle.run_webhooks(webhook_interval).await;
+        });
+    }
+
     let state = Arc::new(AppState {
         db,
         config: config.clone(),
@@ -128,7 +198,11 @@ async fn main() -> Result<()> {
         // API v1 routes
         .nest("/api/v1", api::routes(state.clone()))
         // Status endpoint
-        .route("/status", get(api::status::get_status))
+        .route(
+            "/status",
+            get(api::status::get_status)
+                .layer(middleware::from_fn_with_state(state.clone(), api::blocking_reads)),
+        )
         // Health check
         .route("/health", get(|| async { "OK" }))
         // Swagger UI
@@ -47,6 +47,8 @@ pub struct Transaction {
     pub global_index: i64,
     pub coinbase: bool,
     pub size: i32,
+    /// nanoERG paid to the miners' fee contract
+    pub fee: i64,
     pub inputs: Vec<Input>,
     pub outputs: Vec<Output>,
     pub data_inputs: Vec<DataInput>,
@@ -95,6 +97,7 @@ pub struct Output {
     pub settlement_height: i64,
     pub ergo_tree: String,
     pub assets: Vec<BoxAsset>,
+    /// Register id to `{serializedValue, sigmaType, renderedValue}`
     #[serde(skip_serializing_if = "Option::is_none")]
     pub additional_registers: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -131,6 +134,34 @@ pub struct Token {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub decimals: Option<i32>,
     pub creation_height: i64,
+    /// EIP-4 asset type from R7: picture, audio, video, artwork-collection,
+    /// file-attachments or membership-threshold
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_type: Option<String>,
+    /// SHA-256 of the NFT content (R8, hex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// NFT content link (R9)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_url: Option<String>,
+    /// Cover image link of audio NFTs (R9)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_url: Option<String>,
+    /// Total destroyed by transactions whose outputs carry less than their inputs
+    pub burned_amount: i64,
+    /// Emission amount less burned amount
+    pub circulating_supply: i64,
+    pub holder_count: i64,
+}
+
+/// A transaction that destroyed some amount of a token
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenBurn {
+    pub tx_id: String,
+    pub height: i64,
+    pub timestamp: i64,
+    pub amount: i64,
 }
 
 /// Token summary
@@ -154,6 +185,64 @@ pub struct AddressInfo {
     pub last_seen_height: Option<i64>,
 }
 
+/// Rich list entry: an address ranked by confirmed nanoERG balance
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RichListEntry {
+    pub rank: i64,
+    pub address: String,
+    pub balance: i64,
+    pub tx_count: i64,
+}
+
+/// Totals over an address's confirmed transactions. Amounts are net per
+/// transaction: change returned to the address is not counted as received.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressSummary {
+    pub address: String,
+    pub tx_count: i64,
+    /// nanoERG gained in transactions that increased the balance
+    pub total_received: i64,
+    /// nanoERG lost in transactions that decreased the balance, fees included
+    pub total_sent: i64,
+    /// Miners' fees of transactions whose first input was the address's box
+    pub fees_paid: i64,
+    pub first_activity: Option<i64>,
+    pub last_activity: Option<i64>,
+    pub first_activity_height: Option<i64>,
+    pub last_activity_height: Option<i64>,
+    pub tokens: Vec<TokenFlow>,
+}
+
+/// Net token amounts an address received and sent
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenFlow {
+    pub token_id: String,
+    pub name: Option<String>,
+    pub decimals: Option<i32>,
+    pub received: i64,
+    pub sent: i64,
+    pub adjusted_received: String,
+    pub adjusted_sent: String,
+}
+
+/// Another address on the opposite side of an address's transactions
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Counterparty {
+    pub address: String,
+    pub label: Option<String>,
+    pub tx_count: i64,
+    /// nanoERG the counterparty gained in transactions where the address paid
+    pub sent: i64,
+    /// nanoERG the counterparty gave up in transactions where the address gained
+    pub received: i64,
+    pub first_activity: i64,
+    pub last_activity: i64,
+}
+
 /// Balance information
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
@@ -170,12 +259,33 @@ pub struct BalanceResponse {
     pub unconfirmed: Balance,
 }
 
+/// Confirmed balance of an address as of a block height
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoricalBalance {
+    pub height: i64,
+    /// Timestamp of the block at `height`; in a history, the start of the bucket
+    pub timestamp: Option<i64>,
+    pub nano_ergs: i64,
+    pub tokens: Vec<TokenBalance>,
+}
+
+/// Balance at the end of each daily or weekly bucket in which it changed
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceHistory {
+    pub interval: String,
+    pub items: Vec<HistoricalBalance>,
+}
+
 /// Token balance
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TokenBalance {
     pub token_id: String,
     pub amount: i64,
+    /// `amount` scaled by `decimals`, as a decimal string
+    pub adjusted_amount: String,
     pub decimals: Option<i32>,
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -222,6 +332,75 @@ pub struct SearchResult {
     pub address: Option<AddressInfo>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token: Option<TokenSummary>,
+    /// Address label, when the matched address has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Text relevance; absent for exact id/height matches, which always rank first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+}
+
+/// Human-readable label attached to an address
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressLabel {
+    pub address: String,
+    pub label: String,
+    /// Free-form category, e.g. `miner`, `exchange`, `contract`
+    pub category: Option<String>,
+}
+
+/// Webhook watch on an address, ErgoTree template hash or token id
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Watch {
+    pub id: i64,
+    /// `address`, `template` or `token`
+    pub watch_type: String,
+    pub target: String,
+    pub url: String,
+    /// Blocks a matching box must be buried under before it is delivered
+    pub confirmations: i32,
+    pub created_at: i64,
+}
+
+/// Body of POST /watches
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchRequest {
+    pub watch_type: String,
+    pub target: String,
+    pub url: String,
+    /// HMAC-SHA256 key for the `X-Ergo-Index-Signature` header (at least 16 characters)
+    pub secret: String,
+    #[serde(default)]
+    pub confirmations: Option<i32>,
+}
+
+/// A queued, delivered or dead-lettered webhook notification
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub watch_id: i64,
+    pub box_id: String,
+    pub height: i64,
+    /// `pending`, `delivered` or `dead`
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: i64,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub delivered_at: Option<i64>,
+}
+
+/// Body of PUT /labels/{address}
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LabelRequest {
+    pub label: String,
+    #[serde(default)]
+    pub category: Option<String>,
 }
 
 /// API info
@@ -242,6 +421,13 @@ pub struct Pagination {
     pub offset: i64,
     #[serde(default = "default_limit")]
     pub limit: i64,
+    /// `nextCursor` from the previous page; when set, `offset` is ignored
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Whether to count the total; defaults to on for offset paging and off
+    /// when paging by cursor
+    #[serde(default)]
+    pub with_total: Option<bool>,
 }
 
 fn default_offset() -> i64 { 0 }
@@ -252,10 +438,18 @@ impl Default for Pagination {
         Self {
             offset: 0,
             limit: 20,
+            cursor: None,
+            with_total: None,
         }
     }
 }
 
+impl Pagination {
+    pub fn include_total(&self) -> bool {
+        self.with_total.unwrap_or(self.cursor.is_none())
+    }
+}
+
 /// Paginated response wrapper
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[aliases(
@@ -263,12 +457,32 @@ impl Default for Pagination {
     PaginatedTransactions = PaginatedResponse<TransactionSummary>,
     PaginatedOutputs = PaginatedResponse<Output>,
     PaginatedTokens = PaginatedResponse<TokenSummary>,
-    PaginatedEpochs = PaginatedResponse<Epoch>
+    PaginatedEpochs = PaginatedResponse<Epoch>,
+    PaginatedRichList = PaginatedResponse<RichListEntry>,
+    PaginatedCounterparties = PaginatedResponse<Counterparty>,
+    PaginatedSearchResults = PaginatedResponse<SearchResult>,
+    PaginatedLabels = PaginatedResponse<AddressLabel>,
+    PaginatedWebhookDeliveries = PaginatedResponse<WebhookDelivery>,
+    PaginatedTokenBurns = PaginatedResponse<TokenBurn>
 )]
 #[serde(rename_all = "camelCase")]
 pub struct PaginatedResponse<T> {
     pub items: Vec<T>,
-    pub total: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    /// Pass as `cursor` to fetch the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> PaginatedResponse<T> {
+    pub fn new(items: Vec<T>, total: i64) -> Self {
+        Self {
+            items,
+            total: Some(total),
+            next_cursor: None,
+        }
+    }
 }
 
 /// Box search query
@@ -277,8 +491,10 @@ pub struct PaginatedResponse<T> {
 pub struct BoxSearchQuery {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ergo_tree_template_hash: Option<String>,
+    /// Register id to value, e.g. `{"R4": "0e03..."}`; matches rendered or serialized value
     #[serde(skip_serializing_if = "Option::is_none")]
     pub registers: Option<serde_json::Value>,
+    /// ErgoTree constant index to value, e.g. `{"0": "1000"}`
     #[serde(skip_serializing_if = "Option::is_none")]
     pub constants: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -295,6 +511,74 @@ pub struct Epoch {
     pub timestamp_start: i64,
     pub timestamp_end: Option<i64>,
     pub block_count: i32,
+    pub tx_count: i64,
+    /// nanoERG paid to the miners' fee contract
+    pub total_fees: i64,
+    /// nanoERG released from the emission box
+    pub total_emission: i64,
+    pub avg_difficulty: Option<f64>,
+    /// Average time between blocks in milliseconds
+    pub avg_block_time: Option<f64>,
+}
+
+/// Distribution of transaction fees in nanoERG; coinbase transactions are left out
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeePercentiles {
+    pub tx_count: i64,
+    pub min: Option<i64>,
+    pub p25: Option<i64>,
+    pub median: Option<i64>,
+    pub p75: Option<i64>,
+    pub p90: Option<i64>,
+    pub max: Option<i64>,
+    pub total: i64,
+}
+
+/// Fees of the transactions in one block
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockFees {
+    pub height: i64,
+    pub timestamp: i64,
+    pub fees: FeePercentiles,
+}
+
+/// Fees of the transactions included during one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeBucket {
+    /// Start of the bucket
+    pub timestamp: i64,
+    pub fees: FeePercentiles,
+}
+
+/// Fee market over recent blocks and time buckets, newest first
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeStats {
+    pub blocks: Vec<BlockFees>,
+    pub interval: String,
+    pub buckets: Vec<FeeBucket>,
+}
+
+/// Suggested fees in nanoERG from recent blocks and the mempool
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeEstimate {
+    /// Likely to be included once the mempool clears
+    pub slow: i64,
+    pub standard: i64,
+    /// Likely to be included in the next block
+    pub fast: i64,
+    /// Node default minimum fee
+    pub min_fee: i64,
+    pub blocks_sampled: i64,
+    pub recent: FeePercentiles,
+    pub mempool: FeePercentiles,
+    pub avg_block_tx_count: f64,
+    /// More transactions are waiting than a recent block holds on average
+    pub congested: bool,
 }
 
 /// Table size info
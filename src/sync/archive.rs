@@ -0,0 +1,216 @@
+//! Block archives for offline bootstrap
+//!
+//! An archive holds blocks in the node's block JSON format (as served by
+//! `GET /blocks/{headerId}`), one block per line in height order. Gzip-compressed
+//! archives are recognised by their magic bytes.
+
+use anyhow::{Context, Result};
+use flate2::read::MultiGzDecoder;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::Path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The blocks of an archive in file order. Blank lines are skipped.
+pub struct ArchiveReader {
+    lines: Lines<Box<dyn BufRead>>,
+    line_number: usize,
+}
+
+impl ArchiveReader {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open archive {}", path.display()))?;
+        let mut reader = BufReader::new(file);
+        let gzip = reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+
+        let reader: Box<dyn BufRead> = if gzip {
+            Box::new(BufReader::new(MultiGzDecoder::new(reader)))
+        } else {
+            Box::new(reader)
+        };
+
+        Ok(Self {
+            lines: reader.lines(),
+            line_number: 0,
+        })
+    }
+}
+
+impl Iterator for ArchiveReader {
+    type Item = Result<Value>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(
+                serde_json::from_str(&line)
+                    .with_context(|| format!("Invalid block JSON on line {}", self.line_number)),
+            );
+        }
+    }
+}
+
+/// Height from a block's header
+pub fn block_height(block: &Value) -> Option<i64> {
+    block.get("header")?.get("height")?.as_i64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::test_fixtures::{block, indexed_db, output, p2pk_tree, tx};
+    use crate::sync::SyncService;
+    use crate::utils::network::Network;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use serde_json::json;
+    use std::io::Write;
+
+    /// Two blocks: a box for `alice`, then a transaction spending it that mints a
+    /// token for `bob` and returns change to `alice`
+    fn archive_blocks() -> Vec<Value> {
+        let alice = p2pk_tree("11");
+        let bob = p2pk_tree("22");
+        vec![
+            block(
+                1,
+                json!([tx(
+                    "tx1",
+                    &["emission"],
+                    vec![output("box1", 1000, &alice, 1, json!([]))]
+                )]),
+            ),
+            block(
+                2,
+                json!([tx(
+                    "tx2",
+                    &["box1"],
+                    vec![
+                        output(
+                            "box2",
+                            600,
+                            &bob,
+                            2,
+                            json!([{ "tokenId": "box1", "amount": 50 }])
+                        ),
+                        output("box3", 400, &alice, 2, json!([])),
+                    ],
+                )]),
+            ),
+        ]
+    }
+
+    fn write_archive(name: &str, gzip: bool) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("ergo-index-{}-{}", std::process::id(), name));
+        let mut ndjson = String::new();
+        for block in archive_blocks() {
+            ndjson.push_str(&block.to_string());
+            ndjson.push_str("\n\n");
+        }
+
+        let mut file = File::create(&path).unwrap();
+        if gzip {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(ndjson.as_bytes()).unwrap();
+            file.write_all(&encoder.finish().unwrap()).unwrap();
+        } else {
+            file.write_all(ndjson.as_bytes()).unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn test_reads_plain_and_gzip_archives() {
+        for (name, gzip) in [("plain.ndjson", false), ("blocks.ndjson.gz", true)] {
+            let path = write_archive(name, gzip);
+            let heights: Vec<i64> = ArchiveReader::open(&path)
+                .unwrap()
+                .map(|block| block_height(&block.unwrap()).unwrap())
+                .collect();
+            std::fs::remove_file(&path).ok();
+            assert_eq!(heights, vec![1, 2]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_import_archive_without_node() {
+        let path = write_archive("import.ndjson.gz", true);
+        let db = indexed_db();
+        // The synthetic genesis block is not mainnet's, so the archive is refused there
+        let mainnet = SyncService::new(
+            vec!["http://127.0.0.1:1".to_string()],
+            db.clone(),
+            50,
+            None,
+            Network::Mainnet,
+        );
+        assert!(mainnet.import_archive(&path, None).await.is_err());
+        assert_eq!(db.get_sync_height().unwrap(), 0);
+
+        let sync = SyncService::new(
+            vec!["http://127.0.0.1:1".to_string()],
+            db.clone(),
+            50,
+            None,
+            Network::Testnet,
+        );
+        let height = sync.import_archive(&path, None).await.unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(height, 2);
+        assert_eq!(db.get_sync_height().unwrap(), 2);
+
+        let spent: Option<String> = db
+            .query_one(
+                "SELECT spent_tx_id FROM boxes WHERE box_id = 'box1'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(spent.as_deref(), Some("tx2"));
+
+        let balances: Vec<i64> = db
+            .query_all(
+                "SELECT balance FROM address_stats ORDER BY balance",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(balances, vec![400, 600]);
+
+        let holders: Vec<(String, i64)> = db
+            .query_all("SELECT token_id, amount FROM token_holders", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(holders, vec![("box1".to_string(), 50)]);
+
+        let mint: Option<(String, i64)> = db
+            .query_one(
+                "SELECT event_type, amount FROM token_events WHERE tx_id = 'tx2'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(mint, Some(("mint".to_string(), 50)));
+
+        // Blocks at or below the tip are skipped on a second run
+        let path = write_archive("again.ndjson", false);
+        assert_eq!(sync.import_archive(&path, None).await.unwrap(), 2);
+        std::fs::remove_file(&path).ok();
+        let boxes: Option<i64> = db
+            .query_one("SELECT COUNT(*) FROM boxes", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(boxes, Some(3));
+    }
+}
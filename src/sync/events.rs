@@ -0,0 +1,131 @@
+//! Chain events published after each committed sync batch, consumed by WebSocket clients
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before it starts lagging
+const EVENT_BUFFER: usize = 4096;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ChainEvent {
+    #[serde(rename_all = "camelCase")]
+    NewBlock {
+        id: String,
+        height: i64,
+        timestamp: i64,
+        tx_count: i32,
+        miner_address: Option<String>,
+    },
+    /// Blocks above `fork_height` were orphaned and will be replaced
+    #[serde(rename_all = "camelCase")]
+    Reorg { fork_height: i64, orphaned: i64 },
+    /// Net nanoERG change of an address in a confirmed transaction
+    #[serde(rename_all = "camelCase")]
+    AddressActivity {
+        address: String,
+        tx_id: String,
+        height: i64,
+        balance_change: i64,
+    },
+    /// Net token amount change of an address in a confirmed transaction
+    #[serde(rename_all = "camelCase")]
+    TokenTransfer {
+        token_id: String,
+        address: String,
+        tx_id: String,
+        height: i64,
+        amount: i64,
+    },
+    /// A confirmed output whose ErgoTree has the given template hash
+    #[serde(rename_all = "camelCase")]
+    TemplateMatch {
+        template_hash: String,
+        box_id: String,
+        tx_id: String,
+        address: String,
+        value: i64,
+        height: i64,
+    },
+    /// A transaction entered the mempool
+    #[serde(rename_all = "camelCase")]
+    MempoolTransaction { tx_id: String, addresses: Vec<String> },
+}
+
+/// What a WebSocket client can subscribe to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(tag = "topic", rename_all = "camelCase")]
+pub enum Topic {
+    Blocks,
+    Reorgs,
+    Mempool,
+    /// Confirmed activity and mempool transactions touching the address
+    Address { address: String },
+    Token {
+        #[serde(rename = "tokenId")]
+        token_id: String,
+    },
+    Template {
+        #[serde(rename = "templateHash")]
+        template_hash: String,
+    },
+}
+
+impl ChainEvent {
+    pub fn matches(&self, topic: &Topic) -> bool {
+        match (self, topic) {
+            (ChainEvent::NewBlock { .. }, Topic::Blocks) => true,
+            (ChainEvent::Reorg { .. }, Topic::Reorgs) => true,
+            (ChainEvent::MempoolTransaction { .. }, Topic::Mempool) => true,
+            (ChainEvent::MempoolTransaction { addresses, .. }, Topic::Address { address }) => {
+                addresses.contains(address)
+            }
+            (ChainEvent::AddressActivity { address, .. }, Topic::Address { address: wanted }) => {
+                address == wanted
+            }
+            (ChainEvent::TokenTransfer { token_id, .. }, Topic::Token { token_id: wanted }) => {
+                token_id == wanted
+            }
+            (
+                ChainEvent::TemplateMatch { template_hash, .. },
+                Topic::Template { template_hash: wanted },
+            ) => template_hash == wanted,
+            _ => false,
+        }
+    }
+}
+
+/// Fan-out of chain events to any number of subscribers. Publishing never
+/// blocks sync: slow subscribers miss events and are told how many.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<ChainEvent>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<ChainEvent>> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, events: Vec<ChainEvent>) {
+        // No receivers is the normal case without WebSocket clients
+        if self.sender.receiver_count() == 0 {
+            return;
+        }
+        for event in events {
+            let _ = self.sender.send(Arc::new(event));
+        }
+    }
+}
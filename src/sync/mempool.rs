@@ -0,0 +1,191 @@
+//! Mempool indexer keeping a local copy of the node's unconfirmed transactions
+
+use anyhow::Result;
+use duckdb::{params, Connection};
+use std::collections::HashSet;
+
+use super::events::ChainEvent;
+use super::node_client::MempoolTransaction;
+use crate::db::Database;
+use crate::utils::ergo_tree;
+use crate::utils::network::Network;
+
+pub struct MempoolIndexer {
+    db: Database,
+    network: Network,
+}
+
+impl MempoolIndexer {
+    pub fn new(db: Database, network: Network) -> Self {
+        Self { db, network }
+    }
+
+    /// Bring the indexed mempool in line with a snapshot of the node's pool.
+    /// Transactions that left the pool are dropped, new ones are indexed and
+    /// ones we already know keep their `first_seen` time.
+    /// Returns an event per added transaction and the number removed.
+    pub fn apply_snapshot(&self, txs: &[MempoolTransaction]) -> Result<(Vec<ChainEvent>, usize)> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut added = Vec::new();
+        let mut removed = 0;
+
+        self.db.execute_transaction(|conn| {
+            let known: HashSet<String> = {
+                let mut stmt = conn.prepare("SELECT tx_id FROM mempool_transactions")?;
+                let mut rows = stmt.query([])?;
+                let mut ids = HashSet::new();
+                while let Some(row) = rows.next()? {
+                    ids.insert(row.get::<_, String>(0)?);
+                }
+                ids
+            };
+            let current: HashSet<&str> = txs.iter().map(|tx| tx.id.as_str()).collect();
+
+            for tx_id in known.iter().filter(|id| !current.contains(id.as_str())) {
+                remove_tx(conn, tx_id)?;
+                removed += 1;
+            }
+
+            let new_txs: Vec<&MempoolTransaction> =
+                txs.iter().filter(|tx| !known.contains(&tx.id)).collect();
+
+            // Outputs first so inputs spending other mempool outputs can be resolved
+            let mut output_addresses = Vec::with_capacity(new_txs.len());
+            for tx in &new_txs {
+                conn.execute(
+                    "INSERT INTO mempool_transactions (tx_id, tx_data, first_seen, size)
+                     VALUES (?, ?, ?, ?)",
+                    params![tx.id, serde_json::to_string(tx)?, now, tx.size],
+                )?;
+                output_addresses.push(insert_outputs(conn, tx, self.network)?);
+            }
+            for (tx, mut addresses) in new_txs.iter().zip(output_addresses) {
+                addresses.extend(insert_inputs(conn, tx)?);
+                addresses.sort();
+                addresses.dedup();
+                added.push(ChainEvent::MempoolTransaction {
+                    tx_id: tx.id.clone(),
+                    addresses,
+                });
+            }
+
+            Ok(())
+        })?;
+
+        Ok((added, removed))
+    }
+}
+
+/// Drop pooled transactions that blocks between `from_height` and `to_height`
+/// confirmed, or invalidated by spending one of their inputs. Runs inside the
+/// block's database transaction, so the pool never counts a confirmed box twice.
+pub fn remove_confirmed(conn: &Connection, from_height: i64, to_height: i64) -> Result<usize> {
+    let pooled: i64 = conn.query_row("SELECT COUNT(*) FROM mempool_transactions", [], |row| row.get(0))?;
+    if pooled == 0 {
+        return Ok(0);
+    }
+
+    let stale: Vec<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT m.tx_id FROM mempool_transactions m
+             JOIN transactions t ON t.tx_id = m.tx_id
+             WHERE t.inclusion_height BETWEEN ? AND ?
+             UNION
+             SELECT mi.tx_id FROM mempool_inputs mi
+             JOIN boxes b ON b.box_id = mi.box_id
+             WHERE b.spent_height BETWEEN ? AND ?",
+        )?;
+        let rows = stmt.query_map(params![from_height, to_height, from_height, to_height], |row| row.get(0))?;
+        rows.collect::<duckdb::Result<_>>()?
+    };
+
+    for tx_id in &stale {
+        remove_tx(conn, tx_id)?;
+    }
+    Ok(stale.len())
+}
+
+fn remove_tx(conn: &Connection, tx_id: &str) -> Result<()> {
+    conn.execute(
+        "DELETE FROM mempool_assets WHERE box_id IN (
+             SELECT box_id FROM mempool_outputs WHERE tx_id = ?
+         )",
+        [tx_id],
+    )?;
+    conn.execute("DELETE FROM mempool_outputs WHERE tx_id = ?", [tx_id])?;
+    conn.execute("DELETE FROM mempool_inputs WHERE tx_id = ?", [tx_id])?;
+    conn.execute("DELETE FROM mempool_transactions WHERE tx_id = ?", [tx_id])?;
+    Ok(())
+}
+
+/// Returns the output addresses
+fn insert_outputs(conn: &Connection, tx: &MempoolTransaction, network: Network) -> Result<Vec<String>> {
+    let mut addresses = Vec::with_capacity(tx.outputs.len());
+    for (idx, output) in tx.outputs.iter().enumerate() {
+        let address = ergo_tree::ergo_tree_to_address(&output.ergo_tree, network)
+            .unwrap_or_else(|| output.ergo_tree.clone());
+        let template_hash = ergo_tree::ergo_tree_template_hash(&output.ergo_tree);
+        let registers = output.additional_registers.as_ref().map(|r| r.to_string());
+
+        conn.execute(
+            "INSERT INTO mempool_outputs (
+                box_id, tx_id, output_index, ergo_tree, ergo_tree_template_hash,
+                address, value, creation_height, additional_registers
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT DO NOTHING",
+            params![
+                output.box_id,
+                tx.id,
+                idx as i32,
+                output.ergo_tree,
+                template_hash,
+                address,
+                output.value,
+                output.creation_height,
+                registers
+            ],
+        )?;
+
+        for (asset_idx, asset) in output.assets.iter().flatten().enumerate() {
+            conn.execute(
+                "INSERT INTO mempool_assets (box_id, token_id, amount, asset_index)
+                 VALUES (?, ?, ?, ?)
+                 ON CONFLICT DO NOTHING",
+                params![output.box_id, asset.token_id, asset.amount, asset_idx as i32],
+            )?;
+        }
+        addresses.push(address);
+    }
+    Ok(addresses)
+}
+
+/// Returns the addresses of the spent boxes that could be resolved
+fn insert_inputs(conn: &Connection, tx: &MempoolTransaction) -> Result<Vec<String>> {
+    let mut addresses = Vec::with_capacity(tx.inputs.len());
+    for (idx, input) in tx.inputs.iter().enumerate() {
+        // The spent box is either confirmed or created by another mempool tx
+        let spent: Option<(String, i64)> = conn
+            .query_row(
+                "SELECT address, value FROM boxes WHERE box_id = ?
+                 UNION ALL
+                 SELECT address, value FROM mempool_outputs WHERE box_id = ?
+                 LIMIT 1",
+                params![input.box_id, input.box_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .ok();
+        let (address, value) = match spent {
+            Some((address, value)) => (Some(address), Some(value)),
+            None => (None, None),
+        };
+
+        conn.execute(
+            "INSERT INTO mempool_inputs (tx_id, box_id, input_index, address, value)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT DO NOTHING",
+            params![tx.id, input.box_id, idx as i32, address, value],
+        )?;
+        addresses.extend(address);
+    }
+    Ok(addresses)
+}
@@ -1,17 +1,33 @@
 //! Synchronization service for indexing blockchain data
 
+pub mod archive;
+mod events;
+mod mempool;
 mod node_client;
 mod processor;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
+mod webhooks;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use futures::stream::{self, StreamExt};
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock, Semaphore};
 
 use crate::db::Database;
+use crate::utils::emission::EmissionRules;
+use crate::utils::ergo_tree::ergo_tree_template_hash;
+use crate::utils::network::Network;
+use archive::ArchiveReader;
+pub use events::{ChainEvent, Topic};
+use events::EventBus;
 pub use node_client::NodeClient;
-use processor::BlockProcessor;
+use mempool::MempoolIndexer;
+pub use webhooks::{allow_private_targets, resolve_webhook_url};
+use webhooks::WebhookDispatcher;
+use processor::{insert_block_emission, update_epoch, BlockProcessor, EPOCH_LENGTH};
 
 /// Maximum concurrent HTTP requests to nodes (configurable via SYNC_CONCURRENT_FETCHES)
 fn max_concurrent_fetches() -> usize {
@@ -21,6 +37,42 @@ fn max_concurrent_fetches() -> usize {
         .unwrap_or(20)
 }
 
+/// Transactions requested per mempool page
+const MEMPOOL_PAGE_SIZE: i32 = 100;
+
+/// Upper bound on mempool transactions indexed per poll (configurable via MEMPOOL_MAX_TXS)
+fn mempool_max_txs() -> usize {
+    std::env::var("MEMPOOL_MAX_TXS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10_000)
+}
+
+/// Deepest chain reorganization we are willing to roll back (configurable via SYNC_MAX_REORG_DEPTH)
+fn max_reorg_depth() -> i64 {
+    std::env::var("SYNC_MAX_REORG_DEPTH")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(200)
+}
+
+/// Blocks below the node's tip beyond which sync switches to bulk loading
+/// (configurable via SYNC_FAST_DEPTH, 0 disables fast sync)
+fn fast_sync_depth() -> i64 {
+    std::env::var("SYNC_FAST_DEPTH")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10_000)
+}
+
+/// Blocks per fast sync batch, all loaded in one transaction (configurable via SYNC_FAST_BATCH_SIZE)
+fn fast_sync_batch_size() -> i64 {
+    std::env::var("SYNC_FAST_BATCH_SIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1000)
+}
+
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SyncStatus {
@@ -63,7 +115,12 @@ pub struct SyncService {
     nodes: Vec<NodeClient>,
     db: Database,
     batch_size: u32,
+    network: Network,
+    emission: &'static EmissionRules,
     processor: Mutex<BlockProcessor>,
+    mempool: MempoolIndexer,
+    events: EventBus,
+    webhooks: WebhookDispatcher,
 
     // Sync state
     is_syncing: AtomicBool,
@@ -74,6 +131,9 @@ pub struct SyncService {
     sync_start_time: AtomicU64,
     repair_height: AtomicI64,
     repair_total_height: AtomicI64,
+    search_index_docs: AtomicI64,
+    /// Secondary indexes are dropped for fast sync
+    bulk_loading: AtomicBool,
     last_error: RwLock<Option<String>>,
     node_statuses: RwLock<Vec<NodeStatus>>,
 }
@@ -84,7 +144,9 @@ impl SyncService {
         db: Database,
         batch_size: u32,
         api_key: Option<String>,
+        network: Network,
     ) -> Self {
+        let emission = EmissionRules::for_network(network);
         let nodes: Vec<NodeClient> = node_urls
             .iter()
             .map(|url| NodeClient::new(url.clone(), api_key.clone()))
@@ -110,15 +172,22 @@ impl SyncService {
             .collect();
 
         Self {
-            processor: Mutex::new(BlockProcessor::new(db.clone())),
+            processor: Mutex::new(BlockProcessor::new(db.clone(), network)),
+            mempool: MempoolIndexer::new(db.clone(), network),
+            events: EventBus::new(),
+            webhooks: WebhookDispatcher::new(db.clone()),
             nodes,
             db,
             batch_size,
+            network,
+            emission,
             is_syncing: AtomicBool::new(false),
             is_repairing: AtomicBool::new(false),
             local_height: AtomicI64::new(-1),
             repair_height: AtomicI64::new(0),
             repair_total_height: AtomicI64::new(0),
+            search_index_docs: AtomicI64::new(-1),
+            bulk_loading: AtomicBool::new(false),
             node_height: AtomicI64::new(0),
             blocks_synced: AtomicU64::new(0),
             sync_start_time: AtomicU64::new(0),
@@ -127,9 +196,45 @@ impl SyncService {
         }
     }
 
+    /// Receive chain events as sync batches are committed
+    pub fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<Arc<ChainEvent>> {
+        self.events.subscribe()
+    }
+
+    /// Make sure every reachable node is on the configured network, going by its
+    /// genesis block id. Unreachable nodes and nodes that don't report the id are
+    /// only logged, so a node that is still starting up doesn't block the indexer.
+    pub async fn check_network(&self) -> Result<()> {
+        for node in &self.nodes {
+            let info = match node.get_info().await {
+                Ok(info) => info,
+                Err(e) => {
+                    tracing::warn!("Could not verify network of node {}: {}", node.url, e);
+                    continue;
+                }
+            };
+            match info.genesis_block_id.as_deref() {
+                Some(genesis) if !self.network.matches_genesis(genesis) => anyhow::bail!(
+                    "Node {} has genesis block {}, which does not belong to {}",
+                    node.url,
+                    genesis,
+                    self.network
+                ),
+                Some(_) => {}
+                None => tracing::warn!("Node {} does not report genesisBlockId, network not verified", node.url),
+            }
+        }
+        Ok(())
+    }
+
     pub async fn run(&self, interval_secs: u64) {
         tracing::info!("Starting sync service with {} node(s)", self.nodes.len());
 
+        // Databases indexed before block_emission existed
+        if let Err(e) = self.fill_block_emission() {
+            tracing::error!("Block emission backfill failed: {}", e);
+        }
+
         loop {
             if let Err(e) = self.sync_once().await {
                 tracing::error!("Sync error: {}", e);
@@ -140,6 +245,71 @@ impl SyncService {
         }
     }
 
+    /// Poll the primary node's mempool and keep the local mempool index current
+    pub async fn run_mempool(&self, interval_secs: u64) {
+        tracing::info!("Starting mempool indexer (every {}s)", interval_secs);
+
+        loop {
+            if let Err(e) = self.sync_mempool_once().await {
+                tracing::warn!("Mempool sync error: {}", e);
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(interval_secs)).await;
+        }
+    }
+
+    /// Deliver queued webhook notifications whose boxes are confirmed deeply enough
+    pub async fn run_webhooks(&self, interval_secs: u64) {
+        tracing::info!("Starting webhook dispatcher (every {}s)", interval_secs);
+
+        loop {
+            let tip_height = self.local_height.load(Ordering::SeqCst);
+            if tip_height > 0 {
+                match self.webhooks.dispatch_due(tip_height).await {
+                    Ok((delivered, failed)) if delivered > 0 || failed > 0 => {
+                        tracing::debug!("Webhooks: {} delivered, {} failed", delivered, failed);
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Webhook dispatch error: {}", e),
+                }
+            }
+
+            tokio::time::sleep(tokio::time::Duration::from_secs(interval_secs)).await;
+        }
+    }
+
+    async fn sync_mempool_once(&self) -> Result<()> {
+        // The index only makes sense against a synced chain
+        if self.is_syncing.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let node = self
+            .get_primary_node()
+            .ok_or_else(|| anyhow::anyhow!("No node configured"))?;
+
+        let max_txs = mempool_max_txs();
+        let mut txs = Vec::new();
+        loop {
+            let page = node
+                .get_mempool_transactions(MEMPOOL_PAGE_SIZE, txs.len() as i32)
+                .await?;
+            let page_len = page.len();
+            txs.extend(page);
+            if page_len < MEMPOOL_PAGE_SIZE as usize || txs.len() >= max_txs {
+                break;
+            }
+        }
+
+        let (added, removed) = self.mempool.apply_snapshot(&txs)?;
+        if !added.is_empty() || removed > 0 {
+            tracing::debug!("Mempool: {} txs (+{} -{})", txs.len(), added.len(), removed);
+        }
+        self.events.publish(added);
+
+        Ok(())
+    }
+
     async fn sync_once(&self) -> Result<()> {
         // Skip normal sync while repair is running
         if self.is_repairing.load(Ordering::SeqCst) {
@@ -147,12 +317,22 @@ impl SyncService {
         }
 
         // Check node health and get best height
-        let (_best_node_idx, node_height) = self.find_best_node().await?;
+        let (best_node_idx, node_height) = self.find_best_node().await?;
 
         self.node_height.store(node_height, Ordering::SeqCst);
 
         // Get local height
-        let local_height = self.db.get_sync_height()?;
+        let mut local_height = self.db.get_sync_height()?;
+
+        // Make sure our tip is still on the node's main chain before appending to it
+        if local_height > 0 {
+            let node = &self.nodes[best_node_idx];
+            if let Some(fork_height) = self.find_fork_point(node, local_height, node_height).await? {
+                self.rollback_to(fork_height).await?;
+                local_height = fork_height;
+            }
+        }
+
         self.local_height.store(local_height, Ordering::SeqCst);
 
         if local_height >= node_height {
@@ -183,31 +363,120 @@ impl SyncService {
             total_blocks
         );
 
+        // Indexes dropped by fast sync are rebuilt even when a batch fails, as in import_archive
+        let synced = self.sync_batches(local_height, end_height).await;
+        let indexed = self.set_bulk_loading(false);
+        let complete = synced?;
+        indexed?;
+
+        if !complete {
+            self.is_syncing.store(false, Ordering::SeqCst);
+            self.local_height.store(self.db.get_sync_height()?, Ordering::SeqCst);
+            return Ok(());
+        }
+
+        // Final checkpoint at end of sync
+        if let Err(e) = self.db.checkpoint() {
+            tracing::warn!("Final checkpoint failed: {}", e);
+        }
+
+        self.refresh_search_index()?;
+
+        // Sealed epochs are written as their last block lands; bring the open one up to date
+        self.db
+            .execute_transaction(|conn| update_epoch(conn, end_height / EPOCH_LENGTH))?;
+
+        self.is_syncing.store(false, Ordering::SeqCst);
+        tracing::info!("Sync complete at height {}", end_height);
+
+        Ok(())
+    }
+
+    /// Fetch and index the blocks after `local_height` up to the node's tip at
+    /// `end_height` in batches. Returns false when the node's chain stopped
+    /// extending our tip part way.
+    async fn sync_batches(&self, local_height: i64, end_height: i64) -> Result<bool> {
+        let start_height = local_height + 1;
+        let total_blocks = (end_height - start_height + 1) as u64;
+
         // Checkpoint frequency: checkpoint every N batches (configurable via SYNC_CHECKPOINT_INTERVAL)
         let checkpoint_interval: usize = std::env::var("SYNC_CHECKPOINT_INTERVAL")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(10);
 
+        // Heights far enough below the tip to be loaded in bulk
+        let fast_until = if fast_sync_depth() > 0 {
+            end_height - fast_sync_depth()
+        } else {
+            0
+        };
+
         // Sync in batches with parallel fetching across nodes
         let mut current_height = start_height;
         let mut batch_count: usize = 0;
+        let mut expected_parent = self.db.get_block_id_at_height(local_height)?;
 
         while current_height <= end_height {
-            let batch_end = std::cmp::min(current_height + self.batch_size as i64 - 1, end_height);
+            let fast = current_height + fast_sync_batch_size() - 1 <= fast_until;
+            let step = if fast { fast_sync_batch_size() } else { self.batch_size as i64 };
+            let batch_end = std::cmp::min(current_height + step - 1, end_height);
             let batch_size = (batch_end - current_height + 1) as usize;
 
+            self.set_bulk_loading(fast)?;
+
             // Parallel fetch using multiple nodes
             let blocks = self
                 .fetch_blocks_parallel(current_height, batch_end)
                 .await?;
 
+            // Only the blocks that extend our tip are indexed
+            let mut chain_broken = false;
+            let mut linked = 0;
+            for block in &blocks {
+                let header = block.get("header");
+                let parent_id = header.and_then(|h| h.get("parentId")).and_then(|v| v.as_str());
+
+                // The node switched forks while we were fetching; stop here and let
+                // the next sync cycle find the fork point and roll back
+                if let (Some(expected), Some(parent)) = (expected_parent.as_deref(), parent_id) {
+                    if expected != parent {
+                        tracing::warn!(
+                            "Block at height {} does not extend our tip ({} != {}), deferring to reorg handling",
+                            header.and_then(|h| h.get("height")).and_then(|v| v.as_i64()).unwrap_or(-1),
+                            parent,
+                            expected
+                        );
+                        chain_broken = true;
+                        break;
+                    }
+                }
+
+                expected_parent = header
+                    .and_then(|h| h.get("id"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string());
+                linked += 1;
+            }
+            let blocks = &blocks[..linked];
+
             // Process blocks sequentially (must maintain order)
             let mut processor = self.processor.lock().await;
-            for block in blocks {
-                processor.process_block(&block)?;
+            if fast {
+                processor.process_blocks_bulk(blocks)?;
+                drop(processor);
+            } else {
+                let mut batch_events = Vec::new();
+                for block in blocks {
+                    batch_events.extend(processor.process_block(block)?);
+                }
+                drop(processor);
+                self.events.publish(batch_events);
+            }
+
+            if chain_broken {
+                return Ok(false);
             }
-            drop(processor);
 
             batch_count += 1;
 
@@ -232,13 +501,214 @@ impl SyncService {
             current_height = batch_end + 1;
         }
 
-        // Final checkpoint at end of sync
-        if let Err(e) = self.db.checkpoint() {
-            tracing::warn!("Final checkpoint failed: {}", e);
+        Ok(true)
+    }
+
+    /// Index the blocks of an archive (see [`archive`]) that follow the local tip,
+    /// up to `to_height` when given, through the bulk loader. Live sync picks up
+    /// from the new tip. Returns the new tip height.
+    pub async fn import_archive(&self, path: &Path, to_height: Option<i64>) -> Result<i64> {
+        if self.is_syncing.load(Ordering::SeqCst) {
+            anyhow::bail!("Cannot import while sync is in progress");
         }
 
+        self.is_syncing.store(true, Ordering::SeqCst);
+        self.blocks_synced.store(0, Ordering::SeqCst);
+        self.sync_start_time.store(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            Ordering::SeqCst,
+        );
+
+        let imported = self.import_blocks(path, to_height).await;
+        let indexed = self.set_bulk_loading(false);
         self.is_syncing.store(false, Ordering::SeqCst);
-        tracing::info!("Sync complete at height {}", end_height);
+        let tip_height = imported?;
+        indexed?;
+
+        if let Err(e) = self.db.checkpoint() {
+            tracing::warn!("Final checkpoint failed during import: {}", e);
+        }
+        self.refresh_search_index()?;
+        self.db
+            .execute_transaction(|conn| update_epoch(conn, tip_height / EPOCH_LENGTH))?;
+
+        tracing::info!("Archive import complete at height {}", tip_height);
+        Ok(tip_height)
+    }
+
+    async fn import_blocks(&self, path: &Path, to_height: Option<i64>) -> Result<i64> {
+        let mut tip_height = self.db.get_sync_height()?;
+        let mut expected_parent = self.db.get_block_id_at_height(tip_height)?;
+        self.local_height.store(tip_height, Ordering::SeqCst);
+        tracing::info!("Importing blocks after height {} from {}", tip_height, path.display());
+
+        self.set_bulk_loading(true)?;
+
+        let mut batch = Vec::new();
+        for block in ArchiveReader::open(path)? {
+            let block = block?;
+            let height = archive::block_height(&block).context("Archive block without a height")?;
+
+            // Already indexed
+            if height <= tip_height {
+                continue;
+            }
+            if to_height.is_some_and(|to| height > to) {
+                break;
+            }
+            if height != tip_height + 1 {
+                anyhow::bail!("Archive jumps from height {} to {}", tip_height, height);
+            }
+
+            let header = block.get("header");
+            let parent_id = header.and_then(|h| h.get("parentId")).and_then(|v| v.as_str());
+            if let (Some(expected), Some(parent)) = (expected_parent.as_deref(), parent_id) {
+                if expected != parent {
+                    anyhow::bail!(
+                        "Archive block at height {} does not extend our tip ({} != {})",
+                        height,
+                        parent,
+                        expected
+                    );
+                }
+            }
+
+            // The genesis block ties the archive to a network, as check_network does for nodes
+            if height == 1 {
+                let id = header.and_then(|h| h.get("id")).and_then(|v| v.as_str()).unwrap_or_default();
+                if !self.network.matches_genesis(id) {
+                    anyhow::bail!("Archive genesis block {} does not belong to {}", id, self.network);
+                }
+            }
+
+            expected_parent = header
+                .and_then(|h| h.get("id"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+            tip_height = height;
+            batch.push(block);
+
+            if batch.len() as i64 >= fast_sync_batch_size() {
+                self.import_batch(&mut batch).await?;
+            }
+        }
+        self.import_batch(&mut batch).await?;
+
+        Ok(tip_height)
+    }
+
+    async fn import_batch(&self, batch: &mut Vec<serde_json::Value>) -> Result<()> {
+        let Some(last_height) = batch.last().and_then(archive::block_height) else {
+            return Ok(());
+        };
+
+        self.processor.lock().await.process_blocks_bulk(batch)?;
+        if let Err(e) = self.db.checkpoint() {
+            tracing::warn!("Checkpoint failed during import: {}", e);
+        }
+
+        self.blocks_synced.fetch_add(batch.len() as u64, Ordering::SeqCst);
+        self.local_height.store(last_height, Ordering::SeqCst);
+        tracing::info!("Imported blocks up to height {}", last_height);
+
+        batch.clear();
+        Ok(())
+    }
+
+    /// Drop the secondary indexes of the bulk-loaded tables when fast sync starts
+    /// and build them again once it is over
+    fn set_bulk_loading(&self, bulk: bool) -> Result<()> {
+        if self.bulk_loading.load(Ordering::SeqCst) == bulk {
+            return Ok(());
+        }
+
+        if bulk {
+            tracing::info!("Fast sync: dropping secondary indexes for the bulk load");
+            self.db.drop_bulk_load_indexes()?;
+        } else {
+            tracing::info!("Fast sync done: building secondary indexes");
+            let start = std::time::Instant::now();
+            self.db.create_bulk_load_indexes()?;
+            tracing::info!("Secondary indexes built in {:.1}s", start.elapsed().as_secs_f64());
+        }
+
+        self.bulk_loading.store(bulk, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Rebuild the full-text index when documents were added since the last build
+    fn refresh_search_index(&self) -> Result<()> {
+        let max_doc_id: i64 = self
+            .db
+            .query_one("SELECT COALESCE(MAX(doc_id), 0) FROM search_index", [], |row| row.get(0))?
+            .unwrap_or(0);
+        if self.search_index_docs.swap(max_doc_id, Ordering::SeqCst) != max_doc_id {
+            self.db.rebuild_search_index()?;
+        }
+        Ok(())
+    }
+
+    /// Walk back from our tip until the block we stored at a height matches the
+    /// node's main-chain block at that height. Returns `None` when our tip is
+    /// still on the node's chain, or the last common height when it is not.
+    async fn find_fork_point(
+        &self,
+        node: &NodeClient,
+        local_height: i64,
+        node_height: i64,
+    ) -> Result<Option<i64>> {
+        // A node that is behind us can only vouch for heights it has
+        let tip_height = local_height.min(node_height);
+        let mut height = tip_height;
+
+        while height > 0 {
+            if tip_height - height > max_reorg_depth() {
+                anyhow::bail!(
+                    "Chain reorganization deeper than {} blocks below height {}, refusing to roll back",
+                    max_reorg_depth(),
+                    tip_height
+                );
+            }
+
+            let local_id = self.db.get_block_id_at_height(height)?;
+            let node_ids = node.get_block_ids_at_height(height).await?;
+
+            if local_id.is_some() && local_id.as_ref() == node_ids.first() {
+                if height == tip_height {
+                    return Ok(None);
+                }
+                return Ok(Some(height));
+            }
+
+            height -= 1;
+        }
+
+        Ok(Some(0))
+    }
+
+    /// Undo everything above `fork_height` and reset the processor counters
+    async fn rollback_to(&self, fork_height: i64) -> Result<()> {
+        let local_height = self.db.get_sync_height()?;
+        tracing::warn!(
+            "Chain reorganization detected: rolling back from height {} to {}",
+            local_height,
+            fork_height
+        );
+
+        let mut processor = self.processor.lock().await;
+        let orphaned = processor.rollback_to(fork_height)?;
+        drop(processor);
+
+        self.events.publish(vec![ChainEvent::Reorg {
+            fork_height,
+            orphaned,
+        }]);
+
+        self.local_height.store(fork_height, Ordering::SeqCst);
+        tracing::info!("Rollback complete: {} block(s) orphaned", orphaned);
 
         Ok(())
     }
@@ -468,7 +938,8 @@ impl SyncService {
         // Delete only the affected tables
         self.db.execute_batch(
             "DELETE FROM box_assets;
-             DELETE FROM tokens;"
+             DELETE FROM tokens;
+             DELETE FROM search_index WHERE entity_type = 'token';"
         )?;
         self.db.checkpoint()?;
         tracing::info!("Cleared box_assets and tokens tables. Re-extracting from blocks...");
@@ -495,7 +966,7 @@ impl SyncService {
             // Lightweight extraction: only box_assets and tokens
             // Collect all inserts first, then batch-execute in a transaction
             let mut asset_inserts: Vec<(i64, String, String, i64, i32)> = Vec::new();
-            let mut token_inserts: Vec<(String, String, i64, Option<String>, Option<String>, Option<String>, Option<i32>, i64)> = Vec::new();
+            let mut token_inserts: Vec<processor::TokenData> = Vec::new();
 
             for block in &blocks {
                 let header = match block.get("header") {
@@ -513,11 +984,10 @@ impl SyncService {
                 };
 
                 for tx in transactions {
-                    let inputs = tx.get("inputs").and_then(|v| v.as_array());
-                    let first_input_box_id = inputs
-                        .and_then(|inp| inp.first())
-                        .and_then(|i| i.get("boxId"))
-                        .and_then(|v| v.as_str());
+                    if let Some(token) = processor::minted_token(tx, height) {
+                        token_inserts.push(token);
+                        total_tokens += 1;
+                    }
 
                     let outputs = match tx.get("outputs").and_then(|v| v.as_array()) {
                         Some(o) => o,
@@ -525,7 +995,6 @@ impl SyncService {
                     };
                     for output in outputs {
                         let box_id = output.get("boxId").and_then(|v| v.as_str()).unwrap_or("");
-                        let additional_registers = output.get("additionalRegisters");
                         let assets = output.get("assets").and_then(|v| v.as_array());
 
                         if let Some(assets) = assets {
@@ -545,27 +1014,6 @@ impl SyncService {
                                     asset_idx as i32,
                                 ));
                                 total_assets += 1;
-
-                                // Check for minting
-                                if asset_idx == 0 {
-                                    if let Some(first_box) = first_input_box_id {
-                                        if first_box == token_id {
-                                            let (name, description, token_type, decimals) =
-                                                processor::extract_token_metadata_pub(additional_registers);
-                                            token_inserts.push((
-                                                token_id.to_string(),
-                                                box_id.to_string(),
-                                                amount,
-                                                name,
-                                                description,
-                                                token_type,
-                                                decimals,
-                                                height,
-                                            ));
-                                            total_tokens += 1;
-                                        }
-                                    }
-                                }
                             }
                         }
                     }
@@ -580,11 +1028,8 @@ impl SyncService {
                         duckdb::params![id, box_id, token_id, amount, asset_index],
                     )?;
                 }
-                for (token_id, box_id, amount, name, description, token_type, decimals, height) in &token_inserts {
-                    conn.execute(
-                        "INSERT INTO tokens (token_id, box_id, emission_amount, name, description, token_type, decimals, creation_height) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-                        duckdb::params![token_id, box_id, amount, name, description, token_type, decimals, height],
-                    )?;
+                for token in &token_inserts {
+                    processor::insert_token(conn, token)?;
                 }
                 Ok(())
             })?;
@@ -615,10 +1060,15 @@ impl SyncService {
             tracing::warn!("Final checkpoint failed during repair: {}", e);
         }
 
+        // Holders and supply changes are derived from box_assets, which was just rebuilt
+        self.rebuild_token_holders()?;
+        self.rebuild_token_events()?;
+        self.refresh_search_index()?;
+
         // Reset processor counters from the now-populated tables
         {
             let mut processor = self.processor.lock().await;
-            *processor = BlockProcessor::new(self.db.clone());
+            *processor = BlockProcessor::new(self.db.clone(), self.network);
         }
 
         self.is_repairing.store(false, Ordering::SeqCst);
@@ -631,4 +1081,298 @@ impl SyncService {
 
         Ok(())
     }
+
+    /// Recompute boxes.ergo_tree_template_hash for every stored ErgoTree.
+    /// Needed after the template hashing algorithm changes; block data is not re-fetched.
+    /// Hashing every tree is CPU bound, so this blocks and belongs on the blocking pool.
+    pub fn repair_template_hashes(&self) -> Result<()> {
+        self.run_db_repair(|| self.recompute_template_hashes())
+    }
+
+    /// Rebuild box_registers and script_constants from the stored boxes
+    pub fn repair_box_registers(&self) -> Result<()> {
+        self.run_db_repair(|| self.rebuild_box_registers())
+    }
+
+    /// Rebuild token_holders from the current UTXO set
+    pub fn repair_token_holders(&self) -> Result<()> {
+        self.run_db_repair(|| self.rebuild_token_holders())
+    }
+
+    /// Recompute address_stats.balance from the current UTXO set
+    pub fn repair_address_balances(&self) -> Result<()> {
+        self.run_db_repair(|| self.rebuild_address_balances())
+    }
+
+    /// Record the emission split for main-chain blocks that have none
+    pub fn repair_block_emission(&self) -> Result<()> {
+        self.run_db_repair(|| self.fill_block_emission())
+    }
+
+    /// Run a repair job that only reads and rewrites local tables, holding the
+    /// sync/repair flags so regular sync stays paused meanwhile. The job blocks
+    /// on the database, so handlers start it with `spawn_blocking`.
+    fn run_db_repair<F>(&self, job: F) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        if self.is_syncing.load(Ordering::SeqCst) {
+            anyhow::bail!("Cannot repair while sync is in progress");
+        }
+        if self.is_repairing.load(Ordering::SeqCst) {
+            anyhow::bail!("Repair is already in progress");
+        }
+
+        self.is_repairing.store(true, Ordering::SeqCst);
+        self.is_syncing.store(true, Ordering::SeqCst);
+
+        let result = job();
+
+        self.is_repairing.store(false, Ordering::SeqCst);
+        self.is_syncing.store(false, Ordering::SeqCst);
+        result
+    }
+
+    fn recompute_template_hashes(&self) -> Result<()> {
+        let page_size: i64 = 10_000;
+
+        let total: i64 = self
+            .db
+            .query_one("SELECT COUNT(DISTINCT ergo_tree) FROM boxes", [], |row| row.get(0))?
+            .unwrap_or(0);
+        self.repair_height.store(0, Ordering::SeqCst);
+        self.repair_total_height.store(total, Ordering::SeqCst);
+        tracing::info!("Recomputing template hashes for {} distinct ErgoTrees", total);
+
+        // Stage tree -> hash for every tree whose stored hash is stale, then
+        // rewrite boxes with a single UPDATE ... FROM
+        self.db.execute_batch(
+            "CREATE OR REPLACE TEMP TABLE template_hash_repair (ergo_tree TEXT, template_hash VARCHAR)",
+        )?;
+
+        // Keyset pagination over distinct trees
+        let mut last_tree = String::new();
+        let mut done: i64 = 0;
+        let mut stale: usize = 0;
+
+        loop {
+            let trees: Vec<(String, String)> = self.db.query_all(
+                "SELECT ergo_tree, ANY_VALUE(ergo_tree_template_hash) FROM boxes
+                 WHERE ergo_tree > ?
+                 GROUP BY ergo_tree
+                 ORDER BY ergo_tree
+                 LIMIT ?",
+                duckdb::params![last_tree, page_size],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+
+            let Some((last, _)) = trees.last() else {
+                break;
+            };
+            last_tree = last.clone();
+            done += trees.len() as i64;
+
+            let updates: Vec<(String, String)> = trees
+                .into_iter()
+                .filter_map(|(tree, old_hash)| {
+                    let hash = ergo_tree_template_hash(&tree);
+                    (hash != old_hash).then_some((tree, hash))
+                })
+                .collect();
+
+            if !updates.is_empty() {
+                self.db.execute_transaction(|conn| {
+                    let mut app = conn.appender("template_hash_repair")?;
+                    for (tree, hash) in &updates {
+                        app.append_row(duckdb::params![tree, hash])?;
+                    }
+                    app.flush()?;
+                    Ok(())
+                })?;
+                stale += updates.len();
+            }
+
+            self.repair_height.store(done, Ordering::SeqCst);
+            tracing::info!("Template hashes: {}/{} trees checked, {} stale", done, total, stale);
+        }
+
+        let result = if stale > 0 {
+            self.db.execute_transaction(|conn| {
+                conn.execute(
+                    "UPDATE boxes SET ergo_tree_template_hash = r.template_hash
+                     FROM template_hash_repair r
+                     WHERE boxes.ergo_tree = r.ergo_tree",
+                    [],
+                )?;
+                Ok(())
+            })
+        } else {
+            Ok(())
+        };
+        if let Err(e) = self.db.execute_batch("DROP TABLE IF EXISTS template_hash_repair") {
+            tracing::warn!("Failed to drop template hash staging table: {}", e);
+        }
+        result?;
+
+        if let Err(e) = self.db.checkpoint() {
+            tracing::warn!("Final checkpoint failed during template hash repair: {}", e);
+        }
+        tracing::info!("Template hash repair complete. {} trees updated.", stale);
+
+        Ok(())
+    }
+
+    fn rebuild_token_holders(&self) -> Result<()> {
+        tracing::info!("Rebuilding token holders");
+        self.db.execute_transaction(|conn| {
+            conn.execute("DELETE FROM token_holders", [])?;
+            conn.execute(
+                "INSERT INTO token_holders (token_id, address, amount)
+                 SELECT ba.token_id, b.address, SUM(ba.amount)
+                 FROM box_assets ba
+                 JOIN boxes b ON ba.box_id = b.box_id
+                 WHERE b.spent_tx_id IS NULL
+                 GROUP BY ba.token_id, b.address
+                 HAVING SUM(ba.amount) > 0",
+                [],
+            )?;
+            Ok(())
+        })?;
+        tracing::info!("Token holder rebuild complete.");
+        Ok(())
+    }
+
+    fn rebuild_token_events(&self) -> Result<()> {
+        tracing::info!("Rebuilding token mint and burn events");
+        self.db.execute_transaction(|conn| {
+            conn.execute("DELETE FROM token_events", [])?;
+            conn.execute(
+                "INSERT INTO token_events (tx_id, token_id, height, event_type, amount)
+                 SELECT tx_id, token_id, MAX(height),
+                        CASE WHEN SUM(amount) > 0 THEN 'mint' ELSE 'burn' END,
+                        ABS(SUM(amount))
+                 FROM (
+                     SELECT b.tx_id, ba.token_id, b.settlement_height AS height, ba.amount
+                     FROM box_assets ba
+                     JOIN boxes b ON b.box_id = ba.box_id
+                     UNION ALL
+                     SELECT i.tx_id, ba.token_id, t.inclusion_height AS height, -ba.amount
+                     FROM inputs i
+                     JOIN box_assets ba ON ba.box_id = i.box_id
+                     JOIN transactions t ON t.tx_id = i.tx_id
+                 )
+                 GROUP BY tx_id, token_id
+                 HAVING SUM(amount) <> 0",
+                [],
+            )?;
+            Ok(())
+        })?;
+        tracing::info!("Token event rebuild complete.");
+        Ok(())
+    }
+
+    fn rebuild_address_balances(&self) -> Result<()> {
+        tracing::info!("Rebuilding address balances");
+        self.db.execute_transaction(|conn| {
+            conn.execute("UPDATE address_stats SET balance = 0", [])?;
+            conn.execute(
+                "UPDATE address_stats SET balance = u.total
+                 FROM (
+                     SELECT address, SUM(value) AS total
+                     FROM boxes
+                     WHERE spent_tx_id IS NULL
+                     GROUP BY address
+                 ) u
+                 WHERE address_stats.address = u.address",
+                [],
+            )?;
+            Ok(())
+        })?;
+        tracing::info!("Address balance rebuild complete.");
+        Ok(())
+    }
+
+    fn rebuild_box_registers(&self) -> Result<()> {
+        let page_size: i64 = 10_000;
+
+        let max_gix: i64 = self
+            .db
+            .query_one("SELECT COALESCE(MAX(global_index), 0) FROM boxes", [], |row| row.get(0))?
+            .unwrap_or(0);
+        self.repair_height.store(0, Ordering::SeqCst);
+        self.repair_total_height.store(max_gix, Ordering::SeqCst);
+        tracing::info!("Rebuilding box registers and constants for {} boxes", max_gix);
+
+        self.db.execute_batch(
+            "DELETE FROM box_registers;
+             DELETE FROM script_constants;"
+        )?;
+
+        let mut last_gix: i64 = 0;
+        loop {
+            let boxes: Vec<(i64, String, String, Option<String>)> = self.db.query_all(
+                "SELECT global_index, box_id, ergo_tree, additional_registers FROM boxes
+                 WHERE global_index > ?
+                 ORDER BY global_index
+                 LIMIT ?",
+                duckdb::params![last_gix, page_size],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )?;
+
+            let Some((gix, ..)) = boxes.last() else {
+                break;
+            };
+            last_gix = *gix;
+
+            let mut registers = Vec::new();
+            let mut constants = Vec::new();
+            for (_, box_id, ergo_tree, registers_json) in &boxes {
+                let parsed: Option<serde_json::Value> =
+                    registers_json.as_deref().and_then(|j| serde_json::from_str(j).ok());
+                registers.extend(processor::decode_registers(box_id, parsed.as_ref()));
+                constants.extend(processor::decode_script_constants(box_id, ergo_tree));
+            }
+
+            self.db.execute_transaction(|conn| {
+                processor::insert_registers_and_constants(conn, &registers, &constants)
+            })?;
+
+            self.repair_height.store(last_gix, Ordering::SeqCst);
+            tracing::info!("Box registers: {}/{} boxes", last_gix, max_gix);
+        }
+
+        if let Err(e) = self.db.checkpoint() {
+            tracing::warn!("Final checkpoint failed during register repair: {}", e);
+        }
+        tracing::info!("Box register repair complete.");
+
+        Ok(())
+    }
+
+    fn fill_block_emission(&self) -> Result<()> {
+        let missing: Vec<i64> = self.db.query_all(
+            "SELECT b.height FROM blocks b
+             LEFT JOIN block_emission e ON e.height = b.height
+             WHERE b.main_chain = TRUE AND e.height IS NULL
+             ORDER BY b.height",
+            [],
+            |row| row.get(0),
+        )?;
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        tracing::info!("Recording block emission for {} blocks", missing.len());
+        for chunk in missing.chunks(10_000) {
+            self.db.execute_transaction(|conn| {
+                for &height in chunk {
+                    insert_block_emission(conn, self.emission, height)?;
+                }
+                Ok(())
+            })?;
+        }
+        tracing::info!("Block emission backfill complete.");
+
+        Ok(())
+    }
 }
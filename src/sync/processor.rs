@@ -3,12 +3,22 @@
 use anyhow::{Context, Result};
 use duckdb::{params, Connection};
 use serde_json::Value;
+use std::collections::HashMap;
 
-use crate::db::Database;
-use crate::utils::ergo_tree;
+use super::events::ChainEvent;
+use super::mempool;
+use crate::db::{block_window_stats, Database};
+use crate::utils::emission::EmissionRules;
+use crate::utils::network::Network;
+use crate::utils::{ergo_tree, sigma, MAX_TOKEN_DECIMALS};
+
+/// Blocks per epoch in the epochs table (matches the node's difficulty epoch)
+pub const EPOCH_LENGTH: i64 = 1024;
 
 pub struct BlockProcessor {
     db: Database,
+    network: Network,
+    emission: &'static EmissionRules,
     global_tx_index: i64,
     global_box_index: i64,
     global_block_index: i64,
@@ -18,30 +28,48 @@ pub struct BlockProcessor {
 }
 
 impl BlockProcessor {
-    pub fn new(db: Database) -> Self {
+    pub fn new(db: Database, network: Network) -> Self {
         // Restore counters from existing data to avoid ID collisions on restart
         let box_asset_id = db
-            .query_one("SELECT COALESCE(MAX(id), 0) FROM box_assets", [], |row| row.get(0))
+            .query_one("SELECT COALESCE(MAX(id), 0) FROM box_assets", [], |row| {
+                row.get(0)
+            })
             .unwrap_or(Some(0))
             .unwrap_or(0);
         let input_id = db
-            .query_one("SELECT COALESCE(MAX(id), 0) FROM inputs", [], |row| row.get(0))
+            .query_one("SELECT COALESCE(MAX(id), 0) FROM inputs", [], |row| {
+                row.get(0)
+            })
             .unwrap_or(Some(0))
             .unwrap_or(0);
         let data_input_id = db
-            .query_one("SELECT COALESCE(MAX(id), 0) FROM data_inputs", [], |row| row.get(0))
+            .query_one("SELECT COALESCE(MAX(id), 0) FROM data_inputs", [], |row| {
+                row.get(0)
+            })
             .unwrap_or(Some(0))
             .unwrap_or(0);
         let global_tx_index = db
-            .query_one("SELECT COALESCE(MAX(global_index), 0) FROM transactions", [], |row| row.get(0))
+            .query_one(
+                "SELECT COALESCE(MAX(global_index), 0) FROM transactions",
+                [],
+                |row| row.get(0),
+            )
             .unwrap_or(Some(0))
             .unwrap_or(0);
         let global_box_index = db
-            .query_one("SELECT COALESCE(MAX(global_index), 0) FROM boxes", [], |row| row.get(0))
+            .query_one(
+                "SELECT COALESCE(MAX(global_index), 0) FROM boxes",
+                [],
+                |row| row.get(0),
+            )
             .unwrap_or(Some(0))
             .unwrap_or(0);
         let global_block_index = db
-            .query_one("SELECT COALESCE(MAX(global_index), 0) FROM blocks", [], |row| row.get(0))
+            .query_one(
+                "SELECT COALESCE(MAX(global_index), 0) FROM blocks WHERE main_chain = TRUE",
+                [],
+                |row| row.get(0),
+            )
             .unwrap_or(Some(0))
             .unwrap_or(0);
 
@@ -52,6 +80,8 @@ impl BlockProcessor {
 
         Self {
             db,
+            network,
+            emission: EmissionRules::for_network(network),
             global_tx_index,
             global_box_index,
             global_block_index,
@@ -61,100 +91,21 @@ impl BlockProcessor {
         }
     }
 
-    pub fn process_block(&mut self, block: &Value) -> Result<()> {
-        let header = block.get("header").context("Missing header")?;
-        let block_txs = block.get("blockTransactions").context("Missing blockTransactions")?;
-        let transactions = block_txs
-            .get("transactions")
-            .and_then(|t| t.as_array())
-            .context("Missing transactions array")?;
-
-        // Extract header data
-        let block_id = header.get("id").and_then(|v| v.as_str()).context("Missing block id")?.to_string();
-        let parent_id = header.get("parentId").and_then(|v| v.as_str()).unwrap_or("").to_string();
-        let height = header.get("height").and_then(|v| v.as_i64()).context("Missing height")?;
-        let timestamp = header.get("timestamp").and_then(|v| v.as_i64()).unwrap_or(0);
-        let difficulty = header
-            .get("difficulty")
-            .and_then(|v| v.as_str())
-            .and_then(|s| s.parse::<i64>().ok())
-            .unwrap_or(0);
-        let miner_pk = header.get("minerPk").and_then(|v| v.as_str()).unwrap_or("");
-
-        // Calculate block metrics
-        let tx_count = transactions.len() as i32;
-        let block_size: i32 = transactions
-            .iter()
-            .filter_map(|tx| tx.get("size").and_then(|s| s.as_i64()))
-            .sum::<i64>() as i32;
-
-        let block_coins: i64 = transactions
-            .iter()
-            .map(|tx| {
-                tx.get("outputs")
-                    .and_then(|o| o.as_array())
-                    .map(|outputs| {
-                        outputs
-                            .iter()
-                            .filter_map(|o| o.get("value").and_then(|v| v.as_i64()))
-                            .sum::<i64>()
-                    })
-                    .unwrap_or(0)
-            })
-            .sum();
-
-        // Derive miner address from minerPk
-        let miner_address = if !miner_pk.is_empty() {
-            ergo_tree::miner_pk_to_address(miner_pk)
-        } else {
-            None
-        };
-
-        // Get miner reward from first transaction (coinbase)
-        let miner_reward = transactions
-            .first()
-            .and_then(|tx| tx.get("outputs"))
-            .and_then(|o| o.as_array())
-            .and_then(|outputs| outputs.first())
-            .and_then(|o| o.get("value"))
-            .and_then(|v| v.as_i64())
-            .unwrap_or(0);
-
-        self.global_block_index += 1;
-        let global_block_index = self.global_block_index;
-
-        // Collect all operations for this block
-        let mut collected = CollectedOps::new();
-
-        // Collect block data
-        collected.block = Some(BlockData {
-            block_id: block_id.clone(),
-            parent_id,
-            height,
-            timestamp,
-            difficulty,
-            block_size,
-            block_coins,
-            tx_count,
-            miner_address,
-            miner_reward,
-            global_index: global_block_index,
-        });
-
-        // Process transactions and collect operations
-        for (tx_idx, tx) in transactions.iter().enumerate() {
-            self.collect_transaction_ops(
-                tx,
-                &block_id,
-                height,
-                timestamp,
-                tx_idx as i32,
-                &mut collected,
-            )?;
-        }
+    /// Index one block in a single database transaction. Returns the chain
+    /// events it produced, for publishing once the batch is committed.
+    pub fn process_block(&mut self, block: &Value) -> Result<Vec<ChainEvent>> {
+        let first_box_index = self.global_box_index;
+        let collected = self.collect_block(block)?;
+        let (height, timestamp) = collected
+            .block
+            .as_ref()
+            .map(|b| (b.height, b.timestamp))
+            .unwrap_or_default();
 
         // Execute all operations in a single transaction
         let update_stats = height % 100 == 0;
+        let emission = self.emission;
+        let mut events = Vec::new();
         self.db.execute_transaction(|conn| {
             // Insert block (check first to avoid ON CONFLICT FK issues)
             if let Some(ref b) = collected.block {
@@ -175,8 +126,9 @@ impl BlockProcessor {
                     conn.execute(
                         "INSERT INTO blocks (
                             block_id, parent_id, height, timestamp, difficulty, block_size,
-                            block_coins, tx_count, miner_address, miner_reward, main_chain, global_index
-                        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, TRUE, ?)",
+                            block_coins, tx_count, miner_address, miner_name, miner_reward, main_chain, global_index
+                        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?,
+                            (SELECT label FROM address_labels WHERE address = ?), ?, TRUE, ?)",
                         params![
                             b.block_id,
                             b.parent_id,
@@ -187,11 +139,20 @@ impl BlockProcessor {
                             b.block_coins,
                             b.tx_count,
                             b.miner_address,
+                            b.miner_address,
                             b.miner_reward,
                             b.global_index
                         ],
                     )?;
                 }
+                insert_block_emission(conn, emission, b.height)?;
+                events.push(ChainEvent::NewBlock {
+                    id: b.block_id.clone(),
+                    height: b.height,
+                    timestamp: b.timestamp,
+                    tx_count: b.tx_count,
+                    miner_address: b.miner_address.clone(),
+                });
             }
 
             // Insert all transactions (skip if already exists)
@@ -208,8 +169,8 @@ impl BlockProcessor {
                     conn.execute(
                         "INSERT INTO transactions (
                             tx_id, block_id, inclusion_height, timestamp, index_in_block,
-                            global_index, coinbase, size, input_count, output_count
-                        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                            global_index, coinbase, size, input_count, output_count, fee
+                        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                         params![
                             tx.tx_id,
                             tx.block_id,
@@ -220,12 +181,16 @@ impl BlockProcessor {
                             tx.coinbase,
                             tx.size,
                             tx.input_count,
-                            tx.output_count
+                            tx.output_count,
+                            tx.fee
                         ],
                     )?;
                 }
             }
 
+            // Boxes inserted by this call; re-processed ones must not count twice
+            let mut new_boxes: HashMap<&str, &str> = HashMap::new();
+
             // Insert all boxes (skip if already exists to avoid FK issues)
             for b in &collected.boxes {
                 // Check if box already exists to avoid ON CONFLICT FK issues in DuckDB
@@ -258,11 +223,115 @@ impl BlockProcessor {
                             b.registers_json
                         ],
                     )?;
+                    new_boxes.insert(b.box_id.as_str(), b.address.as_str());
+                }
+            }
+
+            insert_registers_and_constants(conn, &collected.registers, &collected.constants)?;
+
+            // Insert box assets (skip if already exists). Done before inputs so that
+            // boxes created and spent within this block can be resolved.
+            for asset in &collected.box_assets {
+                let exists: bool = conn
+                    .query_row(
+                        "SELECT 1 FROM box_assets WHERE id = ?",
+                        [asset.id],
+                        |_| Ok(true),
+                    )
+                    .unwrap_or(false);
+
+                if !exists {
+                    conn.execute(
+                        "INSERT INTO box_assets (id, box_id, token_id, amount, asset_index)
+                         VALUES (?, ?, ?, ?, ?)",
+                        params![asset.id, asset.box_id, asset.token_id, asset.amount, asset.asset_index],
+                    )?;
+                }
+            }
+
+            enqueue_webhook_deliveries(conn, first_box_index)?;
+
+            // Balance and token holder changes: new outputs add, spent inputs subtract.
+            // The per-transaction variants feed address and token events.
+            let mut balance_deltas: HashMap<String, i64> = HashMap::new();
+            let mut tx_balance_deltas: HashMap<(&str, String), i64> = HashMap::new();
+            // The fee is attributed once, to the address of the first input
+            let mut tx_fee_payers: HashMap<&str, String> = HashMap::new();
+            let mut tx_fees: HashMap<&str, i64> = HashMap::new();
+            let mut box_tx: HashMap<&str, &str> = HashMap::new();
+            for b in &collected.boxes {
+                if new_boxes.contains_key(b.box_id.as_str()) {
+                    *balance_deltas.entry(b.address.clone()).or_insert(0) += b.value;
+                    if b.ergo_tree == ergo_tree::MINERS_FEE_ERGO_TREE {
+                        *tx_fees.entry(b.tx_id.as_str()).or_insert(0) += b.value;
+                    }
+                    *tx_balance_deltas
+                        .entry((b.tx_id.as_str(), b.address.clone()))
+                        .or_insert(0) += b.value;
+                    box_tx.insert(b.box_id.as_str(), b.tx_id.as_str());
+                    if let Some(template_hash) = &b.template_hash {
+                        events.push(ChainEvent::TemplateMatch {
+                            template_hash: template_hash.clone(),
+                            box_id: b.box_id.clone(),
+                            tx_id: b.tx_id.clone(),
+                            address: b.address.clone(),
+                            value: b.value,
+                            height,
+                        });
+                    }
+                }
+            }
+            let mut holder_deltas: HashMap<(String, String), i64> = HashMap::new();
+            let mut tx_token_deltas: HashMap<(&str, String, String), i64> = HashMap::new();
+            for asset in &collected.box_assets {
+                if let Some(address) = new_boxes.get(asset.box_id.as_str()) {
+                    *holder_deltas
+                        .entry((asset.token_id.clone(), address.to_string()))
+                        .or_insert(0) += asset.amount;
+                    if let Some(tx_id) = box_tx.get(asset.box_id.as_str()) {
+                        *tx_token_deltas
+                            .entry((tx_id, asset.token_id.clone(), address.to_string()))
+                            .or_insert(0) += asset.amount;
+                    }
                 }
             }
 
             // Update spent boxes and insert inputs
             for input in &collected.inputs {
+                // Only a box that was still unspent changes balances
+                let unspent: Option<(String, i64)> = conn
+                    .query_row(
+                        "SELECT address, value FROM boxes WHERE box_id = ? AND spent_tx_id IS NULL",
+                        [&input.box_id],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .ok();
+
+                if let Some((address, value)) = unspent {
+                    *balance_deltas.entry(address.clone()).or_insert(0) -= value;
+                    *tx_balance_deltas
+                        .entry((input.tx_id.as_str(), address.clone()))
+                        .or_insert(0) -= value;
+                    tx_fee_payers.entry(input.tx_id.as_str()).or_insert(address);
+
+                    let mut stmt = conn.prepare_cached(
+                        "SELECT b.address, ba.token_id, ba.amount
+                         FROM box_assets ba
+                         JOIN boxes b ON ba.box_id = b.box_id
+                         WHERE ba.box_id = ?",
+                    )?;
+                    let mut rows = stmt.query([&input.box_id])?;
+                    while let Some(row) = rows.next()? {
+                        let address: String = row.get(0)?;
+                        let token_id: String = row.get(1)?;
+                        let amount: i64 = row.get(2)?;
+                        *tx_token_deltas
+                            .entry((input.tx_id.as_str(), token_id.clone(), address.clone()))
+                            .or_insert(0) -= amount;
+                        *holder_deltas.entry((token_id, address)).or_insert(0) -= amount;
+                    }
+                }
+
                 conn.execute(
                     "UPDATE boxes SET spent_tx_id = ?, spent_index = ?, spent_height = ? WHERE box_id = ?",
                     params![input.tx_id, input.input_index, input.height, input.box_id],
@@ -304,78 +373,592 @@ impl BlockProcessor {
                 }
             }
 
-            // Insert box assets (skip if already exists)
-            for asset in &collected.box_assets {
-                let exists: bool = conn
-                    .query_row(
-                        "SELECT 1 FROM box_assets WHERE id = ?",
-                        [asset.id],
-                        |_| Ok(true),
-                    )
-                    .unwrap_or(false);
+            apply_token_holder_deltas(conn, &holder_deltas)?;
 
-                if !exists {
+            // Per-transaction flows behind address summaries and counterparties
+            for ((tx_id, address), delta) in &tx_balance_deltas {
+                let fee = if tx_fee_payers.get(tx_id) == Some(address) {
+                    tx_fees.get(tx_id).copied().unwrap_or(0)
+                } else {
+                    0
+                };
+                conn.execute(
+                    "INSERT INTO address_activity (tx_id, address, height, timestamp, value_change, fee)
+                     VALUES (?, ?, ?, ?, ?, ?)
+                     ON CONFLICT (tx_id, address) DO NOTHING",
+                    params![tx_id, address, height, timestamp, delta, fee],
+                )?;
+            }
+            for ((tx_id, token_id, address), delta) in &tx_token_deltas {
+                if *delta != 0 {
                     conn.execute(
-                        "INSERT INTO box_assets (id, box_id, token_id, amount, asset_index)
-                         VALUES (?, ?, ?, ?, ?)",
-                        params![asset.id, asset.box_id, asset.token_id, asset.amount, asset.asset_index],
+                        "INSERT INTO address_token_activity (tx_id, address, token_id, height, amount_change)
+                         VALUES (?, ?, ?, ?, ?)
+                         ON CONFLICT (tx_id, address, token_id) DO NOTHING",
+                        params![tx_id, address, token_id, height, delta],
                     )?;
                 }
             }
 
+            for ((tx_id, address), delta) in tx_balance_deltas {
+                events.push(ChainEvent::AddressActivity {
+                    address,
+                    tx_id: tx_id.to_string(),
+                    height,
+                    balance_change: delta,
+                });
+            }
+            // A transaction's net change of a token is a mint when positive (only the
+            // token it issues) and a burn when negative
+            let mut tx_token_net: HashMap<(&str, &str), i64> = HashMap::new();
+            for ((tx_id, token_id, _), delta) in &tx_token_deltas {
+                *tx_token_net.entry((*tx_id, token_id.as_str())).or_insert(0) += delta;
+            }
+            for ((tx_id, token_id), net) in tx_token_net {
+                if net == 0 {
+                    continue;
+                }
+                conn.execute(
+                    "INSERT INTO token_events (tx_id, token_id, height, event_type, amount)
+                     VALUES (?, ?, ?, ?, ?)
+                     ON CONFLICT (tx_id, token_id) DO NOTHING",
+                    params![tx_id, token_id, height, if net > 0 { "mint" } else { "burn" }, net.abs()],
+                )?;
+            }
+
+            for ((tx_id, token_id, address), delta) in tx_token_deltas {
+                if delta != 0 {
+                    events.push(ChainEvent::TokenTransfer {
+                        token_id,
+                        address,
+                        tx_id: tx_id.to_string(),
+                        height,
+                        amount: delta,
+                    });
+                }
+            }
+
             // Insert tokens (new mints only, skip if already exists)
             for token in &collected.tokens {
-                let exists: bool = conn
-                    .query_row(
-                        "SELECT 1 FROM tokens WHERE token_id = ?",
-                        [&token.token_id],
-                        |_| Ok(true),
-                    )
-                    .unwrap_or(false);
+                insert_token(conn, token)?;
+            }
+
+            // Update address stats
+            let now = chrono::Utc::now().timestamp();
+            for addr in &collected.addresses {
+                conn.execute(
+                    "INSERT INTO address_stats (address, tx_count, first_seen_height, last_seen_height, updated_at)
+                     VALUES (?, 1, ?, ?, ?)
+                     ON CONFLICT (address) DO UPDATE SET
+                        tx_count = address_stats.tx_count + 1,
+                        last_seen_height = EXCLUDED.last_seen_height,
+                        updated_at = EXCLUDED.updated_at",
+                    params![addr.address, addr.height, addr.height, now],
+                )?;
+            }
+            for (address, delta) in &balance_deltas {
+                if *delta == 0 {
+                    continue;
+                }
+                conn.execute(
+                    "INSERT INTO address_stats (address, tx_count, balance, updated_at)
+                     VALUES (?, 0, ?, ?)
+                     ON CONFLICT (address) DO UPDATE SET
+                        balance = address_stats.balance + EXCLUDED.balance,
+                        updated_at = EXCLUDED.updated_at",
+                    params![address, delta, now],
+                )?;
+            }
+
+            // Update network stats periodically (every 100 blocks)
+            if update_stats {
+                if let Some(ref b) = collected.block {
+                    update_network_stats_sync(conn, emission, b.height, b.timestamp, b.difficulty)?;
+                }
+            }
+
+            // Seal the epoch on its last block; the open epoch is refreshed after each sync cycle
+            if let Some(ref b) = collected.block {
+                if (b.height + 1) % EPOCH_LENGTH == 0 {
+                    update_epoch(conn, b.height / EPOCH_LENGTH)?;
+                }
+                mempool::remove_confirmed(conn, b.height, b.height)?;
+            }
+
+            Ok(())
+        })?;
+
+        Ok(events)
+    }
+
+    /// Index a run of blocks well below the node's tip in one database transaction.
+    /// Nothing is checked for existence first: rows are streamed in through
+    /// appenders, spends are applied with a single `UPDATE ... FROM`, and balances,
+    /// holders and activity are derived set-wise from the rows just loaded. The
+    /// blocks must extend our tip and must not have been indexed before. No chain
+    /// events are produced.
+    pub fn process_blocks_bulk(&mut self, blocks: &[Value]) -> Result<()> {
+        if blocks.is_empty() {
+            return Ok(());
+        }
+
+        let first_block_index = self.global_block_index;
+        let first_tx_index = self.global_tx_index;
+        let first_box_index = self.global_box_index;
+        let first_asset_id = self.box_asset_id;
+        let first_input_id = self.input_id;
+
+        let collected = blocks
+            .iter()
+            .map(|block| self.collect_block(block))
+            .collect::<Result<Vec<_>>>()?;
+
+        let emission = self.emission;
+        self.db.execute_transaction(|conn| {
+            append_collected(conn, emission, &collected)?;
+
+            conn.execute(
+                "UPDATE blocks SET miner_name = l.label
+                 FROM address_labels l
+                 WHERE blocks.miner_address = l.address AND blocks.global_index > ?",
+                [first_block_index],
+            )?;
+
+            // Staging for the spends and value movements of this run
+            conn.execute_batch(
+                "CREATE OR REPLACE TEMP TABLE bulk_spent (
+                    tx_id VARCHAR, input_index INTEGER, height INTEGER,
+                    box_id VARCHAR, address TEXT, value BIGINT
+                 );
+                 CREATE OR REPLACE TEMP TABLE bulk_flows (
+                    tx_id VARCHAR, address TEXT, height INTEGER, value BIGINT, spent BOOLEAN
+                 );
+                 CREATE OR REPLACE TEMP TABLE bulk_token_flows (
+                    tx_id VARCHAR, address TEXT, token_id VARCHAR, amount BIGINT
+                 );",
+            )?;
+
+            // Boxes spent by this run, whether created before it or within it
+            conn.execute(
+                "INSERT INTO bulk_spent
+                 SELECT i.tx_id, i.input_index, t.inclusion_height AS height, b.box_id, b.address, b.value
+                 FROM inputs i
+                 JOIN transactions t ON t.tx_id = i.tx_id
+                 JOIN boxes b ON b.box_id = i.box_id
+                 WHERE i.id > ? AND t.global_index > ? AND b.spent_tx_id IS NULL",
+                [first_input_id, first_tx_index],
+            )?;
+            conn.execute(
+                "UPDATE boxes SET spent_tx_id = s.tx_id, spent_index = s.input_index, spent_height = s.height
+                 FROM bulk_spent s
+                 WHERE boxes.box_id = s.box_id",
+                [],
+            )?;
+
+            // Value and token movements: new outputs add, spent inputs subtract
+            conn.execute(
+                "INSERT INTO bulk_flows
+                 SELECT tx_id, address, settlement_height AS height, value, FALSE AS spent
+                 FROM boxes
+                 WHERE global_index > ?
+                 UNION ALL
+                 SELECT tx_id, address, height, -value, TRUE
+                 FROM bulk_spent",
+                [first_box_index],
+            )?;
+            conn.execute(
+                "INSERT INTO bulk_token_flows
+                 SELECT b.tx_id, b.address, ba.token_id, ba.amount
+                 FROM box_assets ba
+                 JOIN boxes b ON b.box_id = ba.box_id
+                 WHERE ba.id > ? AND b.global_index > ?
+                 UNION ALL
+                 SELECT s.tx_id, s.address, ba.token_id, -ba.amount
+                 FROM bulk_spent s
+                 JOIN box_assets ba ON ba.box_id = s.box_id",
+                [first_asset_id, first_box_index],
+            )?;
+
+            // Outputs count towards tx_count and last seen, as in process_block
+            conn.execute(
+                "INSERT INTO address_stats (address, tx_count, balance, first_seen_height, last_seen_height, updated_at)
+                 SELECT address,
+                        COUNT(*) FILTER (WHERE NOT spent),
+                        SUM(value),
+                        MIN(height) FILTER (WHERE NOT spent),
+                        MAX(height) FILTER (WHERE NOT spent),
+                        ?
+                 FROM bulk_flows
+                 GROUP BY address
+                 ON CONFLICT (address) DO UPDATE SET
+                    tx_count = address_stats.tx_count + EXCLUDED.tx_count,
+                    balance = address_stats.balance + EXCLUDED.balance,
+                    last_seen_height = COALESCE(EXCLUDED.last_seen_height, address_stats.last_seen_height),
+                    updated_at = EXCLUDED.updated_at",
+                [chrono::Utc::now().timestamp()],
+            )?;
+
+            conn.execute(
+                "INSERT INTO token_holders (token_id, address, amount)
+                 SELECT token_id, address, SUM(amount)
+                 FROM bulk_token_flows
+                 GROUP BY token_id, address
+                 HAVING SUM(amount) <> 0
+                 ON CONFLICT (token_id, address) DO UPDATE SET
+                    amount = token_holders.amount + EXCLUDED.amount",
+                [],
+            )?;
+            conn.execute("DELETE FROM token_holders WHERE amount <= 0", [])?;
+
+            // The fee is attributed once, to the address of the first input
+            conn.execute(
+                "INSERT INTO address_activity (tx_id, address, height, timestamp, value_change, fee)
+                 SELECT f.tx_id, f.address, t.inclusion_height, t.timestamp, SUM(f.value),
+                        CASE WHEN f.address = p.address THEN t.fee ELSE 0 END
+                 FROM bulk_flows f
+                 JOIN transactions t ON t.tx_id = f.tx_id
+                 LEFT JOIN (
+                    SELECT tx_id, arg_min(address, input_index) AS address
+                    FROM bulk_spent
+                    GROUP BY tx_id
+                 ) p ON p.tx_id = f.tx_id
+                 WHERE t.global_index > ?
+                 GROUP BY f.tx_id, f.address, t.inclusion_height, t.timestamp, t.fee, p.address",
+                [first_tx_index],
+            )?;
+            conn.execute(
+                "INSERT INTO address_token_activity (tx_id, address, token_id, height, amount_change)
+                 SELECT f.tx_id, f.address, f.token_id, t.inclusion_height, SUM(f.amount)
+                 FROM bulk_token_flows f
+                 JOIN transactions t ON t.tx_id = f.tx_id
+                 WHERE t.global_index > ?
+                 GROUP BY f.tx_id, f.address, f.token_id, t.inclusion_height
+                 HAVING SUM(f.amount) <> 0",
+                [first_tx_index],
+            )?;
+            conn.execute(
+                "INSERT INTO token_events (tx_id, token_id, height, event_type, amount)
+                 SELECT f.tx_id, f.token_id, t.inclusion_height,
+                        CASE WHEN SUM(f.amount) > 0 THEN 'mint' ELSE 'burn' END,
+                        ABS(SUM(f.amount))
+                 FROM bulk_token_flows f
+                 JOIN transactions t ON t.tx_id = f.tx_id
+                 WHERE t.global_index > ?
+                 GROUP BY f.tx_id, f.token_id, t.inclusion_height
+                 HAVING SUM(f.amount) <> 0",
+                [first_tx_index],
+            )?;
+
+            conn.execute_batch(
+                "DROP TABLE bulk_token_flows;
+                 DROP TABLE bulk_flows;
+                 DROP TABLE bulk_spent;",
+            )?;
+
+            for token in collected.iter().flat_map(|ops| &ops.tokens) {
+                insert_token(conn, token)?;
+            }
+
+            enqueue_webhook_deliveries(conn, first_box_index)?;
+
+            for b in collected.iter().filter_map(|ops| ops.block.as_ref()) {
+                if b.height % 100 == 0 {
+                    update_network_stats_sync(conn, emission, b.height, b.timestamp, b.difficulty)?;
+                }
+                if (b.height + 1) % EPOCH_LENGTH == 0 {
+                    update_epoch(conn, b.height / EPOCH_LENGTH)?;
+                }
+            }
+
+            let heights = collected.iter().filter_map(|ops| ops.block.as_ref()).map(|b| b.height);
+            if let (Some(from), Some(to)) = (heights.clone().min(), heights.max()) {
+                mempool::remove_confirmed(conn, from, to)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Roll the index back to `fork_height`, the last height where our chain and
+    /// the node's chain agree. Everything settled above it is undone: spent boxes
+    /// become unspent again, orphaned transactions, boxes, assets, inputs and
+    /// tokens are removed, and the orphaned blocks are kept with `main_chain = FALSE`.
+    /// Returns the number of blocks that were orphaned.
+    pub fn rollback_to(&mut self, fork_height: i64) -> Result<i64> {
+        let orphaned: i64 = self
+            .db
+            .query_one(
+                "SELECT COUNT(*) FROM blocks WHERE height > ? AND main_chain = TRUE",
+                [fork_height],
+                |row| row.get(0),
+            )?
+            .unwrap_or(0);
+
+        if orphaned == 0 {
+            return Ok(0);
+        }
+
+        self.db.execute_transaction(|conn| {
+            // Undo balances: orphaned unspent outputs go away, older boxes spent
+            // above the fork come back
+            conn.execute(
+                "UPDATE address_stats SET balance = address_stats.balance + d.delta
+                 FROM (
+                     SELECT address,
+                            SUM(CASE WHEN settlement_height > ? THEN -value ELSE value END) AS delta
+                     FROM boxes
+                     WHERE (settlement_height > ? AND spent_tx_id IS NULL)
+                        OR (settlement_height <= ? AND spent_height > ?)
+                     GROUP BY address
+                 ) d
+                 WHERE address_stats.address = d.address",
+                params![fork_height, fork_height, fork_height, fork_height],
+            )?;
+
+            // Undo the per-output address counters before the boxes disappear
+            conn.execute(
+                "UPDATE address_stats SET tx_count = address_stats.tx_count - d.cnt
+                 FROM (
+                     SELECT address, COUNT(*) AS cnt FROM boxes
+                     WHERE settlement_height > ?
+                     GROUP BY address
+                 ) d
+                 WHERE address_stats.address = d.address",
+                [fork_height],
+            )?;
+            conn.execute("DELETE FROM address_stats WHERE tx_count <= 0", [])?;
+
+            // Seen heights come back from the outputs that survive the fork
+            conn.execute(
+                "UPDATE address_stats SET
+                    first_seen_height = h.first_seen_height,
+                    last_seen_height = h.last_seen_height
+                 FROM (
+                     SELECT address,
+                            MIN(settlement_height) AS first_seen_height,
+                            MAX(settlement_height) AS last_seen_height
+                     FROM boxes
+                     WHERE settlement_height <= ?
+                       AND address IN (SELECT address FROM boxes WHERE settlement_height > ?)
+                     GROUP BY address
+                 ) h
+                 WHERE address_stats.address = h.address",
+                params![fork_height, fork_height],
+            )?;
+
+            // Holders: outputs created above the fork that are still unspent go away,
+            // older boxes spent above the fork come back
+            conn.execute(
+                "INSERT INTO token_holders (token_id, address, amount)
+                 SELECT ba.token_id, b.address,
+                        SUM(CASE WHEN b.settlement_height > ? THEN -ba.amount ELSE ba.amount END)
+                 FROM box_assets ba
+                 JOIN boxes b ON ba.box_id = b.box_id
+                 WHERE (b.settlement_height > ? AND b.spent_tx_id IS NULL)
+                    OR (b.settlement_height <= ? AND b.spent_height > ?)
+                 GROUP BY ba.token_id, b.address
+                 ON CONFLICT (token_id, address) DO UPDATE SET
+                    amount = token_holders.amount + EXCLUDED.amount",
+                params![fork_height, fork_height, fork_height, fork_height],
+            )?;
+            conn.execute("DELETE FROM token_holders WHERE amount <= 0", [])?;
+
+            // Boxes spent by orphaned transactions are unspent again
+            conn.execute(
+                "UPDATE boxes SET spent_tx_id = NULL, spent_index = NULL, spent_height = NULL
+                 WHERE spent_height > ?",
+                [fork_height],
+            )?;
+
+            for table in ["box_assets", "box_registers", "script_constants"] {
+                conn.execute(
+                    &format!(
+                        "DELETE FROM {} WHERE box_id IN (
+                             SELECT box_id FROM boxes WHERE settlement_height > ?
+                         )",
+                        table
+                    ),
+                    [fork_height],
+                )?;
+            }
+            conn.execute(
+                "DELETE FROM search_index WHERE entity_type = 'token' AND entity_id IN (
+                     SELECT token_id FROM tokens WHERE creation_height > ?
+                 )",
+                [fork_height],
+            )?;
+            conn.execute(
+                "DELETE FROM tokens WHERE creation_height > ?",
+                [fork_height],
+            )?;
+            conn.execute("DELETE FROM token_events WHERE height > ?", [fork_height])?;
+            conn.execute(
+                "DELETE FROM address_activity WHERE height > ?",
+                [fork_height],
+            )?;
+            conn.execute(
+                "DELETE FROM address_token_activity WHERE height > ?",
+                [fork_height],
+            )?;
+            conn.execute(
+                "DELETE FROM inputs WHERE tx_id IN (
+                     SELECT tx_id FROM transactions WHERE inclusion_height > ?
+                 )",
+                [fork_height],
+            )?;
+            conn.execute(
+                "DELETE FROM data_inputs WHERE tx_id IN (
+                     SELECT tx_id FROM transactions WHERE inclusion_height > ?
+                 )",
+                [fork_height],
+            )?;
+            conn.execute(
+                "DELETE FROM boxes WHERE settlement_height > ?",
+                [fork_height],
+            )?;
+            conn.execute(
+                "DELETE FROM transactions WHERE inclusion_height > ?",
+                [fork_height],
+            )?;
+            conn.execute("DELETE FROM network_stats WHERE height > ?", [fork_height])?;
+            conn.execute("DELETE FROM block_emission WHERE height > ?", [fork_height])?;
+
+            // Keep orphaned headers around, but off the main chain
+            conn.execute(
+                "UPDATE blocks SET main_chain = FALSE WHERE height > ? AND main_chain = TRUE",
+                [fork_height],
+            )?;
+
+            // Deliveries already sent can't be recalled; pending ones are dropped
+            conn.execute(
+                "DELETE FROM webhook_deliveries WHERE height > ? AND status <> 'delivered'",
+                [fork_height],
+            )?;
+
+            let fork_epoch = fork_height / EPOCH_LENGTH;
+            conn.execute("DELETE FROM epochs WHERE epoch_index > ?", [fork_epoch])?;
+            update_epoch(conn, fork_epoch)?;
+
+            Ok(())
+        })?;
+
+        // Rewind the global index counters to the new tip
+        let db = self.db.clone();
+        *self = BlockProcessor::new(db, self.network);
+
+        Ok(orphaned)
+    }
+
+    /// Parse a block and collect the rows it adds, advancing the global counters
+    fn collect_block(&mut self, block: &Value) -> Result<CollectedOps> {
+        let header = block.get("header").context("Missing header")?;
+        let block_txs = block
+            .get("blockTransactions")
+            .context("Missing blockTransactions")?;
+        let transactions = block_txs
+            .get("transactions")
+            .and_then(|t| t.as_array())
+            .context("Missing transactions array")?;
+
+        // Extract header data
+        let block_id = header
+            .get("id")
+            .and_then(|v| v.as_str())
+            .context("Missing block id")?
+            .to_string();
+        let parent_id = header
+            .get("parentId")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let height = header
+            .get("height")
+            .and_then(|v| v.as_i64())
+            .context("Missing height")?;
+        let timestamp = header
+            .get("timestamp")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0);
+        let difficulty = header
+            .get("difficulty")
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.parse::<i64>().ok())
+            .unwrap_or(0);
+        let miner_pk = header.get("minerPk").and_then(|v| v.as_str()).unwrap_or("");
+
+        // Calculate block metrics
+        let tx_count = transactions.len() as i32;
+        let block_size: i32 = transactions
+            .iter()
+            .filter_map(|tx| tx.get("size").and_then(|s| s.as_i64()))
+            .sum::<i64>() as i32;
+
+        let block_coins: i64 = transactions
+            .iter()
+            .map(|tx| {
+                tx.get("outputs")
+                    .and_then(|o| o.as_array())
+                    .map(|outputs| {
+                        outputs
+                            .iter()
+                            .filter_map(|o| o.get("value").and_then(|v| v.as_i64()))
+                            .sum::<i64>()
+                    })
+                    .unwrap_or(0)
+            })
+            .sum();
+
+        // Derive miner address from minerPk
+        let miner_address = if !miner_pk.is_empty() {
+            ergo_tree::miner_pk_to_address(miner_pk, self.network)
+        } else {
+            None
+        };
+
+        // Get miner reward from first transaction (coinbase)
+        let miner_reward = transactions
+            .first()
+            .and_then(|tx| tx.get("outputs"))
+            .and_then(|o| o.as_array())
+            .and_then(|outputs| outputs.first())
+            .and_then(|o| o.get("value"))
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0);
 
-                if !exists {
-                    conn.execute(
-                        "INSERT INTO tokens (token_id, box_id, emission_amount, name, description, token_type, decimals, creation_height)
-                         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-                        params![
-                            token.token_id,
-                            token.box_id,
-                            token.emission_amount,
-                            token.name,
-                            token.description,
-                            token.token_type,
-                            token.decimals,
-                            token.creation_height
-                        ],
-                    )?;
-                }
-            }
+        self.global_block_index += 1;
+        let global_block_index = self.global_block_index;
 
-            // Update address stats
-            let now = chrono::Utc::now().timestamp();
-            for addr in &collected.addresses {
-                conn.execute(
-                    "INSERT INTO address_stats (address, tx_count, first_seen_height, last_seen_height, updated_at)
-                     VALUES (?, 1, ?, ?, ?)
-                     ON CONFLICT (address) DO UPDATE SET
-                        tx_count = address_stats.tx_count + 1,
-                        last_seen_height = EXCLUDED.last_seen_height,
-                        updated_at = EXCLUDED.updated_at",
-                    params![addr.address, addr.height, addr.height, now],
-                )?;
-            }
+        // Collect all operations for this block
+        let mut collected = CollectedOps::new();
 
-            // Update network stats periodically (every 100 blocks)
-            if update_stats {
-                if let Some(ref b) = collected.block {
-                    update_network_stats_sync(conn, b.height, b.timestamp, b.difficulty)?;
-                }
-            }
+        // Collect block data
+        collected.block = Some(BlockData {
+            block_id: block_id.clone(),
+            parent_id,
+            height,
+            timestamp,
+            difficulty,
+            block_size,
+            block_coins,
+            tx_count,
+            miner_address,
+            miner_reward,
+            global_index: global_block_index,
+        });
 
-            Ok(())
-        })?;
+        // Process transactions and collect operations
+        for (tx_idx, tx) in transactions.iter().enumerate() {
+            self.collect_transaction_ops(
+                tx,
+                &block_id,
+                height,
+                timestamp,
+                tx_idx as i32,
+                &mut collected,
+            )?;
+        }
 
-        Ok(())
+        Ok(collected)
     }
 
     fn collect_transaction_ops(
@@ -387,22 +970,29 @@ impl BlockProcessor {
         tx_idx: i32,
         collected: &mut CollectedOps,
     ) -> Result<()> {
-        let tx_id = tx.get("id").and_then(|v| v.as_str()).context("Missing tx id")?.to_string();
+        let tx_id = tx
+            .get("id")
+            .and_then(|v| v.as_str())
+            .context("Missing tx id")?
+            .to_string();
         let inputs = tx.get("inputs").and_then(|v| v.as_array());
-        let outputs = tx.get("outputs").and_then(|v| v.as_array()).context("Missing outputs")?;
+        let outputs = tx
+            .get("outputs")
+            .and_then(|v| v.as_array())
+            .context("Missing outputs")?;
         let data_inputs = tx.get("dataInputs").and_then(|v| v.as_array());
         let size = tx.get("size").and_then(|v| v.as_i64()).unwrap_or(0) as i32;
 
         let input_count = inputs.map(|i| i.len()).unwrap_or(0) as i32;
         let output_count = outputs.len() as i32;
         let coinbase = input_count == 0 || tx_idx == 0;
-
-        // Get first input's box_id for minting detection
-        let first_input_box_id = inputs
-            .and_then(|i| i.first())
-            .and_then(|inp| inp.get("boxId"))
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+        let fee: i64 = outputs
+            .iter()
+            .filter(|o| {
+                o.get("ergoTree").and_then(|v| v.as_str()) == Some(ergo_tree::MINERS_FEE_ERGO_TREE)
+            })
+            .filter_map(|o| o.get("value").and_then(|v| v.as_i64()))
+            .sum();
 
         self.global_tx_index += 1;
 
@@ -417,6 +1007,7 @@ impl BlockProcessor {
             size,
             input_count,
             output_count,
+            fee,
         });
 
         // Collect inputs
@@ -435,9 +1026,11 @@ impl BlockProcessor {
 
         // Collect outputs
         for (output_idx, output) in outputs.iter().enumerate() {
-            self.collect_output(output, &tx_id, height, output_idx as i32, first_input_box_id.as_deref(), collected)?;
+            self.collect_output(output, &tx_id, height, output_idx as i32, collected)?;
         }
 
+        collected.tokens.extend(minted_token(tx, height));
+
         Ok(())
     }
 
@@ -449,7 +1042,10 @@ impl BlockProcessor {
         input_idx: i32,
         collected: &mut CollectedOps,
     ) -> Result<()> {
-        let box_id = input.get("boxId").and_then(|v| v.as_str()).context("Missing boxId")?;
+        let box_id = input
+            .get("boxId")
+            .and_then(|v| v.as_str())
+            .context("Missing boxId")?;
         let proof_bytes = input
             .get("spendingProof")
             .and_then(|sp| sp.get("proofBytes"))
@@ -477,7 +1073,10 @@ impl BlockProcessor {
         input_idx: i32,
         collected: &mut CollectedOps,
     ) -> Result<()> {
-        let box_id = data_input.get("boxId").and_then(|v| v.as_str()).context("Missing boxId")?;
+        let box_id = data_input
+            .get("boxId")
+            .and_then(|v| v.as_str())
+            .context("Missing boxId")?;
 
         self.data_input_id += 1;
 
@@ -497,13 +1096,21 @@ impl BlockProcessor {
         tx_id: &str,
         height: i64,
         output_idx: i32,
-        first_input_box_id: Option<&str>,
         collected: &mut CollectedOps,
     ) -> Result<()> {
-        let box_id = output.get("boxId").and_then(|v| v.as_str()).context("Missing boxId")?;
+        let box_id = output
+            .get("boxId")
+            .and_then(|v| v.as_str())
+            .context("Missing boxId")?;
         let value = output.get("value").and_then(|v| v.as_i64()).unwrap_or(0);
-        let ergo_tree_hex = output.get("ergoTree").and_then(|v| v.as_str()).unwrap_or("");
-        let creation_height = output.get("creationHeight").and_then(|v| v.as_i64()).unwrap_or(height);
+        let ergo_tree_hex = output
+            .get("ergoTree")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let creation_height = output
+            .get("creationHeight")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(height);
         let additional_registers = output.get("additionalRegisters");
         let assets = output.get("assets").and_then(|v| v.as_array());
 
@@ -511,7 +1118,7 @@ impl BlockProcessor {
         // The Ergo node uses P2SH (short hash) for scripts > 255 bytes, but the
         // official Ergo Explorer always uses P2S (full script), so we must derive
         // addresses ourselves to match.
-        let address = ergo_tree::ergo_tree_to_address(ergo_tree_hex)
+        let address = ergo_tree::ergo_tree_to_address(ergo_tree_hex, self.network)
             .unwrap_or_else(|| ergo_tree_hex.to_string());
         let template_hash = Some(ergo_tree::ergo_tree_template_hash(ergo_tree_hex));
 
@@ -533,24 +1140,21 @@ impl BlockProcessor {
             registers_json,
         });
 
+        // Decoded registers and constants for box search
+        collected
+            .registers
+            .extend(decode_registers(box_id, additional_registers));
+        collected
+            .constants
+            .extend(decode_script_constants(box_id, ergo_tree_hex));
+
         // Collect address for stats update
-        collected.addresses.push(AddressData {
-            address,
-            height,
-        });
+        collected.addresses.push(AddressData { address, height });
 
         // Collect assets
         if let Some(assets) = assets {
             for (asset_idx, asset) in assets.iter().enumerate() {
-                self.collect_asset(
-                    asset,
-                    box_id,
-                    height,
-                    asset_idx as i32,
-                    first_input_box_id,
-                    additional_registers,
-                    collected,
-                )?;
+                self.collect_asset(asset, box_id, asset_idx as i32, collected)?;
             }
         }
 
@@ -561,13 +1165,13 @@ impl BlockProcessor {
         &mut self,
         asset: &Value,
         box_id: &str,
-        height: i64,
         asset_idx: i32,
-        first_input_box_id: Option<&str>,
-        registers: Option<&Value>,
         collected: &mut CollectedOps,
     ) -> Result<()> {
-        let token_id = asset.get("tokenId").and_then(|v| v.as_str()).context("Missing tokenId")?;
+        let token_id = asset
+            .get("tokenId")
+            .and_then(|v| v.as_str())
+            .context("Missing tokenId")?;
         let amount = asset.get("amount").and_then(|v| v.as_i64()).unwrap_or(0);
 
         self.box_asset_id += 1;
@@ -580,32 +1184,492 @@ impl BlockProcessor {
             asset_index: asset_idx,
         });
 
-        // Check if this is a minting transaction
-        if asset_idx == 0 {
-            let is_minting = first_input_box_id.map(|id| id == token_id).unwrap_or(false);
-            if is_minting {
-                let (name, description, token_type, decimals) = extract_token_metadata(registers);
-                collected.tokens.push(TokenData {
-                    token_id: token_id.to_string(),
-                    box_id: box_id.to_string(),
-                    emission_amount: amount,
-                    name,
-                    description,
-                    token_type,
-                    decimals,
-                    creation_height: height,
-                });
+        Ok(())
+    }
+}
+
+/// The token issued by a transaction, if any. A new token's id is the id of the transaction's
+/// first input; it may sit in any asset slot of any output, and the amounts of all
+/// outputs carrying it make up the emission. EIP-4 metadata is read from the
+/// registers of the first output that carries it.
+pub fn minted_token(tx: &Value, height: i64) -> Option<TokenData> {
+    let first_input_box_id = tx
+        .get("inputs")
+        .and_then(|v| v.as_array())
+        .and_then(|inputs| inputs.first())
+        .and_then(|input| input.get("boxId"))
+        .and_then(|v| v.as_str())?;
+    let outputs = tx.get("outputs").and_then(|v| v.as_array())?;
+
+    let mut minted: Option<TokenData> = None;
+    for output in outputs {
+        let Some(assets) = output.get("assets").and_then(|v| v.as_array()) else {
+            continue;
+        };
+        for asset in assets {
+            if asset.get("tokenId").and_then(|v| v.as_str()) != Some(first_input_box_id) {
+                continue;
+            }
+            let amount = asset.get("amount").and_then(|v| v.as_i64()).unwrap_or(0);
+            match minted.as_mut() {
+                Some(token) => token.emission_amount += amount,
+                None => {
+                    let registers = output.get("additionalRegisters");
+                    let (name, description, token_type, decimals) =
+                        extract_token_metadata(registers);
+                    minted = Some(TokenData {
+                        token_id: first_input_box_id.to_string(),
+                        box_id: output
+                            .get("boxId")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .to_string(),
+                        emission_amount: amount,
+                        name,
+                        description,
+                        token_type,
+                        decimals,
+                        creation_height: height,
+                        nft: extract_nft_metadata(registers),
+                    });
+                }
             }
         }
+    }
 
-        Ok(())
+    minted
+}
+
+/// Insert a newly minted token and its search document; existing tokens are left alone
+pub fn insert_token(conn: &Connection, token: &TokenData) -> Result<()> {
+    let exists: bool = conn
+        .query_row(
+            "SELECT 1 FROM tokens WHERE token_id = ?",
+            [&token.token_id],
+            |_| Ok(true),
+        )
+        .unwrap_or(false);
+    if exists {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO tokens (
+            token_id, box_id, emission_amount, name, description, token_type, decimals, creation_height,
+            asset_type, content_hash, content_url, cover_url
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            token.token_id,
+            token.box_id,
+            token.emission_amount,
+            token.name,
+            token.description,
+            token.token_type,
+            token.decimals,
+            token.creation_height,
+            token.nft.asset_type,
+            token.nft.content_hash,
+            token.nft.content_url,
+            token.nft.cover_url
+        ],
+    )?;
+    upsert_token_document(
+        conn,
+        &token.token_id,
+        token.name.as_deref(),
+        token.description.as_deref(),
+    )
+}
+
+/// Make a token findable by name and description through /search
+pub fn upsert_token_document(
+    conn: &Connection,
+    token_id: &str,
+    name: Option<&str>,
+    description: Option<&str>,
+) -> Result<()> {
+    let text = format!("{} {}", name.unwrap_or(""), description.unwrap_or(""));
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO search_index (entity_type, entity_id, search_text)
+         VALUES ('token', ?, ?)
+         ON CONFLICT (entity_type, entity_id) DO UPDATE SET search_text = EXCLUDED.search_text",
+        params![token_id, text],
+    )?;
+    Ok(())
+}
+
+/// Stream the rows collected for a run of blocks into their tables
+fn append_collected(
+    conn: &Connection,
+    rules: &EmissionRules,
+    collected: &[CollectedOps],
+) -> Result<()> {
+    let mut app = conn.appender_with_columns(
+        "blocks",
+        &[
+            "block_id",
+            "parent_id",
+            "height",
+            "timestamp",
+            "difficulty",
+            "block_size",
+            "block_coins",
+            "tx_count",
+            "miner_address",
+            "miner_reward",
+            "main_chain",
+            "global_index",
+        ],
+    )?;
+    for b in collected.iter().filter_map(|ops| ops.block.as_ref()) {
+        app.append_row(params![
+            b.block_id,
+            b.parent_id,
+            b.height,
+            b.timestamp,
+            b.difficulty,
+            b.block_size,
+            b.block_coins,
+            b.tx_count,
+            b.miner_address,
+            b.miner_reward,
+            true,
+            b.global_index
+        ])?;
+    }
+    app.flush()?;
+
+    let mut app = conn.appender_with_columns(
+        "block_emission",
+        &[
+            "height",
+            "emission",
+            "miner_reward",
+            "foundation_reward",
+            "reemission_charge",
+            "reemission_payout",
+            "supply",
+            "circulating_supply",
+        ],
+    )?;
+    for b in collected.iter().filter_map(|ops| ops.block.as_ref()) {
+        let e = rules.block_emission(b.height);
+        app.append_row(params![
+            b.height,
+            e.emission,
+            e.miner_reward,
+            e.foundation_reward,
+            e.reemission_charge,
+            e.reemission_payout,
+            rules.issued_coins_after_height(b.height),
+            rules.circulating_supply_after_height(b.height)
+        ])?;
+    }
+    app.flush()?;
+
+    let mut app = conn.appender_with_columns(
+        "transactions",
+        &[
+            "tx_id",
+            "block_id",
+            "inclusion_height",
+            "timestamp",
+            "index_in_block",
+            "global_index",
+            "coinbase",
+            "size",
+            "input_count",
+            "output_count",
+            "fee",
+        ],
+    )?;
+    for tx in collected.iter().flat_map(|ops| &ops.transactions) {
+        app.append_row(params![
+            tx.tx_id,
+            tx.block_id,
+            tx.inclusion_height,
+            tx.timestamp,
+            tx.index_in_block,
+            tx.global_index,
+            tx.coinbase,
+            tx.size,
+            tx.input_count,
+            tx.output_count,
+            tx.fee
+        ])?;
+    }
+    app.flush()?;
+
+    let mut app = conn.appender_with_columns(
+        "boxes",
+        &[
+            "box_id",
+            "tx_id",
+            "output_index",
+            "ergo_tree",
+            "ergo_tree_template_hash",
+            "address",
+            "value",
+            "creation_height",
+            "settlement_height",
+            "global_index",
+            "additional_registers",
+        ],
+    )?;
+    for b in collected.iter().flat_map(|ops| &ops.boxes) {
+        app.append_row(params![
+            b.box_id,
+            b.tx_id,
+            b.output_index,
+            b.ergo_tree,
+            b.template_hash,
+            b.address,
+            b.value,
+            b.creation_height,
+            b.settlement_height,
+            b.global_index,
+            b.registers_json
+        ])?;
+    }
+    app.flush()?;
+
+    let mut app = conn.appender_with_columns(
+        "box_assets",
+        &["id", "box_id", "token_id", "amount", "asset_index"],
+    )?;
+    for a in collected.iter().flat_map(|ops| &ops.box_assets) {
+        app.append_row(params![a.id, a.box_id, a.token_id, a.amount, a.asset_index])?;
+    }
+    app.flush()?;
+
+    let mut app = conn.appender_with_columns(
+        "box_registers",
+        &[
+            "box_id",
+            "register_id",
+            "serialized_value",
+            "sigma_type",
+            "rendered_value",
+        ],
+    )?;
+    for r in collected.iter().flat_map(|ops| &ops.registers) {
+        app.append_row(params![
+            r.box_id,
+            r.register_id,
+            r.serialized_value,
+            r.sigma_type,
+            r.rendered_value
+        ])?;
+    }
+    app.flush()?;
+
+    let mut app = conn.appender_with_columns(
+        "script_constants",
+        &[
+            "box_id",
+            "constant_index",
+            "serialized_value",
+            "sigma_type",
+            "rendered_value",
+        ],
+    )?;
+    for c in collected.iter().flat_map(|ops| &ops.constants) {
+        app.append_row(params![
+            c.box_id,
+            c.constant_index,
+            c.serialized_value,
+            c.sigma_type,
+            c.rendered_value
+        ])?;
+    }
+    app.flush()?;
+
+    let mut app = conn.appender_with_columns(
+        "inputs",
+        &["id", "tx_id", "box_id", "input_index", "proof_bytes"],
+    )?;
+    for i in collected.iter().flat_map(|ops| &ops.inputs) {
+        app.append_row(params![
+            i.id,
+            i.tx_id,
+            i.box_id,
+            i.input_index,
+            i.proof_bytes
+        ])?;
     }
+    app.flush()?;
+
+    let mut app =
+        conn.appender_with_columns("data_inputs", &["id", "tx_id", "box_id", "input_index"])?;
+    for di in collected.iter().flat_map(|ops| &ops.data_inputs) {
+        app.append_row(params![di.id, di.tx_id, di.box_id, di.input_index])?;
+    }
+    app.flush()?;
+
+    Ok(())
+}
+
+/// Add per-(token, address) amount changes to token_holders, dropping holders whose
+/// balance reaches zero
+fn apply_token_holder_deltas(
+    conn: &Connection,
+    deltas: &HashMap<(String, String), i64>,
+) -> Result<()> {
+    for ((token_id, address), delta) in deltas {
+        if *delta == 0 {
+            continue;
+        }
+        conn.execute(
+            "INSERT INTO token_holders (token_id, address, amount) VALUES (?, ?, ?)
+             ON CONFLICT (token_id, address) DO UPDATE SET
+                amount = token_holders.amount + EXCLUDED.amount",
+            params![token_id, address, delta],
+        )?;
+        if *delta < 0 {
+            conn.execute(
+                "DELETE FROM token_holders WHERE token_id = ? AND address = ? AND amount <= 0",
+                params![token_id, address],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Queue a webhook delivery for every output above `after_box_index` that matches
+/// a watch. Delivery waits until the output's block has the watch's number of
+/// confirmations.
+fn enqueue_webhook_deliveries(conn: &Connection, after_box_index: i64) -> Result<()> {
+    let watches: i64 = conn.query_row("SELECT COUNT(*) FROM watches", [], |row| row.get(0))?;
+    if watches == 0 {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    conn.execute(
+        "INSERT INTO webhook_deliveries (watch_id, box_id, height, next_attempt_at, created_at)
+         SELECT DISTINCT watch_id, box_id, height, ?, ? FROM (
+             SELECT w.watch_id, b.box_id, b.settlement_height AS height
+             FROM boxes b JOIN watches w ON w.watch_type = 'address' AND w.target = b.address
+             WHERE b.global_index > ?
+             UNION ALL
+             SELECT w.watch_id, b.box_id, b.settlement_height
+             FROM boxes b JOIN watches w
+               ON w.watch_type = 'template' AND w.target = b.ergo_tree_template_hash
+             WHERE b.global_index > ?
+             UNION ALL
+             SELECT w.watch_id, b.box_id, b.settlement_height
+             FROM boxes b
+             JOIN box_assets ba ON ba.box_id = b.box_id
+             JOIN watches w ON w.watch_type = 'token' AND w.target = ba.token_id
+             WHERE b.global_index > ?
+         )
+         ON CONFLICT DO NOTHING",
+        params![now, now, after_box_index, after_box_index, after_box_index],
+    )?;
+    Ok(())
+}
+
+/// (Re)compute the aggregates of one epoch from the main-chain blocks in its
+/// height range. Fees are outputs to the miners' fee contract; emission is what
+/// each coinbase took out of the emission box.
+pub fn update_epoch(conn: &Connection, epoch_index: i64) -> Result<()> {
+    let height_start = epoch_index * EPOCH_LENGTH;
+    let height_end = height_start + EPOCH_LENGTH - 1;
+
+    let total_fees: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(value), 0) FROM boxes
+         WHERE ergo_tree_template_hash = ? AND ergo_tree = ?
+           AND settlement_height BETWEEN ? AND ?",
+        params![
+            ergo_tree::MINERS_FEE_TEMPLATE_HASH,
+            ergo_tree::MINERS_FEE_ERGO_TREE,
+            height_start,
+            height_end
+        ],
+        |row| row.get(0),
+    )?;
+
+    let total_emission: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(GREATEST(inp.value - out.value, 0)), 0)
+         FROM transactions t
+         JOIN boxes out ON out.tx_id = t.tx_id AND out.output_index = 0
+         JOIN boxes inp ON inp.spent_tx_id = t.tx_id
+         WHERE t.index_in_block = 0 AND t.inclusion_height BETWEEN ? AND ?",
+        params![height_start, height_end],
+        |row| row.get(0),
+    )?;
+
+    conn.execute(
+        "INSERT INTO epochs (
+            epoch_index, height_start, height_end, timestamp_start, timestamp_end, block_count,
+            tx_count, total_fees, total_emission, avg_difficulty, avg_block_time
+        )
+        SELECT ?, ?, MAX(height), MIN(timestamp), MAX(timestamp), COUNT(*),
+               SUM(tx_count), ?, ?, AVG(difficulty),
+               CASE WHEN COUNT(*) > 1
+                    THEN (MAX(timestamp) - MIN(timestamp)) / (COUNT(*) - 1)
+               END
+        FROM blocks
+        WHERE height BETWEEN ? AND ? AND main_chain = TRUE
+        HAVING COUNT(*) > 0
+        ON CONFLICT (epoch_index) DO UPDATE SET
+            height_end = EXCLUDED.height_end,
+            timestamp_start = EXCLUDED.timestamp_start,
+            timestamp_end = EXCLUDED.timestamp_end,
+            block_count = EXCLUDED.block_count,
+            tx_count = EXCLUDED.tx_count,
+            total_fees = EXCLUDED.total_fees,
+            total_emission = EXCLUDED.total_emission,
+            avg_difficulty = EXCLUDED.avg_difficulty,
+            avg_block_time = EXCLUDED.avg_block_time",
+        params![
+            epoch_index,
+            height_start,
+            total_fees,
+            total_emission,
+            height_start,
+            height_end
+        ],
+    )?;
+
+    Ok(())
+}
+
+/// Record the emission split of the block at `height` along with the supply after it
+pub fn insert_block_emission(conn: &Connection, rules: &EmissionRules, height: i64) -> Result<()> {
+    let e = rules.block_emission(height);
+    conn.execute(
+        "INSERT INTO block_emission (
+            height, emission, miner_reward, foundation_reward, reemission_charge,
+            reemission_payout, supply, circulating_supply
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (height) DO NOTHING",
+        params![
+            height,
+            e.emission,
+            e.miner_reward,
+            e.foundation_reward,
+            e.reemission_charge,
+            e.reemission_payout,
+            rules.issued_coins_after_height(height),
+            rules.circulating_supply_after_height(height)
+        ],
+    )?;
+    Ok(())
 }
 
 // Helper function to update network stats within a transaction
-fn update_network_stats_sync(conn: &Connection, height: i64, timestamp: i64, difficulty: i64) -> Result<()> {
-    // Use a simpler calculation that doesn't require full table scan
-    // Just get the block's values directly
+fn update_network_stats_sync(
+    conn: &Connection,
+    rules: &EmissionRules,
+    height: i64,
+    timestamp: i64,
+    difficulty: i64,
+) -> Result<()> {
     let block_info: Option<(i64, i64)> = conn
         .query_row(
             "SELECT COALESCE(block_size, 0), COALESCE(block_coins, 0) FROM blocks WHERE height = ?",
@@ -615,20 +1679,26 @@ fn update_network_stats_sync(conn: &Connection, height: i64, timestamp: i64, dif
         .ok();
 
     let (block_size, block_coins) = block_info.unwrap_or((0, 0));
-
-    // Estimate total coins from recent blocks rather than full scan
-    let estimated_supply = height * 75_000_000_000i64; // ~75 ERG average reward per block
-
-    // Simple hashrate estimate
-    let hashrate = difficulty as f64 / 120.0;
+    let total_coins = rules.issued_coins_after_height(height);
+    let window = block_window_stats(conn, height)?;
 
     conn.execute(
         "INSERT INTO network_stats (
-            timestamp, height, difficulty, block_size, block_coins, total_coins, hashrate
-        ) VALUES (?, ?, ?, ?, ?, ?, ?)
+            timestamp, height, difficulty, block_size, block_coins, total_coins, hashrate, block_time_avg
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?)
         ON CONFLICT (timestamp) DO UPDATE SET
-            height = EXCLUDED.height, difficulty = EXCLUDED.difficulty, hashrate = EXCLUDED.hashrate",
-        params![timestamp, height, difficulty, block_size, block_coins, estimated_supply, hashrate],
+            height = EXCLUDED.height, difficulty = EXCLUDED.difficulty, total_coins = EXCLUDED.total_coins,
+            hashrate = EXCLUDED.hashrate, block_time_avg = EXCLUDED.block_time_avg",
+        params![
+            timestamp,
+            height,
+            difficulty,
+            block_size,
+            block_coins,
+            total_coins,
+            window.map(|w| w.hashrate),
+            window.map(|w| w.block_time_avg)
+        ],
     )?;
 
     Ok(())
@@ -645,6 +1715,8 @@ struct CollectedOps {
     box_assets: Vec<BoxAssetData>,
     tokens: Vec<TokenData>,
     addresses: Vec<AddressData>,
+    registers: Vec<RegisterData>,
+    constants: Vec<ConstantData>,
 }
 
 impl CollectedOps {
@@ -658,6 +1730,8 @@ impl CollectedOps {
             box_assets: Vec::with_capacity(32),
             tokens: Vec::new(),
             addresses: Vec::with_capacity(64),
+            registers: Vec::with_capacity(32),
+            constants: Vec::with_capacity(32),
         }
     }
 }
@@ -687,6 +1761,8 @@ struct TransactionData {
     size: i32,
     input_count: i32,
     output_count: i32,
+    /// nanoERG paid to the miners' fee contract
+    fee: i64,
 }
 
 struct BoxData {
@@ -727,15 +1803,26 @@ struct BoxAssetData {
     asset_index: i32,
 }
 
-struct TokenData {
-    token_id: String,
-    box_id: String,
-    emission_amount: i64,
-    name: Option<String>,
-    description: Option<String>,
-    token_type: Option<String>,
-    decimals: Option<i32>,
-    creation_height: i64,
+pub struct TokenData {
+    pub token_id: String,
+    pub box_id: String,
+    pub emission_amount: i64,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub token_type: Option<String>,
+    pub decimals: Option<i32>,
+    pub creation_height: i64,
+    pub nft: NftMetadata,
+}
+
+/// EIP-4 artwork fields of an issuing box (R7 type, R8 content hash, R9 link)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NftMetadata {
+    pub asset_type: Option<String>,
+    pub content_hash: Option<String>,
+    pub content_url: Option<String>,
+    /// Second R9 link, the cover image of audio NFTs
+    pub cover_url: Option<String>,
 }
 
 struct AddressData {
@@ -743,33 +1830,110 @@ struct AddressData {
     height: i64,
 }
 
-/// Public wrapper for repair use
-pub fn extract_token_metadata_pub(registers: Option<&serde_json::Value>) -> (Option<String>, Option<String>, Option<String>, Option<i32>) {
-    extract_token_metadata(registers)
+pub struct RegisterData {
+    box_id: String,
+    register_id: String,
+    serialized_value: String,
+    sigma_type: Option<String>,
+    rendered_value: Option<String>,
+}
+
+pub struct ConstantData {
+    box_id: String,
+    constant_index: i32,
+    serialized_value: String,
+    sigma_type: String,
+    rendered_value: String,
+}
+
+/// Decode a box's non-mandatory registers. Values that fail to parse are kept
+/// with only their serialized form so they can still be searched.
+pub fn decode_registers(box_id: &str, registers: Option<&Value>) -> Vec<RegisterData> {
+    let Some(registers) = registers.and_then(|r| r.as_object()) else {
+        return Vec::new();
+    };
+
+    let mut result: Vec<RegisterData> = registers
+        .iter()
+        .filter_map(|(register_id, value)| {
+            let serialized = value
+                .as_str()
+                .or_else(|| value.get("serializedValue").and_then(|v| v.as_str()))?;
+            let constant = sigma::decode_constant_hex(serialized);
+            Some(RegisterData {
+                box_id: box_id.to_string(),
+                register_id: register_id.clone(),
+                serialized_value: serialized.to_string(),
+                sigma_type: constant.as_ref().map(|c| c.tpe.to_string()),
+                rendered_value: constant.as_ref().map(|c| sigma::render_value(&c.value)),
+            })
+        })
+        .collect();
+    result.sort_by(|a, b| a.register_id.cmp(&b.register_id));
+    result
+}
+
+/// Decode the segregated constants of a box's ErgoTree
+pub fn decode_script_constants(box_id: &str, ergo_tree_hex: &str) -> Vec<ConstantData> {
+    let Ok(bytes) = hex::decode(ergo_tree_hex) else {
+        return Vec::new();
+    };
+    let Some(parsed) = ergo_tree::parse_ergo_tree(&bytes) else {
+        return Vec::new();
+    };
+
+    parsed
+        .constants
+        .iter()
+        .enumerate()
+        .map(|(idx, c)| ConstantData {
+            box_id: box_id.to_string(),
+            constant_index: idx as i32,
+            serialized_value: hex::encode(c.bytes),
+            sigma_type: c.constant.tpe.to_string(),
+            rendered_value: sigma::render_value(&c.constant.value),
+        })
+        .collect()
+}
+
+/// Insert decoded registers and constants, ignoring rows that already exist
+pub fn insert_registers_and_constants(
+    conn: &Connection,
+    registers: &[RegisterData],
+    constants: &[ConstantData],
+) -> Result<()> {
+    for r in registers {
+        conn.execute(
+            "INSERT INTO box_registers (box_id, register_id, serialized_value, sigma_type, rendered_value)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT DO NOTHING",
+            params![r.box_id, r.register_id, r.serialized_value, r.sigma_type, r.rendered_value],
+        )?;
+    }
+    for c in constants {
+        conn.execute(
+            "INSERT INTO script_constants (box_id, constant_index, serialized_value, sigma_type, rendered_value)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT DO NOTHING",
+            params![c.box_id, c.constant_index, c.serialized_value, c.sigma_type, c.rendered_value],
+        )?;
+    }
+    Ok(())
 }
 
 /// Extract token metadata from box registers
 /// Returns (name, description, token_type, decimals)
-fn extract_token_metadata(registers: Option<&Value>) -> (Option<String>, Option<String>, Option<String>, Option<i32>) {
+fn extract_token_metadata(
+    registers: Option<&Value>,
+) -> (Option<String>, Option<String>, Option<String>, Option<i32>) {
     let registers = match registers {
         Some(r) => r,
         None => return (None, None, None, None),
     };
 
-    let name = registers
-        .get("R4")
-        .and_then(|v| v.as_str())
-        .and_then(decode_sigma_string);
-
-    let description = registers
-        .get("R5")
-        .and_then(|v| v.as_str())
-        .and_then(decode_sigma_string);
-
-    let decimals = registers
-        .get("R6")
-        .and_then(|v| v.as_str())
-        .and_then(decode_sigma_int);
+    let name = register_string(registers, "R4");
+    let description = register_string(registers, "R5");
+    let decimals = register_decimals(registers, "R6");
 
     // Tokens with R4 (name) register follow EIP-004 standard
     let token_type = if name.is_some() {
@@ -781,69 +1945,442 @@ fn extract_token_metadata(registers: Option<&Value>) -> (Option<String>, Option<
     (name, description, token_type, decimals)
 }
 
-/// Decode a Sigma-encoded Coll[Byte] (type 0e) to a UTF-8 string
-fn decode_sigma_string(hex: &str) -> Option<String> {
-    let bytes = hex::decode(hex).ok()?;
+/// EIP-4 asset type names by R7 value
+fn asset_type_name(code: &[u8]) -> Option<&'static str> {
+    match code {
+        [0x01, 0x01] => Some("picture"),
+        [0x01, 0x02] => Some("audio"),
+        [0x01, 0x03] => Some("video"),
+        [0x01, 0x04] => Some("artwork-collection"),
+        [0x01, 0x0f] => Some("file-attachments"),
+        [0x02, 0x01] => Some("membership-threshold"),
+        _ => None,
+    }
+}
+
+fn register_bytes(registers: &Value, id: &str) -> Option<Vec<u8>> {
+    let constant = sigma::decode_constant_hex(registers.get(id)?.as_str()?)?;
+    match constant.value {
+        sigma::SigmaValue::Bytes(bytes) => Some(bytes),
+        _ => None,
+    }
+}
+
+/// Extract EIP-4 NFT fields. R8 and R9 only carry the content hash and links
+/// for the artwork types (R7 = 0x01 0x..).
+fn extract_nft_metadata(registers: Option<&Value>) -> NftMetadata {
+    let Some(registers) = registers else {
+        return NftMetadata::default();
+    };
+    let Some(type_code) = register_bytes(registers, "R7") else {
+        return NftMetadata::default();
+    };
 
-    if bytes.is_empty() {
-        return None;
+    let mut nft = NftMetadata {
+        asset_type: asset_type_name(&type_code).map(|s| s.to_string()),
+        ..Default::default()
+    };
+    if type_code.first() != Some(&0x01) {
+        return nft;
     }
 
-    if bytes[0] == 0x0e {
-        let (len, offset) = decode_vlq(&bytes[1..])?;
-        if offset + 1 + len > bytes.len() {
-            return None;
+    nft.content_hash = register_bytes(registers, "R8").map(hex::encode);
+
+    let link = registers
+        .get("R9")
+        .and_then(|v| v.as_str())
+        .and_then(sigma::decode_constant_hex)
+        .map(|c| c.value);
+    let utf8 = |value: &sigma::SigmaValue| match value {
+        sigma::SigmaValue::Bytes(bytes) => String::from_utf8(bytes.clone()).ok(),
+        _ => None,
+    };
+    match link {
+        Some(value @ sigma::SigmaValue::Bytes(_)) => nft.content_url = utf8(&value),
+        // Audio NFTs: (audio url, cover image url)
+        Some(sigma::SigmaValue::Tuple(items)) if items.len() == 2 => {
+            nft.content_url = utf8(&items[0]);
+            nft.cover_url = utf8(&items[1]);
         }
-        let string_bytes = &bytes[1 + offset..1 + offset + len];
-        return String::from_utf8(string_bytes.to_vec()).ok();
+        _ => {}
     }
 
-    String::from_utf8(bytes).ok()
+    nft
+}
+
+/// A register holding a UTF-8 string as `Coll[Byte]`
+fn register_string(registers: &Value, id: &str) -> Option<String> {
+    String::from_utf8(register_bytes(registers, id)?).ok()
 }
 
-/// Decode a Sigma-encoded integer or Coll[Byte] containing decimal digits
-fn decode_sigma_int(hex: &str) -> Option<i32> {
-    let bytes = hex::decode(hex).ok()?;
+/// Token decimals: EIP-4 stores them as a `Coll[Byte]` of decimal digits, some
+/// older tokens as a numeric constant. Values outside 0..=MAX_TOKEN_DECIMALS are ignored.
+fn register_decimals(registers: &Value, id: &str) -> Option<i32> {
+    let constant = sigma::decode_constant_hex(registers.get(id)?.as_str()?)?;
+    let decimals = match constant.value {
+        sigma::SigmaValue::Bytes(bytes) => String::from_utf8(bytes).ok()?.trim().parse().ok(),
+        sigma::SigmaValue::Byte(v) => Some(v.into()),
+        sigma::SigmaValue::Short(v) => Some(v.into()),
+        sigma::SigmaValue::Int(v) => Some(v),
+        sigma::SigmaValue::Long(v) => i32::try_from(v).ok(),
+        _ => None,
+    }?;
+    (0..=MAX_TOKEN_DECIMALS)
+        .contains(&decimals)
+        .then_some(decimals)
+}
 
-    if bytes.is_empty() {
-        return None;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::mempool::MempoolIndexer;
+    use crate::sync::node_client::MempoolTransaction;
+    use crate::sync::test_fixtures::{block, indexed_db, output, p2pk_tree, rows, tx};
+    use serde_json::json;
+
+    /// alice and bob are funded; a fee-paying transaction spends both to mint a
+    /// token for carol, whose box is spent in the same block, and the token is
+    /// partly burned a block later
+    fn test_blocks() -> Vec<Value> {
+        let alice = p2pk_tree("11");
+        let bob = p2pk_tree("22");
+        let carol = p2pk_tree("33");
+        let fee = ergo_tree::MINERS_FEE_ERGO_TREE;
+        vec![
+            block(
+                1,
+                json!([tx(
+                    "tx1",
+                    &["emission"],
+                    vec![
+                        output("box1", 10_000, &alice, 1, json!([])),
+                        output("box2", 5_000, &bob, 1, json!([])),
+                    ],
+                )]),
+            ),
+            block(
+                2,
+                json!([
+                    tx(
+                        "tx2",
+                        &["box1", "box2"],
+                        vec![
+                            output(
+                                "box3",
+                                9_000,
+                                &carol,
+                                2,
+                                json!([{ "tokenId": "box1", "amount": 100 }])
+                            ),
+                            output("box4", 5_000, &alice, 2, json!([])),
+                            output("box5", 1_000, fee, 2, json!([])),
+                        ],
+                    ),
+                    tx(
+                        "tx3",
+                        &["box3"],
+                        vec![
+                            output(
+                                "box6",
+                                8_500,
+                                &bob,
+                                2,
+                                json!([{ "tokenId": "box1", "amount": 100 }])
+                            ),
+                            output("box7", 500, fee, 2, json!([])),
+                        ],
+                    ),
+                ]),
+            ),
+            block(
+                3,
+                json!([tx(
+                    "tx4",
+                    &["box6", "box4"],
+                    vec![
+                        output(
+                            "box8",
+                            12_500,
+                            &carol,
+                            3,
+                            json!([{ "tokenId": "box1", "amount": 60 }])
+                        ),
+                        output("box9", 1_000, fee, 3, json!([])),
+                    ],
+                )]),
+            ),
+        ]
     }
 
-    match bytes[0] {
-        0x0e => {
-            let (len, offset) = decode_vlq(&bytes[1..])?;
-            if offset + 1 + len > bytes.len() {
-                return None;
-            }
-            let string_bytes = &bytes[1 + offset..1 + offset + len];
-            let s = String::from_utf8(string_bytes.to_vec()).ok()?;
-            s.trim().parse().ok()
+    #[test]
+    fn test_bulk_load_matches_per_block_processing() {
+        let blocks = test_blocks();
+
+        let per_block = indexed_db();
+        let mut processor = BlockProcessor::new(per_block.clone(), Network::Mainnet);
+        for block in &blocks {
+            processor.process_block(block).unwrap();
         }
-        0x04 | 0x05 => {
-            let (value, _) = decode_vlq(&bytes[1..])?;
-            Some(((value >> 1) as i32) ^ -((value & 1) as i32))
+
+        let bulk = indexed_db();
+        let mut processor = BlockProcessor::new(bulk.clone(), Network::Mainnet);
+        processor.process_blocks_bulk(&blocks).unwrap();
+
+        for query in [
+            "SELECT address, tx_count, balance, first_seen_height, last_seen_height FROM address_stats",
+            "SELECT * FROM token_holders",
+            "SELECT * FROM address_activity",
+            "SELECT * FROM address_token_activity",
+            "SELECT * FROM token_events",
+        ] {
+            let expected = rows(&per_block, query);
+            assert!(!expected.is_empty(), "{} is empty", query);
+            assert_eq!(rows(&bulk, query), expected, "{}", query);
         }
-        _ => {
-            let s = String::from_utf8(bytes).ok()?;
-            s.trim().parse().ok()
+
+        // The fee of the two-input transaction is attributed once, to its first input
+        let alice = ergo_tree::ergo_tree_to_address(&p2pk_tree("11"), Network::Mainnet).unwrap();
+        for db in [&per_block, &bulk] {
+            let fees: Vec<(String, i64)> = db
+                .query_all(
+                    "SELECT address, fee FROM address_activity WHERE tx_id = 'tx2' AND fee <> 0",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!(fees, vec![(alice.clone(), 1_000)]);
         }
     }
-}
 
-/// Decode a VLQ encoded integer
-fn decode_vlq(bytes: &[u8]) -> Option<(usize, usize)> {
-    let mut value: usize = 0;
-    let mut shift = 0;
+    #[test]
+    fn test_rollback_matches_processing_up_to_fork() {
+        let blocks = test_blocks();
+
+        for fork_height in [1, 2] {
+            let rolled_back = indexed_db();
+            let mut processor = BlockProcessor::new(rolled_back.clone(), Network::Mainnet);
+            for block in &blocks {
+                processor.process_block(block).unwrap();
+            }
+            // The sync loop refreshes the open epoch after each cycle
+            rolled_back
+                .execute_transaction(|conn| update_epoch(conn, 0))
+                .unwrap();
+            let orphaned = processor.rollback_to(fork_height).unwrap();
+            assert_eq!(orphaned, blocks.len() as i64 - fork_height);
+
+            let expected = indexed_db();
+            let mut processor = BlockProcessor::new(expected.clone(), Network::Mainnet);
+            for block in &blocks[..fork_height as usize] {
+                processor.process_block(block).unwrap();
+            }
+            expected
+                .execute_transaction(|conn| update_epoch(conn, 0))
+                .unwrap();
+
+            for query in [
+                "SELECT * FROM boxes",
+                "SELECT address, tx_count, balance, first_seen_height, last_seen_height FROM address_stats",
+                "SELECT * FROM token_holders",
+                "SELECT * FROM token_events",
+                "SELECT * FROM address_activity",
+                "SELECT * FROM epochs",
+            ] {
+                assert_eq!(
+                    rows(&rolled_back, query),
+                    rows(&expected, query),
+                    "{} after rollback to {}",
+                    query,
+                    fork_height
+                );
+            }
+        }
+    }
 
-    for (i, &byte) in bytes.iter().enumerate() {
-        value |= ((byte & 0x7f) as usize) << shift;
-        if byte & 0x80 == 0 {
-            return Some((value, i + 1));
+    /// A fixture transaction as the node's mempool serves it
+    fn pooled(tx: &Value) -> MempoolTransaction {
+        let mut tx = tx.clone();
+        for input in tx["inputs"].as_array_mut().unwrap() {
+            input["spendingProof"] = json!({ "proofBytes": "", "extension": {} });
         }
-        shift += 7;
-        if shift > 35 {
-            return None;
+        serde_json::from_value(tx).unwrap()
+    }
+
+    #[test]
+    fn test_confirmed_transactions_leave_the_mempool() {
+        let blocks = test_blocks();
+        let tx2 = &blocks[1]["blockTransactions"]["transactions"][0];
+        // Spends box2 like tx2 does, so it can never confirm once tx2 has
+        let conflicting = tx(
+            "tx2b",
+            &["box2"],
+            vec![output("box2b", 5_000, &p2pk_tree("44"), 2, json!([]))],
+        );
+
+        for bulk in [false, true] {
+            let db = indexed_db();
+            let mut processor = BlockProcessor::new(db.clone(), Network::Mainnet);
+            processor.process_block(&blocks[0]).unwrap();
+            MempoolIndexer::new(db.clone(), Network::Mainnet)
+                .apply_snapshot(&[pooled(tx2), pooled(&conflicting)])
+                .unwrap();
+            assert_eq!(rows(&db, "SELECT tx_id FROM mempool_transactions").len(), 2);
+
+            if bulk {
+                processor.process_blocks_bulk(&blocks[1..2]).unwrap();
+            } else {
+                processor.process_block(&blocks[1]).unwrap();
+            }
+
+            for table in [
+                "mempool_transactions",
+                "mempool_outputs",
+                "mempool_inputs",
+                "mempool_assets",
+            ] {
+                let left = rows(&db, &format!("SELECT * FROM {}", table));
+                assert!(
+                    left.is_empty(),
+                    "{} keeps {:?} (bulk: {})",
+                    table,
+                    left,
+                    bulk
+                );
+            }
         }
     }
-    None
+
+    /// A `Coll[Byte]` register constant
+    fn coll_bytes(bytes: &[u8]) -> String {
+        format!("0e{:02x}{}", bytes.len(), hex::encode(bytes))
+    }
+
+    fn with_registers(mut output: Value, registers: Value) -> Value {
+        output["additionalRegisters"] = registers;
+        output
+    }
+
+    #[test]
+    fn test_minted_token_in_any_slot_of_any_output() {
+        let owner = p2pk_tree("11");
+        let issue = tx(
+            "mint",
+            &["issuer", "funding"],
+            vec![
+                output("change", 1_000, &owner, 5, json!([])),
+                with_registers(
+                    output(
+                        "issuing",
+                        1_000,
+                        &owner,
+                        5,
+                        json!([
+                            { "tokenId": "funding", "amount": 1 },
+                            { "tokenId": "issuer", "amount": 70 }
+                        ]),
+                    ),
+                    json!({
+                        "R4": coll_bytes(b"Coin"),
+                        "R5": coll_bytes(b"A test coin"),
+                        "R6": coll_bytes(b"2")
+                    }),
+                ),
+                output(
+                    "split",
+                    1_000,
+                    &owner,
+                    5,
+                    json!([{ "tokenId": "issuer", "amount": 30 }]),
+                ),
+            ],
+        );
+
+        let token = minted_token(&issue, 5).unwrap();
+        assert_eq!(token.token_id, "issuer");
+        assert_eq!(token.box_id, "issuing");
+        assert_eq!(token.emission_amount, 100);
+        assert_eq!(token.name.as_deref(), Some("Coin"));
+        assert_eq!(token.description.as_deref(), Some("A test coin"));
+        assert_eq!(token.decimals, Some(2));
+        assert_eq!(token.token_type.as_deref(), Some("EIP-004"));
+        assert_eq!(token.creation_height, 5);
+        assert_eq!(token.nft, NftMetadata::default());
+
+        // Only the first input's id can be minted
+        let transfer = tx(
+            "transfer",
+            &["funding", "issuer"],
+            vec![output(
+                "moved",
+                1_000,
+                &owner,
+                5,
+                json!([{ "tokenId": "issuer", "amount": 100 }]),
+            )],
+        );
+        assert!(minted_token(&transfer, 5).is_none());
+    }
+
+    #[test]
+    fn test_minted_nft_metadata() {
+        let owner = p2pk_tree("11");
+        let hash = [0xab; 32];
+        let nft = |registers: Value| {
+            let issue = tx(
+                "mint",
+                &["issuer"],
+                vec![with_registers(
+                    output(
+                        "issuing",
+                        1_000,
+                        &owner,
+                        5,
+                        json!([{ "tokenId": "issuer", "amount": 1 }]),
+                    ),
+                    registers,
+                )],
+            );
+            minted_token(&issue, 5).unwrap().nft
+        };
+
+        let picture = nft(json!({
+            "R4": coll_bytes(b"Art"),
+            "R7": coll_bytes(&[0x01, 0x01]),
+            "R8": coll_bytes(&hash),
+            "R9": coll_bytes(b"ipfs://picture")
+        }));
+        assert_eq!(
+            picture,
+            NftMetadata {
+                asset_type: Some("picture".to_string()),
+                content_hash: Some(hex::encode(hash)),
+                content_url: Some("ipfs://picture".to_string()),
+                cover_url: None,
+            }
+        );
+
+        // Audio links are a (audio, cover) pair of Coll[Byte]
+        let audio = nft(json!({
+            "R7": coll_bytes(&[0x01, 0x02]),
+            "R8": coll_bytes(&hash),
+            "R9": format!("3c0e0e{}{}", &coll_bytes(b"ipfs://audio")[2..], &coll_bytes(b"ipfs://cover")[2..])
+        }));
+        assert_eq!(audio.asset_type.as_deref(), Some("audio"));
+        assert_eq!(audio.content_url.as_deref(), Some("ipfs://audio"));
+        assert_eq!(audio.cover_url.as_deref(), Some("ipfs://cover"));
+
+        // R8 and R9 only mean something for artwork types
+        let membership = nft(json!({
+            "R7": coll_bytes(&[0x02, 0x01]),
+            "R8": coll_bytes(&hash),
+            "R9": coll_bytes(b"ipfs://ignored")
+        }));
+        assert_eq!(
+            membership,
+            NftMetadata {
+                asset_type: Some("membership-threshold".to_string()),
+                ..Default::default()
+            }
+        );
+    }
 }
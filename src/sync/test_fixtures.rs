@@ -0,0 +1,95 @@
+//! Synthetic blocks and helpers shared by the sync tests
+
+use clap::Parser;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use super::processor::BlockProcessor;
+use super::SyncService;
+use crate::db::Database;
+use crate::utils::network::Network;
+use crate::{AppState, Config};
+
+/// Filters 10^10 joined rows, far longer than any query timeout used in tests
+pub const SLOW_READ: &str =
+    "SELECT COUNT(*) FROM range(100000) a, range(100000) b WHERE a.range + b.range < 0";
+
+/// P2PK ErgoTree for a compressed key made of one repeated byte
+pub fn p2pk_tree(key_byte: &str) -> String {
+    format!("0008cd02{}", key_byte.repeat(32))
+}
+
+/// Block in the node's full-block format, chained to the block below it
+pub fn block(height: i64, transactions: Value) -> Value {
+    json!({
+        "header": {
+            "id": format!("{:064x}", height),
+            "parentId": format!("{:064x}", height - 1),
+            "height": height,
+            "timestamp": 1_561_978_800_000i64 + height * 120_000,
+            "difficulty": "1000",
+            "minerPk": ""
+        },
+        "blockTransactions": { "transactions": transactions }
+    })
+}
+
+pub fn output(box_id: &str, value: i64, ergo_tree: &str, height: i64, assets: Value) -> Value {
+    json!({
+        "boxId": box_id, "value": value, "ergoTree": ergo_tree,
+        "creationHeight": height, "assets": assets, "additionalRegisters": {}
+    })
+}
+
+pub fn tx(id: &str, inputs: &[&str], outputs: Vec<Value>) -> Value {
+    let inputs: Vec<Value> = inputs
+        .iter()
+        .map(|box_id| json!({ "boxId": box_id }))
+        .collect();
+    json!({ "id": id, "inputs": inputs, "dataInputs": [], "outputs": outputs, "size": 100 })
+}
+
+pub fn indexed_db() -> Database {
+    let db = Database::new(":memory:").unwrap();
+    db.migrate().unwrap();
+    db
+}
+
+/// A database with `blocks` indexed one at a time
+pub fn index_blocks(blocks: &[Value]) -> Database {
+    let db = indexed_db();
+    let mut processor = BlockProcessor::new(db.clone(), Network::Mainnet);
+    for block in blocks {
+        processor.process_block(block).unwrap();
+    }
+    db
+}
+
+/// API state over `db`, configured from command line `args`, with an unreachable node
+pub fn app_state(db: Database, args: &[&str]) -> Arc<AppState> {
+    let sync_service = SyncService::new(
+        vec!["http://127.0.0.1:1".to_string()],
+        db.clone(),
+        50,
+        None,
+        Network::Mainnet,
+    );
+    Arc::new(AppState {
+        db,
+        config: Config::parse_from(std::iter::once("ergo-index").chain(args.iter().copied())),
+        sync_service: Arc::new(sync_service),
+    })
+}
+
+/// Rows of a query rendered as strings, in a stable order
+pub fn rows(db: &Database, query: &str) -> Vec<String> {
+    db.query_all(
+        &format!(
+            "SELECT CAST(t AS VARCHAR) AS r FROM ({}) t ORDER BY r",
+            query
+        ),
+        [],
+        |row| row.get(0),
+    )
+    .unwrap()
+}
@@ -0,0 +1,407 @@
+//! Webhook delivery for watches on addresses, ErgoTree templates and tokens
+//!
+//! The block processor queues one delivery per matching box. This dispatcher
+//! POSTs them once the box has enough confirmations, signs each body with the
+//! watch's secret and retries failures with exponential backoff. Deliveries that
+//! exhaust their attempts are marked `dead` and kept as the dead-letter log.
+
+use anyhow::{Context, Result};
+use duckdb::params;
+use reqwest::{redirect, Client};
+use serde_json::json;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use crate::db::Database;
+use crate::utils::{hmac_sha256, to_hex};
+
+/// Header carrying `sha256=<hex HMAC of the body>`
+pub const SIGNATURE_HEADER: &str = "X-Ergo-Index-Signature";
+/// Header carrying the delivery id, stable across retries
+pub const DELIVERY_HEADER: &str = "X-Ergo-Index-Delivery";
+
+/// Deliveries attempted per dispatch round
+const DISPATCH_BATCH: i64 = 100;
+/// First retry delay; doubles per attempt up to MAX_BACKOFF_SECS
+const BASE_BACKOFF_SECS: i64 = 10;
+const MAX_BACKOFF_SECS: i64 = 3600;
+
+/// Attempts before a delivery is dead-lettered (configurable via WEBHOOK_MAX_ATTEMPTS)
+fn max_attempts() -> i32 {
+    std::env::var("WEBHOOK_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8)
+}
+
+/// Whether webhooks may target loopback and private networks (WEBHOOK_ALLOW_PRIVATE).
+/// Off by default so watches cannot reach services next to the indexer.
+pub fn allow_private_targets() -> bool {
+    std::env::var("WEBHOOK_ALLOW_PRIVATE")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+/// Loopback, RFC 1918, link-local (which holds cloud metadata at 169.254.169.254),
+/// unique local and unspecified addresses are not valid webhook destinations
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            !(v4.is_loopback() || v4.is_private() || v4.is_link_local() || v4.is_unspecified() || v4.is_broadcast())
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback() || v6.is_unspecified() || (first & 0xfe00) == 0xfc00 || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Resolve a webhook URL and check every address it resolves to. Returns the host
+/// and the vetted addresses, which deliveries connect to instead of resolving again.
+pub async fn resolve_webhook_url(url: &str, allow_private: bool) -> Result<(String, Vec<SocketAddr>)> {
+    let parsed = reqwest::Url::parse(url).context("Invalid url")?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        anyhow::bail!("url must be http(s)");
+    }
+    let host = parsed.host_str().context("url has no host")?.to_string();
+    let port = parsed.port_or_known_default().context("url has no port")?;
+
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+        .await
+        .with_context(|| format!("Cannot resolve {}", host))?
+        .collect();
+    if addrs.is_empty() {
+        anyhow::bail!("{} does not resolve", host);
+    }
+    if !allow_private {
+        if let Some(addr) = addrs.iter().find(|a| !is_public_ip(a.ip())) {
+            anyhow::bail!("{} resolves to non-public address {}", host, addr.ip());
+        }
+    }
+    Ok((host, addrs))
+}
+
+/// Signature header value for a payload
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    format!("sha256={}", to_hex(&hmac_sha256(secret.as_bytes(), body)))
+}
+
+struct DueDelivery {
+    delivery_id: i64,
+    attempts: i32,
+    url: String,
+    secret: String,
+    payload: serde_json::Value,
+}
+
+pub struct WebhookDispatcher {
+    db: Database,
+    allow_private: bool,
+}
+
+impl WebhookDispatcher {
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            allow_private: allow_private_targets(),
+        }
+    }
+
+    /// Send every delivery that is due and sufficiently confirmed at `tip_height`.
+    /// Returns (delivered, failed).
+    pub async fn dispatch_due(&self, tip_height: i64) -> Result<(usize, usize)> {
+        let now = chrono::Utc::now().timestamp();
+        let due = self.load_due(tip_height, now)?;
+
+        let mut delivered = 0;
+        let mut failed = 0;
+        for delivery in due {
+            match self.post(&delivery).await {
+                Ok(()) => {
+                    self.db.execute(
+                        "UPDATE webhook_deliveries
+                         SET status = 'delivered', attempts = attempts + 1, delivered_at = ?, last_error = NULL
+                         WHERE delivery_id = ?",
+                        params![chrono::Utc::now().timestamp(), delivery.delivery_id],
+                    )?;
+                    delivered += 1;
+                }
+                Err(e) => {
+                    let attempts = delivery.attempts + 1;
+                    let status = if attempts >= max_attempts() { "dead" } else { "pending" };
+                    let backoff = (BASE_BACKOFF_SECS << (attempts - 1).min(20)).min(MAX_BACKOFF_SECS);
+                    self.db.execute(
+                        "UPDATE webhook_deliveries
+                         SET status = ?, attempts = ?, next_attempt_at = ?, last_error = ?
+                         WHERE delivery_id = ?",
+                        params![status, attempts, now + backoff, e.to_string(), delivery.delivery_id],
+                    )?;
+                    if status == "dead" {
+                        tracing::warn!(
+                            "Webhook delivery {} to {} dead-lettered after {} attempts: {}",
+                            delivery.delivery_id, delivery.url, attempts, e
+                        );
+                    }
+                    failed += 1;
+                }
+            }
+        }
+
+        Ok((delivered, failed))
+    }
+
+    fn load_due(&self, tip_height: i64, now: i64) -> Result<Vec<DueDelivery>> {
+        let rows = self.db.query_all(
+            "SELECT d.delivery_id, d.attempts, w.url, w.secret, w.watch_id, w.watch_type, w.target,
+                    b.box_id, b.tx_id, b.address, b.value, b.settlement_height, b.ergo_tree_template_hash,
+                    b.spent_tx_id
+             FROM webhook_deliveries d
+             JOIN watches w ON w.watch_id = d.watch_id
+             JOIN boxes b ON b.box_id = d.box_id
+             WHERE d.status = 'pending'
+               AND d.next_attempt_at <= ?
+               AND ? - d.height + 1 >= w.confirmations
+             ORDER BY d.delivery_id
+             LIMIT ?",
+            params![now, tip_height, DISPATCH_BATCH],
+            |row| {
+                let height: i64 = row.get(11)?;
+                let box_id: String = row.get(7)?;
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i32>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    json!({
+                        "event": "box",
+                        "watchId": row.get::<_, i64>(4)?,
+                        "watchType": row.get::<_, String>(5)?,
+                        "target": row.get::<_, String>(6)?,
+                        "boxId": box_id,
+                        "txId": row.get::<_, String>(8)?,
+                        "address": row.get::<_, String>(9)?,
+                        "value": row.get::<_, i64>(10)?,
+                        "height": height,
+                        "confirmations": tip_height - height + 1,
+                        "ergoTreeTemplateHash": row.get::<_, String>(12)?,
+                        "spentTransactionId": row.get::<_, Option<String>>(13)?,
+                    }),
+                ))
+            },
+        )?;
+
+        let mut due = Vec::with_capacity(rows.len());
+        for (delivery_id, attempts, url, secret, mut payload) in rows {
+            let box_id = payload["boxId"].as_str().unwrap_or_default().to_string();
+            let assets = self.db.query_all(
+                "SELECT token_id, amount FROM box_assets WHERE box_id = ? ORDER BY asset_index",
+                [&box_id],
+                |row| {
+                    Ok(json!({
+                        "tokenId": row.get::<_, String>(0)?,
+                        "amount": row.get::<_, i64>(1)?,
+                    }))
+                },
+            )?;
+            payload["assets"] = json!(assets);
+            payload["deliveryId"] = json!(delivery_id);
+            due.push(DueDelivery {
+                delivery_id,
+                attempts,
+                url,
+                secret,
+                payload,
+            });
+        }
+        Ok(due)
+    }
+
+    async fn post(&self, delivery: &DueDelivery) -> Result<()> {
+        // Checked again on every attempt: the host may resolve elsewhere than at
+        // registration. The client is pinned to the vetted addresses and does not
+        // follow redirects, so neither a second lookup nor a Location header can
+        // send the request to a private address.
+        let (host, addrs) = resolve_webhook_url(&delivery.url, self.allow_private).await?;
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .redirect(redirect::Policy::none())
+            .resolve_to_addrs(&host, &addrs)
+            .build()?;
+
+        let body = serde_json::to_vec(&delivery.payload)?;
+        let response = client
+            .post(&delivery.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, sign(&delivery.secret, &body))
+            .header(DELIVERY_HEADER, delivery.delivery_id.to_string())
+            .body(body)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            anyhow::bail!("HTTP {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, http::HeaderMap, routing::post, Router};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    type Received = Arc<Mutex<Vec<(Option<String>, Vec<u8>)>>>;
+
+    /// Local stand-in for a subscriber: answers with `status` and records requests
+    async fn stand_in(status: u16) -> (String, Received) {
+        let received: Received = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .route(
+                "/hook",
+                post(move |State(received): State<Received>, headers: HeaderMap, body: axum::body::Bytes| async move {
+                    let signature = headers
+                        .get(SIGNATURE_HEADER)
+                        .and_then(|v| v.to_str().ok())
+                        .map(|s| s.to_string());
+                    received.lock().unwrap().push((signature, body.to_vec()));
+                    axum::http::StatusCode::from_u16(status).unwrap()
+                }),
+            )
+            .with_state(received.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{}/hook", addr), received)
+    }
+
+    fn setup(url: &str, confirmations: i32) -> Database {
+        let db = Database::new(":memory:").unwrap();
+        db.migrate().unwrap();
+        db.execute(
+            "INSERT INTO watches (watch_type, target, url, secret, confirmations, created_at)
+             VALUES ('address', '9addr', ?, 'topsecret', ?, 0)",
+            params![url, confirmations],
+        )
+        .unwrap();
+        db.execute(
+            "INSERT INTO boxes (box_id, tx_id, output_index, ergo_tree, ergo_tree_template_hash,
+                address, value, creation_height, settlement_height, global_index)
+             VALUES ('box1', 'tx1', 0, '00', 'hash', '9addr', 1000, 10, 10, 1)",
+            [],
+        )
+        .unwrap();
+        db.execute(
+            "INSERT INTO webhook_deliveries (watch_id, box_id, height, next_attempt_at, created_at)
+             SELECT watch_id, 'box1', 10, 0, 0 FROM watches",
+            [],
+        )
+        .unwrap();
+        db
+    }
+
+    fn delivery_state(db: &Database) -> (String, i32) {
+        db.query_one(
+            "SELECT status, attempts FROM webhook_deliveries",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap()
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_delivers_signed_payload_after_confirmations() {
+        let (url, received) = stand_in(200).await;
+        let db = setup(&url, 3);
+        let mut dispatcher = WebhookDispatcher::new(db.clone());
+        dispatcher.allow_private = true;
+
+        // Two confirmations at height 11: not yet due
+        assert_eq!(dispatcher.dispatch_due(11).await.unwrap(), (0, 0));
+        assert!(received.lock().unwrap().is_empty());
+
+        assert_eq!(dispatcher.dispatch_due(12).await.unwrap(), (1, 0));
+        let requests = received.lock().unwrap().clone();
+        assert_eq!(requests.len(), 1);
+        let (signature, body) = &requests[0];
+        assert_eq!(signature.as_deref(), Some(sign("topsecret", body).as_str()));
+        let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(payload["boxId"], "box1");
+        assert_eq!(payload["confirmations"], 3);
+        assert_eq!(delivery_state(&db), ("delivered".to_string(), 1));
+
+        // Delivered once only
+        assert_eq!(dispatcher.dispatch_due(13).await.unwrap(), (0, 0));
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_backs_off_then_dead_letters() {
+        let (url, received) = stand_in(500).await;
+        let db = setup(&url, 1);
+        let mut dispatcher = WebhookDispatcher::new(db.clone());
+        dispatcher.allow_private = true;
+
+        assert_eq!(dispatcher.dispatch_due(10).await.unwrap(), (0, 1));
+        assert_eq!(delivery_state(&db), ("pending".to_string(), 1));
+
+        // Backoff: not retried immediately
+        assert_eq!(dispatcher.dispatch_due(10).await.unwrap(), (0, 0));
+
+        db.execute(
+            "UPDATE webhook_deliveries SET next_attempt_at = 0, attempts = ?",
+            [max_attempts() - 1],
+        )
+        .unwrap();
+        assert_eq!(dispatcher.dispatch_due(10).await.unwrap(), (0, 1));
+        assert_eq!(delivery_state(&db).0, "dead");
+        assert_eq!(received.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_private_webhook_targets() {
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://localhost/hook",
+            "http://10.1.2.3/hook",
+            "http://192.168.0.10/hook",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://0.0.0.0/hook",
+            "http://[::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+            "http://[fe80::1]/hook",
+            "ftp://example.com/hook",
+        ] {
+            assert!(resolve_webhook_url(url, false).await.is_err(), "{} accepted", url);
+        }
+        assert!(resolve_webhook_url("http://127.0.0.1:8080/hook", true).await.is_ok());
+        assert!(is_public_ip("1.1.1.1".parse().unwrap()));
+        assert!(is_public_ip("2606:4700::1111".parse().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_private_target_is_not_delivered() {
+        let (url, received) = stand_in(200).await;
+        let db = setup(&url, 1);
+        let mut dispatcher = WebhookDispatcher::new(db.clone());
+        dispatcher.allow_private = false;
+
+        assert_eq!(dispatcher.dispatch_due(10).await.unwrap(), (0, 1));
+        assert!(received.lock().unwrap().is_empty());
+    }
+}
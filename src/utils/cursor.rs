@@ -0,0 +1,51 @@
+//! Opaque keyset pagination cursors
+//!
+//! A cursor holds the sort key of the last item on a page, base64url encoded so
+//! clients treat it as a token rather than something to build themselves.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+
+/// Encode the sort key parts of the last item on a page
+pub fn encode(parts: &[&str]) -> String {
+    URL_SAFE_NO_PAD.encode(parts.join(":"))
+}
+
+/// Decode a cursor into exactly `count` key parts. The last part keeps any
+/// remaining `:` characters.
+pub fn decode(cursor: &str, count: usize) -> Option<Vec<String>> {
+    let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    let key = String::from_utf8(bytes).ok()?;
+    let parts: Vec<String> = key.splitn(count, ':').map(str::to_string).collect();
+    if parts.len() == count {
+        Some(parts)
+    } else {
+        None
+    }
+}
+
+/// Cursor over a `global_index` ordering
+pub fn encode_index(global_index: i64) -> String {
+    encode(&[&global_index.to_string()])
+}
+
+pub fn decode_index(cursor: &str) -> Option<i64> {
+    decode(cursor, 1)?.first()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        assert_eq!(decode_index(&encode_index(1234567)), Some(1234567));
+
+        let cursor = encode(&["500", "9fAbc:def"]);
+        assert_eq!(decode(&cursor, 2), Some(vec!["500".to_string(), "9fAbc:def".to_string()]));
+        assert_eq!(decode(&encode(&["500"]), 2), None);
+
+        assert_eq!(decode_index("not a cursor!"), None);
+        assert_eq!(decode_index(&encode(&["abc"])), None);
+    }
+}
@@ -0,0 +1,231 @@
+//! Ergo emission schedule, including the EIP-27 re-emission rules
+//!
+//! Amounts are in nanoERG and follow the node's `EmissionRules` and
+//! `ReemissionRules`: 75 ERG per block for the first 525,600 blocks (7.5 of it to
+//! the foundation), then 3 ERG less every 64,800 blocks. From the EIP-27
+//! activation height 12 ERG of each block reward (less near the end of
+//! emission) is locked in the re-emission contract, which pays 3 ERG per block
+//! back to miners once regular emission is over.
+
+use super::network::Network;
+
+const COINS_IN_ONE_ERG: i64 = 1_000_000_000;
+
+const FIXED_RATE_PERIOD: i64 = 525_600;
+const FIXED_RATE: i64 = 75 * COINS_IN_ONE_ERG;
+const EPOCH_LENGTH: i64 = 64_800;
+const ONE_EPOCH_REDUCTION: i64 = 3 * COINS_IN_ONE_ERG;
+const FOUNDERS_INITIAL_REWARD: i64 = 75 * COINS_IN_ONE_ERG / 10;
+
+/// Re-emission charge per block while the block reward allows it
+const BASIC_CHARGE_AMOUNT: i64 = 12 * COINS_IN_ONE_ERG;
+/// Part of the block reward that is never charged, and the re-emission payout per block
+const REEMISSION_REWARD: i64 = 3 * COINS_IN_ONE_ERG;
+
+/// Epochs after the fixed-rate period until emission reaches zero
+const EMISSION_EPOCHS: i64 = FIXED_RATE / ONE_EPOCH_REDUCTION;
+
+/// Network-specific part of the schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmissionRules {
+    /// First height at which EIP-27 charges apply
+    pub eip27_activation_height: i64,
+    /// First height at which the re-emission contract pays miners
+    pub reemission_start_height: i64,
+}
+
+pub const MAINNET: EmissionRules = EmissionRules {
+    eip27_activation_height: 777_217,
+    reemission_start_height: 2_080_800,
+};
+
+pub const TESTNET: EmissionRules = EmissionRules {
+    eip27_activation_height: 188_001,
+    reemission_start_height: 186_400,
+};
+
+/// Per-block split of newly emitted coins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BlockEmission {
+    /// Coins taken out of the emission box
+    pub emission: i64,
+    /// Miners' share of `emission`, before the re-emission charge
+    pub miner_reward: i64,
+    pub foundation_reward: i64,
+    /// Part of `miner_reward` locked in the re-emission contract
+    pub reemission_charge: i64,
+    /// Paid to the miner by the re-emission contract
+    pub reemission_payout: i64,
+}
+
+impl EmissionRules {
+    pub fn for_network(network: Network) -> &'static EmissionRules {
+        match network {
+            Network::Mainnet => &MAINNET,
+            Network::Testnet => &TESTNET,
+        }
+    }
+
+    /// Coins released from the emission box by the block at `height`
+    pub fn emission_at_height(&self, height: i64) -> i64 {
+        if height < 1 {
+            0
+        } else if height < FIXED_RATE_PERIOD {
+            FIXED_RATE
+        } else {
+            let epoch = 1 + (height - FIXED_RATE_PERIOD) / EPOCH_LENGTH;
+            (FIXED_RATE - ONE_EPOCH_REDUCTION * epoch).max(0)
+        }
+    }
+
+    pub fn miner_reward_at_height(&self, height: i64) -> i64 {
+        if height < 1 {
+            0
+        } else if height < FIXED_RATE_PERIOD + 2 * EPOCH_LENGTH {
+            FIXED_RATE - FOUNDERS_INITIAL_REWARD
+        } else {
+            self.emission_at_height(height)
+        }
+    }
+
+    pub fn foundation_reward_at_height(&self, height: i64) -> i64 {
+        self.emission_at_height(height) - self.miner_reward_at_height(height)
+    }
+
+    /// EIP-27 charge moved from the block reward into the re-emission contract
+    pub fn reemission_charge_at_height(&self, height: i64) -> i64 {
+        if height < self.eip27_activation_height {
+            return 0;
+        }
+        let emission = self.emission_at_height(height);
+        if emission >= BASIC_CHARGE_AMOUNT + REEMISSION_REWARD {
+            BASIC_CHARGE_AMOUNT
+        } else if emission > REEMISSION_REWARD {
+            emission - REEMISSION_REWARD
+        } else {
+            0
+        }
+    }
+
+    pub fn reemission_payout_at_height(&self, height: i64) -> i64 {
+        self.reemission_paid_after_height(height) - self.reemission_paid_after_height(height - 1)
+    }
+
+    pub fn block_emission(&self, height: i64) -> BlockEmission {
+        BlockEmission {
+            emission: self.emission_at_height(height),
+            miner_reward: self.miner_reward_at_height(height),
+            foundation_reward: self.foundation_reward_at_height(height),
+            reemission_charge: self.reemission_charge_at_height(height),
+            reemission_payout: self.reemission_payout_at_height(height),
+        }
+    }
+
+    /// Total coins released from the emission box by blocks 1..=height
+    pub fn issued_coins_after_height(&self, height: i64) -> i64 {
+        self.cumulative(height, |h| self.emission_at_height(h))
+    }
+
+    /// Total EIP-27 charges of blocks 1..=height
+    pub fn reemission_charged_after_height(&self, height: i64) -> i64 {
+        self.cumulative(height, |h| self.reemission_charge_at_height(h))
+    }
+
+    /// Total paid out by the re-emission contract up to `height`; it pays a fixed
+    /// amount per block from the start height for as long as charges cover it
+    pub fn reemission_paid_after_height(&self, height: i64) -> i64 {
+        if height < self.reemission_start_height {
+            return 0;
+        }
+        let blocks = height - self.reemission_start_height + 1;
+        (blocks * REEMISSION_REWARD).min(self.reemission_charged_after_height(height))
+    }
+
+    /// Coins in circulation after `height`: everything emitted, minus what sits
+    /// in the re-emission contract
+    pub fn circulating_supply_after_height(&self, height: i64) -> i64 {
+        self.issued_coins_after_height(height) - self.reemission_charged_after_height(height)
+            + self.reemission_paid_after_height(height)
+    }
+
+    /// Sum a per-block amount over heights 1..=height. Every amount is constant
+    /// between the epoch boundaries and the EIP-27 activation height, so this
+    /// adds one product per segment instead of one term per block.
+    fn cumulative<F: Fn(i64) -> i64>(&self, height: i64, amount_at: F) -> i64 {
+        let mut boundaries: Vec<i64> = (0..=EMISSION_EPOCHS)
+            .map(|epoch| FIXED_RATE_PERIOD + epoch * EPOCH_LENGTH)
+            .collect();
+        boundaries.push(self.eip27_activation_height);
+        boundaries.push(1);
+        boundaries.sort_unstable();
+
+        let mut total = 0;
+        for (i, &start) in boundaries.iter().enumerate() {
+            if start > height {
+                break;
+            }
+            let end = boundaries.get(i + 1).map_or(height, |next| (next - 1).min(height));
+            if end >= start {
+                total += amount_at(start) * (end - start + 1);
+            }
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force<F: Fn(i64) -> i64>(height: i64, f: F) -> i64 {
+        (1..=height).map(f).sum()
+    }
+
+    #[test]
+    fn test_schedule_milestones() {
+        let rules = &MAINNET;
+        assert_eq!(rules.emission_at_height(1), 75 * COINS_IN_ONE_ERG);
+        assert_eq!(rules.foundation_reward_at_height(1), FOUNDERS_INITIAL_REWARD);
+        assert_eq!(rules.emission_at_height(525_600), 72 * COINS_IN_ONE_ERG);
+        assert_eq!(rules.foundation_reward_at_height(525_600), 45 * COINS_IN_ONE_ERG / 10);
+        assert_eq!(rules.foundation_reward_at_height(525_600 + 2 * 64_800), 0);
+        assert_eq!(rules.emission_at_height(2_080_800), 0);
+
+        // EIP-27
+        assert_eq!(rules.reemission_charge_at_height(777_216), 0);
+        assert_eq!(rules.reemission_charge_at_height(777_217), 12 * COINS_IN_ONE_ERG);
+        // 9 ERG emission near the end: everything above 3 ERG is charged
+        assert_eq!(rules.reemission_charge_at_height(525_600 + 21 * 64_800), 6 * COINS_IN_ONE_ERG);
+    }
+
+    #[test]
+    fn test_total_supply() {
+        let rules = &MAINNET;
+        assert_eq!(rules.issued_coins_after_height(3_000_000), 97_739_925 * COINS_IN_ONE_ERG);
+        assert_eq!(
+            rules.cumulative(3_000_000, |h| rules.foundation_reward_at_height(h)),
+            4_330_792_500_000_000
+        );
+        // Re-emission eventually returns every charged coin
+        assert_eq!(rules.circulating_supply_after_height(10_000_000), 97_739_925 * COINS_IN_ONE_ERG);
+    }
+
+    #[test]
+    fn test_cumulative_matches_block_sums() {
+        let rules = &MAINNET;
+        for height in [0, 1, 2, 525_599, 525_600, 590_400, 777_216, 777_217, 800_000] {
+            assert_eq!(
+                rules.issued_coins_after_height(height),
+                brute_force(height, |h| rules.emission_at_height(h)),
+                "issued at {}",
+                height
+            );
+            assert_eq!(
+                rules.reemission_charged_after_height(height),
+                brute_force(height, |h| rules.reemission_charge_at_height(h)),
+                "charged at {}",
+                height
+            );
+        }
+    }
+}
@@ -7,6 +7,12 @@ use blake2::{Blake2b, Digest, digest::consts::U32};
 
 type Blake2b256 = Blake2b<U32>;
 
+use super::network::Network;
+use super::sha256;
+use super::sigma::{read_constant, Constant, SigmaReader};
+
+pub mod decompile;
+
 const MAINNET_P2PK_PREFIX: u8 = 0x01;  // P2PK address (network 0x00 + type 0x01)
 const MAINNET_P2SH_PREFIX: u8 = 0x02;  // P2SH address (network 0x00 + type 0x02)
 const MAINNET_P2S_PREFIX: u8 = 0x03;   // P2S address  (network 0x00 + type 0x03)
@@ -15,11 +21,16 @@ const TESTNET_P2PK_PREFIX: u8 = 0x11;  // network 0x10 + type 0x01
 const TESTNET_P2SH_PREFIX: u8 = 0x12;  // network 0x10 + type 0x02
 const TESTNET_P2S_PREFIX: u8 = 0x13;   // network 0x10 + type 0x03
 
+/// Miners' fee contract every transaction pays its fee to
+pub const MINERS_FEE_ERGO_TREE: &str = "1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304";
+/// Template hash of the miners' fee contract, for index lookups on boxes
+pub const MINERS_FEE_TEMPLATE_HASH: &str = "5b710d70f207f03745a8bb713006f235446f0104f89e977ae066ae184c0494fa";
+
 /// Base58 alphabet used by Ergo
 const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
 
 /// Convert ErgoTree hex to human-readable address
-pub fn ergo_tree_to_address(ergo_tree: &str) -> Option<String> {
+pub fn ergo_tree_to_address(ergo_tree: &str, network: Network) -> Option<String> {
     let bytes = hex::decode(ergo_tree).ok()?;
 
     if bytes.is_empty() {
@@ -30,16 +41,16 @@ pub fn ergo_tree_to_address(ergo_tree: &str) -> Option<String> {
     if bytes.len() >= 36 && bytes[0] == 0x00 && bytes[1] == 0x08 && bytes[2] == 0xcd {
         // Extract public key (33 bytes after prefix)
         let pk = &bytes[3..36];
-        return Some(encode_p2pk_address(pk, true));
+        return Some(encode_p2pk_address(pk, network));
     }
 
     // For other trees, use P2S encoding
-    Some(encode_p2s_address(&bytes, true))
+    Some(encode_p2s_address(&bytes, network))
 }
 
 /// Encode a P2PK address from public key bytes
-fn encode_p2pk_address(pk: &[u8], mainnet: bool) -> String {
-    let prefix = if mainnet { MAINNET_P2PK_PREFIX } else { TESTNET_P2PK_PREFIX };
+fn encode_p2pk_address(pk: &[u8], network: Network) -> String {
+    let prefix = if network.is_mainnet() { MAINNET_P2PK_PREFIX } else { TESTNET_P2PK_PREFIX };
 
     let mut content = vec![prefix];
     content.extend_from_slice(pk);
@@ -55,8 +66,8 @@ fn encode_p2pk_address(pk: &[u8], mainnet: bool) -> String {
 ///
 /// P2S addresses use the full ErgoTree bytes directly (not a hash).
 /// Format: prefix_byte || ergo_tree_bytes || checksum(4 bytes)
-fn encode_p2s_address(tree: &[u8], mainnet: bool) -> String {
-    let prefix = if mainnet { MAINNET_P2S_PREFIX } else { TESTNET_P2S_PREFIX };
+fn encode_p2s_address(tree: &[u8], network: Network) -> String {
+    let prefix = if network.is_mainnet() { MAINNET_P2S_PREFIX } else { TESTNET_P2S_PREFIX };
 
     let mut content = vec![prefix];
     content.extend_from_slice(tree);
@@ -116,11 +127,66 @@ fn base58_encode(data: &[u8]) -> String {
     String::from_utf8(result).unwrap_or_default()
 }
 
+/// ErgoTree header bit: the tree size follows the header
+const SIZE_FLAG: u8 = 0x08;
+/// ErgoTree header bit: constants are segregated from the tree body
+const CONSTANT_SEGREGATION_FLAG: u8 = 0x10;
+
+/// A segregated constant together with its serialized bytes
+pub struct TreeConstant<'a> {
+    pub constant: Constant,
+    pub bytes: &'a [u8],
+}
+
+/// An ErgoTree split into header, segregated constants and template
+pub struct ParsedErgoTree<'a> {
+    pub header: u8,
+    pub constants: Vec<TreeConstant<'a>>,
+    /// Tree body with constants replaced by placeholders
+    pub template: &'a [u8],
+}
+
+/// Split an ErgoTree into its parts, as sigma-rust's `ErgoTree::template_bytes` does
+pub fn parse_ergo_tree(bytes: &[u8]) -> Option<ParsedErgoTree<'_>> {
+    let mut r = SigmaReader::new(bytes);
+    let header = r.get_u8()?;
+
+    if header & SIZE_FLAG != 0 {
+        let size = r.get_u32()? as usize;
+        if r.remaining().len() != size {
+            return None;
+        }
+    }
+
+    let mut constants = Vec::new();
+    if header & CONSTANT_SEGREGATION_FLAG != 0 {
+        let count = r.get_u32()?;
+        for _ in 0..count {
+            let start = r.position();
+            let constant = read_constant(&mut r)?;
+            constants.push(TreeConstant {
+                constant,
+                bytes: &bytes[start..r.position()],
+            });
+        }
+    }
+
+    let template = r.remaining();
+    if template.is_empty() {
+        return None;
+    }
+
+    Some(ParsedErgoTree {
+        header,
+        constants,
+        template,
+    })
+}
+
 /// Compute ErgoTree template hash
-/// This extracts the "template" of an ErgoTree by replacing constants with placeholders
+/// Matches the Explorer: hex(sha256(template bytes)). Trees that fail to parse
+/// are hashed whole so they still group with identical trees.
 pub fn ergo_tree_template_hash(ergo_tree: &str) -> String {
-    // For simplicity, we hash the first 8 bytes + structure
-    // A full implementation would parse and extract the template
     let bytes = match hex::decode(ergo_tree) {
         Ok(b) => b,
         Err(_) => return String::new(),
@@ -130,54 +196,155 @@ pub fn ergo_tree_template_hash(ergo_tree: &str) -> String {
         return String::new();
     }
 
-    // Simple template: use first byte (header) + size as template indicator
-    // This is a simplified version - full implementation needs ErgoTree parsing
-    let template_bytes = if bytes.len() > 8 {
-        &bytes[..8]
-    } else {
-        &bytes
+    let template_bytes = match parse_ergo_tree(&bytes) {
+        Some(parsed) => parsed.template,
+        None => &bytes[..],
     };
 
-    let hash = blake2b256(template_bytes);
-    hex::encode(&hash[..32])
+    hex::encode(sha256(template_bytes))
 }
 
 /// Convert miner public key to address
-pub fn miner_pk_to_address(miner_pk: &str) -> Option<String> {
+pub fn miner_pk_to_address(miner_pk: &str, network: Network) -> Option<String> {
     let pk_bytes = hex::decode(miner_pk).ok()?;
 
     if pk_bytes.len() != 33 {
         return None;
     }
 
-    Some(encode_p2pk_address(&pk_bytes, true))
+    Some(encode_p2pk_address(&pk_bytes, network))
 }
 
-/// Validate an Ergo address
-pub fn validate_address(address: &str) -> bool {
-    // P2PK addresses are ~51 chars, P2S addresses can be hundreds of chars
-    if address.len() < 30 {
-        return false;
+/// Base58 decode a string; None on characters outside the alphabet
+pub fn base58_decode(s: &str) -> Option<Vec<u8>> {
+    let leading_ones = s.bytes().take_while(|&c| c == b'1').count();
+
+    // Little-endian base-256 digits
+    let mut num: Vec<u8> = Vec::new();
+    for c in s.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in num.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            num.push(carry as u8);
+            carry >>= 8;
+        }
     }
 
-    // Check all characters are valid base58
-    address.chars().all(|c| BASE58_ALPHABET.contains(&(c as u8)))
+    let mut result = vec![0u8; leading_ones];
+    result.extend(num.iter().rev());
+    Some(result)
 }
 
-/// Get address type from prefix
-pub fn get_address_type(address: &str) -> Option<&'static str> {
-    if address.is_empty() {
-        return None;
+/// Ergo address kinds, the low nibble of an address's prefix byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressType {
+    P2PK,
+    P2SH,
+    P2S,
+}
+
+impl AddressType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AddressType::P2PK => "P2PK",
+            AddressType::P2SH => "P2SH",
+            AddressType::P2S => "P2S",
+        }
     }
+}
 
-    match address.chars().next()? {
-        '9' => Some("P2PK"),  // Mainnet P2PK
-        '2' => Some("P2S"),   // Mainnet P2S
-        '3' => Some("P2SH"),  // Mainnet P2SH
-        _ => Some("Unknown"),
+/// A checksum-verified address split into its parts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedAddress {
+    pub network: Network,
+    pub address_type: AddressType,
+    /// Public key (P2PK), 24-byte script hash (P2SH) or ErgoTree (P2S)
+    pub content: Vec<u8>,
+}
+
+impl DecodedAddress {
+    /// The ErgoTree a box must have to belong to this address
+    pub fn ergo_tree(&self) -> Vec<u8> {
+        match self.address_type {
+            AddressType::P2PK => [&P2PK_TREE_PREFIX[..], &self.content].concat(),
+            AddressType::P2SH => [&P2SH_TREE_PREFIX[..], &self.content, &P2SH_TREE_SUFFIX[..]].concat(),
+            AddressType::P2S => self.content.clone(),
+        }
     }
 }
 
+/// `sigmaProp(<pk>)` without constant segregation, followed by the 33-byte key
+const P2PK_TREE_PREFIX: [u8; 3] = [0x00, 0x08, 0xcd];
+/// The node's P2SH script: `sigmaProp(blake2b256(getVar[Coll[Byte]](1).get).slice(0, 24) == <hash>)
+/// && executeFromVar[SigmaProp](1)`, split around the 24-byte hash
+const P2SH_TREE_PREFIX: [u8; 17] = [
+    0x00, 0xea, 0x02, 0xd1, 0x93, 0xb4, 0xcb, 0xe4, 0xe3, 0x01, 0x0e, 0x04, 0x00, 0x04, 0x30, 0x0e, 0x18,
+];
+const P2SH_TREE_SUFFIX: [u8; 3] = [0xd4, 0x08, 0x01];
+const P2SH_HASH_SIZE: usize = 24;
+const PK_SIZE: usize = 33;
+const CHECKSUM_SIZE: usize = 4;
+
+/// Decode an address, verifying its checksum, network/type prefix and content size
+pub fn decode_address(address: &str) -> Result<DecodedAddress, String> {
+    let bytes = base58_decode(address).ok_or("Address is not valid base58")?;
+    if bytes.len() <= 1 + CHECKSUM_SIZE {
+        return Err("Address is too short".to_string());
+    }
+
+    let (body, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+    if blake2b256_checksum(body) != checksum {
+        return Err("Address checksum mismatch".to_string());
+    }
+
+    let (network, address_type) = match body[0] {
+        MAINNET_P2PK_PREFIX => (Network::Mainnet, AddressType::P2PK),
+        MAINNET_P2SH_PREFIX => (Network::Mainnet, AddressType::P2SH),
+        MAINNET_P2S_PREFIX => (Network::Mainnet, AddressType::P2S),
+        TESTNET_P2PK_PREFIX => (Network::Testnet, AddressType::P2PK),
+        TESTNET_P2SH_PREFIX => (Network::Testnet, AddressType::P2SH),
+        TESTNET_P2S_PREFIX => (Network::Testnet, AddressType::P2S),
+        prefix => return Err(format!("Unknown address prefix 0x{:02x}", prefix)),
+    };
+
+    let content = body[1..].to_vec();
+    let expected_size = match address_type {
+        AddressType::P2PK => Some(PK_SIZE),
+        AddressType::P2SH => Some(P2SH_HASH_SIZE),
+        AddressType::P2S => None,
+    };
+    if expected_size.is_some_and(|size| size != content.len()) {
+        return Err(format!("Invalid {} address length", address_type.as_str()));
+    }
+
+    Ok(DecodedAddress {
+        network,
+        address_type,
+        content,
+    })
+}
+
+/// Convert an address to the hex ErgoTree its boxes are locked by
+pub fn address_to_ergo_tree(address: &str) -> Result<String, String> {
+    decode_address(address).map(|decoded| hex::encode(decoded.ergo_tree()))
+}
+
+/// Validate an Ergo address of either network: base58, checksum, prefix and length
+pub fn validate_address(address: &str) -> bool {
+    decode_address(address).is_ok()
+}
+
+/// Get address type from the decoded prefix; None for invalid addresses
+pub fn get_address_type(address: &str) -> Option<&'static str> {
+    decode_address(address)
+        .ok()
+        .map(|decoded| decoded.address_type.as_str())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,7 +353,7 @@ mod tests {
     fn test_p2pk_address() {
         // Known P2PK ErgoTree
         let ergo_tree = "0008cd03a1e7be27b2f0e4a6e4f6f3e3e1e2e3e4e5e6e7e8e9eaebecedeeeff0f1f2f3f4";
-        let address = ergo_tree_to_address(ergo_tree);
+        let address = ergo_tree_to_address(ergo_tree, Network::Mainnet);
         assert!(address.is_some());
         assert!(address.unwrap().starts_with('9'));
     }
@@ -195,7 +362,7 @@ mod tests {
     fn test_p2s_address_known_vector() {
         // Test vector from sigma-rust reference implementation
         let ergo_tree = "100204a00b08cd021dde34603426402615658f1d970cfa7c7bd92ac81a8b16eeebff264d59ce4604ea02d192a39a8cc7a70173007301";
-        let address = ergo_tree_to_address(ergo_tree).unwrap();
+        let address = ergo_tree_to_address(ergo_tree, Network::Mainnet).unwrap();
         assert_eq!(
             address,
             "88dhgzEuTXaQLG2u9aud6SkPCGyXvw8mQWLCWfkv6wwuC9X9gdzELR9mt2hHQaM654aamzscP8r45NsJ",
@@ -207,7 +374,7 @@ mod tests {
     fn test_p2s_miners_fee_address() {
         // Miners fee ErgoTree - known test vector from sigma-rust
         let ergo_tree = "1005040004000e36100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a701730073011001020402d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304";
-        let address = ergo_tree_to_address(ergo_tree).unwrap();
+        let address = ergo_tree_to_address(ergo_tree, Network::Mainnet).unwrap();
         assert_eq!(
             address,
             "2iHkR7CWvD1R4j1yZg5bkeDRQavjAaVPeTDFGGLZduHyfWMuYpmhHocX8GJoaieTx78FntzJbCBVL6rf96ocJoZdmWBL2fci7NqWgAirppPQmZ7fN9V6z13Ay6brPriBKYqLp1bT2Fk4FkFLCfdPpe",
@@ -222,7 +389,7 @@ mod tests {
         // the script exceeds 255 bytes. The official Ergo Explorer always uses P2S.
         // Our explorer must also use P2S to match.
         let ergo_tree = "1999030f0400040204020404040405feffffffffffffffff0105feffffffffffffffff01050004d00f040004000406050005000580dac409d819d601b2a5730000d602e4c6a70404d603db63087201d604db6308a7d605b27203730100d606b27204730200d607b27203730300d608b27204730400d6099973058c720602d60a999973068c7205027209d60bc17201d60cc1a7d60d99720b720cd60e91720d7307d60f8c720802d6107e720f06d6117e720d06d612998c720702720fd6137e720c06d6147308d6157e721206d6167e720a06d6177e720906d6189c72117217d6199c72157217d1ededededededed93c27201c2a793e4c672010404720293b27203730900b27204730a00938c7205018c720601938c7207018c72080193b17203730b9593720a730c95720e929c9c721072117e7202069c7ef07212069a9c72137e7214067e9c720d7e72020506929c9c721372157e7202069c7ef0720d069a9c72107e7214067e9c72127e7202050695ed720e917212730d907216a19d721872139d72197210ed9272189c721672139272199c7216721091720b730e";
-        let address = ergo_tree_to_address(ergo_tree).unwrap();
+        let address = ergo_tree_to_address(ergo_tree, Network::Mainnet).unwrap();
         assert_eq!(
             address,
             "5vSUZRZbdVbnk4sJWjg2uhL94VZWRg4iatK9VgMChufzUgdihgvhR8yWSUEJKszzV7Vmi6K8hCyKTNhUaiP8p5ko6YEU9yfHpjVuXdQ4i5p4cRCzch6ZiqWrNukYjv7Vs5jvBwqg5hcEJ8u1eerr537YLWUoxxi1M4vQxuaCihzPKMt8NDXP4WcbN6mfNxxLZeGBvsHVvVmina5THaECosCWozKJFBnscjhpr3AJsdaL8evXAvPfEjGhVMoTKXAb2ZGGRmR8g1eZshaHmgTg2imSiaoXU5eiF3HvBnDuawaCtt674ikZ3oZdekqswcVPGMwqqUKVsGY4QuFeQoGwRkMqEYTdV2UDMMsfrjrBYQYKUBFMwsQGMNBL1VoY78aotXzdeqJCBVKbQdD3ZZWvukhSe4xrz8tcF3PoxpysDLt89boMqZJtGEHTV9UBTBEac6sDyQP693qT3nKaErN8TCXrJBUmHPqKozAg9bwxTqMYkpmb9iVKLSoJxG7MjAj72SRbcqQfNCVTztSwN3cRxSrVtz4p87jNFbVtFzhPg7UqDwNFTaasySCqM",
@@ -230,12 +397,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_template_hash_miner_reward() {
+        // Miner reward contract; hash matches the official Explorer
+        let ergo_tree = "100204a00b08cd021dde34603426402615658f1d970cfa7c7bd92ac81a8b16eeebff264d59ce4604ea02d192a39a8cc7a70173007301";
+        let bytes = hex::decode(ergo_tree).unwrap();
+        let parsed = parse_ergo_tree(&bytes).unwrap();
+        assert_eq!(parsed.constants.len(), 2);
+        assert_eq!(hex::encode(parsed.template), "ea02d192a39a8cc7a70173007301");
+        assert_eq!(
+            ergo_tree_template_hash(ergo_tree),
+            "961e872f7ab750cb77ad75ea8a32d0ea3472bd0c230de09329b802801b3d1817"
+        );
+
+        // Same contract for a different miner key shares the template
+        let other = "100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a70173007301";
+        assert_eq!(ergo_tree_template_hash(ergo_tree), ergo_tree_template_hash(other));
+    }
+
+    #[test]
+    fn test_template_hash_miners_fee() {
+        let ergo_tree = MINERS_FEE_ERGO_TREE;
+        let bytes = hex::decode(ergo_tree).unwrap();
+        let parsed = parse_ergo_tree(&bytes).unwrap();
+        assert_eq!(parsed.constants.len(), 5);
+        assert_eq!(
+            hex::encode(parsed.template),
+            "d19683030193a38cc7b2a57300000193c2b2a57301007473027303830108cdeeac93b1a57304"
+        );
+        assert_eq!(ergo_tree_template_hash(ergo_tree), MINERS_FEE_TEMPLATE_HASH);
+    }
+
     #[test]
     fn test_validate_address() {
         assert!(validate_address("9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vA"));
-        // P2S addresses can be very long
-        assert!(validate_address("BxKBaHkvrTvLZrDcZjcsxsF7aSsrN73ijeFZXtbj4CXZHHcvBtqSxQ"));
+        assert!(validate_address(
+            "88dhgzEuTXaQLG2u9aud6SkPCGyXvw8mQWLCWfkv6wwuC9X9gdzELR9mt2hHQaM654aamzscP8r45NsJ"
+        ));
         assert!(!validate_address("invalid"));
         assert!(!validate_address("0invalid"));
+        // Last character changed: checksum no longer matches
+        assert!(!validate_address("9fRAWhdxEsTcdb8PhGNrZfwqa65zfkuYHAMmkQLcic1gdLSV5vB"));
+    }
+
+    #[test]
+    fn test_address_round_trip() {
+        let p2pk = "0008cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        for ergo_tree in [p2pk, MINERS_FEE_ERGO_TREE] {
+            let address = ergo_tree_to_address(ergo_tree, Network::Mainnet).unwrap();
+            assert_eq!(address_to_ergo_tree(&address).unwrap(), ergo_tree);
+        }
+
+        let decoded = decode_address(&ergo_tree_to_address(p2pk, Network::Mainnet).unwrap()).unwrap();
+        assert_eq!(decoded.network, Network::Mainnet);
+        assert_eq!(decoded.address_type, AddressType::P2PK);
+
+        let testnet = ergo_tree_to_address(p2pk, Network::Testnet).unwrap();
+        assert!(testnet.starts_with('3'));
+        assert_eq!(decode_address(&testnet).unwrap().network, Network::Testnet);
+        assert_eq!(address_to_ergo_tree(&testnet).unwrap(), p2pk);
+        assert_eq!(get_address_type(&testnet), Some("P2PK"));
+    }
+
+    #[test]
+    fn test_p2sh_address() {
+        // P2SH content is the first 24 bytes of the script's blake2b256 hash
+        let hash = &blake2b256(&hex::decode(MINERS_FEE_ERGO_TREE).unwrap())[..24];
+        let mut content = vec![MAINNET_P2SH_PREFIX];
+        content.extend_from_slice(hash);
+        content.extend(blake2b256_checksum(&content));
+        let address = base58_encode(&content);
+
+        assert_eq!(get_address_type(&address), Some("P2SH"));
+        assert_eq!(
+            address_to_ergo_tree(&address).unwrap(),
+            format!("00ea02d193b4cbe4e3010e040004300e18{}d40801", hex::encode(hash))
+        );
+
+        // A full 32-byte hash is not a valid P2SH address
+        let mut content = vec![MAINNET_P2SH_PREFIX];
+        content.extend(blake2b256(&hex::decode(MINERS_FEE_ERGO_TREE).unwrap()));
+        content.extend(blake2b256_checksum(&content));
+        assert!(decode_address(&base58_encode(&content)).is_err());
     }
 }
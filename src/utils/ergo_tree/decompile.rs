@@ -0,0 +1,914 @@
+//! ErgoTree decompiler
+//!
+//! Deserializes the expression tree of an ErgoTree the way sigma-state's
+//! `ValueSerializer` writes it, and renders it as ErgoScript-like pseudocode and
+//! as structured JSON. Segregated constants are resolved in the pseudocode and
+//! listed separately in the JSON. Opcodes outside the supported set stop
+//! decoding with an error naming the opcode and its offset.
+
+use serde_json::{json, Value as Json};
+
+use super::{encode_p2pk_address, parse_ergo_tree, CONSTANT_SEGREGATION_FLAG, SIZE_FLAG};
+use crate::utils::network::Network;
+use crate::utils::sigma::{
+    read_type, read_value, render_value, Constant, SType, SigmaBoolean, SigmaReader, SigmaValue,
+};
+use Arity::*;
+use Syntax::*;
+
+/// ErgoTree header bits holding the version
+const VERSION_MASK: u8 = 0x07;
+
+/// Codes up to this one start an inline constant (they are its type code)
+const LAST_CONSTANT_CODE: u8 = 112;
+
+/// Expressions nested deeper than this are rejected rather than risking the stack
+const MAX_DEPTH: usize = 128;
+
+// Opcodes that need more than "opcode followed by N expressions"
+const TAGGED_VARIABLE: u8 = 0x71;
+const VAL_USE: u8 = 0x72;
+const CONSTANT_PLACEHOLDER: u8 = 0x73;
+const DOWNCAST: u8 = 0x7d;
+const UPCAST: u8 = 0x7e;
+const TRUE: u8 = 0x7f;
+const FALSE: u8 = 0x80;
+const UNIT_CONSTANT: u8 = 0x81;
+const CONCRETE_COLLECTION: u8 = 0x83;
+const CONCRETE_COLLECTION_BOOLEAN_CONSTANT: u8 = 0x85;
+const SELECT_1: u8 = 0x87;
+const SELECT_5: u8 = 0x8b;
+const SELECT_FIELD: u8 = 0x8c;
+const BY_INDEX: u8 = 0xb2;
+const EXTRACT_REGISTER_AS: u8 = 0xc6;
+const DESERIALIZE_CONTEXT: u8 = 0xd4;
+const DESERIALIZE_REGISTER: u8 = 0xd5;
+const VAL_DEF: u8 = 0xd6;
+const FUN_DEF: u8 = 0xd7;
+const BLOCK_VALUE: u8 = 0xd8;
+const FUNC_VALUE: u8 = 0xd9;
+const FUNC_APPLY: u8 = 0xda;
+const PROPERTY_CALL: u8 = 0xdb;
+const METHOD_CALL: u8 = 0xdc;
+const GET_VAR: u8 = 0xe3;
+
+/// Comparisons and boolean binary ops: when both arguments are boolean
+/// constants they are packed into a two-bit `ConcreteCollectionBooleanConstant`
+const RELATIONS: &[u8] = &[0x8f, 0x90, 0x91, 0x92, 0x93, 0x94, 0xec, 0xed, 0xf4];
+
+/// How many argument expressions follow an opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Arity {
+    Fixed(usize),
+    /// A VLQ count, then that many expressions
+    Counted,
+    /// A single-byte count, then that many expressions
+    CountedU8,
+}
+
+/// How an operation is written in the pseudocode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Syntax {
+    /// `HEIGHT`
+    Keyword(&'static str),
+    /// `(a < b)`
+    Infix(&'static str),
+    /// `!a`
+    Prefix(&'static str),
+    /// `a.size`
+    Property(&'static str),
+    /// `a.map(b)`
+    Method(&'static str),
+    /// `blake2b256(a)`
+    Function(&'static str),
+    /// Items of a literal collection joined by the operator, otherwise `allOf(a)`
+    Reduce(&'static str, &'static str),
+    /// All arguments joined by the operator
+    Join(&'static str),
+    /// `if (a) b else c`
+    If,
+    /// `a(b)`, or `a.getOrElse(b, c)` with a default
+    Index,
+}
+
+/// An operation without extra fields besides its arguments
+#[derive(Debug, PartialEq, Eq)]
+pub struct OpInfo {
+    code: u8,
+    /// Name of the node in sigma-state, used in the JSON output
+    pub name: &'static str,
+    arity: Arity,
+    syntax: Syntax,
+}
+
+const fn op(code: u8, name: &'static str, arity: Arity, syntax: Syntax) -> OpInfo {
+    OpInfo { code, name, arity, syntax }
+}
+
+const OPS: &[OpInfo] = &[
+    op(0x74, "SubstConstants", Fixed(3), Function("substConstants")),
+    op(0x7a, "LongToByteArray", Fixed(1), Function("longToByteArray")),
+    op(0x7b, "ByteArrayToBigInt", Fixed(1), Function("byteArrayToBigInt")),
+    op(0x7c, "ByteArrayToLong", Fixed(1), Function("byteArrayToLong")),
+    op(0x82, "GroupGenerator", Fixed(0), Keyword("groupGenerator")),
+    op(0x86, "Tuple", CountedU8, Function("")),
+    op(0x8f, "LT", Fixed(2), Infix("<")),
+    op(0x90, "LE", Fixed(2), Infix("<=")),
+    op(0x91, "GT", Fixed(2), Infix(">")),
+    op(0x92, "GE", Fixed(2), Infix(">=")),
+    op(0x93, "EQ", Fixed(2), Infix("==")),
+    op(0x94, "NEQ", Fixed(2), Infix("!=")),
+    op(0x95, "If", Fixed(3), If),
+    op(0x96, "AND", Fixed(1), Reduce("&&", "allOf")),
+    op(0x97, "OR", Fixed(1), Reduce("||", "anyOf")),
+    op(0x98, "AtLeast", Fixed(2), Function("atLeast")),
+    op(0x99, "Minus", Fixed(2), Infix("-")),
+    op(0x9a, "Plus", Fixed(2), Infix("+")),
+    op(0x9b, "Xor", Fixed(2), Function("xor")),
+    op(0x9c, "Multiply", Fixed(2), Infix("*")),
+    op(0x9d, "Division", Fixed(2), Infix("/")),
+    op(0x9e, "Modulo", Fixed(2), Infix("%")),
+    op(0x9f, "Exponentiate", Fixed(2), Method("exp")),
+    op(0xa0, "MultiplyGroup", Fixed(2), Method("multiply")),
+    op(0xa1, "Min", Fixed(2), Function("min")),
+    op(0xa2, "Max", Fixed(2), Function("max")),
+    op(0xa3, "Height", Fixed(0), Keyword("HEIGHT")),
+    op(0xa4, "Inputs", Fixed(0), Keyword("INPUTS")),
+    op(0xa5, "Outputs", Fixed(0), Keyword("OUTPUTS")),
+    op(0xa6, "LastBlockUtxoRootHash", Fixed(0), Keyword("LastBlockUtxoRootHash")),
+    op(0xa7, "Self", Fixed(0), Keyword("SELF")),
+    op(0xac, "MinerPubkey", Fixed(0), Keyword("minerPubKey")),
+    op(0xad, "MapCollection", Fixed(2), Method("map")),
+    op(0xae, "Exists", Fixed(2), Method("exists")),
+    op(0xaf, "ForAll", Fixed(2), Method("forall")),
+    op(0xb0, "Fold", Fixed(3), Method("fold")),
+    op(0xb1, "SizeOf", Fixed(1), Property("size")),
+    op(BY_INDEX, "ByIndex", Fixed(2), Index),
+    op(0xb3, "Append", Fixed(2), Method("append")),
+    op(0xb4, "Slice", Fixed(3), Method("slice")),
+    op(0xb5, "Filter", Fixed(2), Method("filter")),
+    op(0xb8, "FlatMap", Fixed(2), Method("flatMap")),
+    op(0xc1, "ExtractAmount", Fixed(1), Property("value")),
+    op(0xc2, "ExtractScriptBytes", Fixed(1), Property("propositionBytes")),
+    op(0xc3, "ExtractBytes", Fixed(1), Property("bytes")),
+    op(0xc4, "ExtractBytesWithNoRef", Fixed(1), Property("bytesWithoutRef")),
+    op(0xc5, "ExtractId", Fixed(1), Property("id")),
+    op(0xc7, "ExtractCreationInfo", Fixed(1), Property("creationInfo")),
+    op(0xcb, "CalcBlake2b256", Fixed(1), Function("blake2b256")),
+    op(0xcc, "CalcSha256", Fixed(1), Function("sha256")),
+    op(0xcd, "CreateProveDlog", Fixed(1), Function("proveDlog")),
+    op(0xce, "CreateProveDHTuple", Fixed(4), Function("proveDHTuple")),
+    op(0xcf, "SigmaPropIsProven", Fixed(1), Property("isProven")),
+    op(0xd0, "SigmaPropBytes", Fixed(1), Property("propBytes")),
+    op(0xd1, "BoolToSigmaProp", Fixed(1), Function("sigmaProp")),
+    op(0xd2, "TrivialPropFalse", Fixed(0), Keyword("sigmaProp(false)")),
+    op(0xd3, "TrivialPropTrue", Fixed(0), Keyword("sigmaProp(true)")),
+    op(0xdd, "Global", Fixed(0), Keyword("Global")),
+    op(0xe4, "OptionGet", Fixed(1), Property("get")),
+    op(0xe5, "OptionGetOrElse", Fixed(2), Method("getOrElse")),
+    op(0xe6, "OptionIsDefined", Fixed(1), Property("isDefined")),
+    op(0xe7, "ModQ", Fixed(1), Property("modQ")),
+    op(0xe8, "PlusModQ", Fixed(2), Method("plusModQ")),
+    op(0xe9, "MinusModQ", Fixed(2), Method("minusModQ")),
+    op(0xea, "SigmaAnd", Counted, Join("&&")),
+    op(0xeb, "SigmaOr", Counted, Join("||")),
+    op(0xec, "BinOr", Fixed(2), Infix("||")),
+    op(0xed, "BinAnd", Fixed(2), Infix("&&")),
+    op(0xee, "DecodePoint", Fixed(1), Function("decodePoint")),
+    op(0xef, "LogicalNot", Fixed(1), Prefix("!")),
+    op(0xf0, "Negation", Fixed(1), Prefix("-")),
+    op(0xf1, "BitInversion", Fixed(1), Prefix("~")),
+    op(0xf2, "BitOr", Fixed(2), Infix("|")),
+    op(0xf3, "BitAnd", Fixed(2), Infix("&")),
+    op(0xf4, "BinXor", Fixed(2), Infix("^")),
+    op(0xf5, "BitXor", Fixed(2), Infix("^")),
+    op(0xf6, "BitShiftRight", Fixed(2), Infix(">>")),
+    op(0xf7, "BitShiftLeft", Fixed(2), Infix("<<")),
+    op(0xf8, "BitShiftRightZeroed", Fixed(2), Infix(">>>")),
+    op(0xfe, "Context", Fixed(0), Keyword("CONTEXT")),
+    op(0xff, "XorOf", Fixed(1), Function("xorOf")),
+];
+
+/// A node of the expression tree
+#[derive(Debug, PartialEq)]
+pub enum Expr {
+    /// Constant written inline in the tree body
+    Constant(Constant),
+    /// Reference to a segregated constant
+    Placeholder(u32),
+    Op(&'static OpInfo, Vec<Expr>),
+    ValDef { id: u32, rhs: Box<Expr> },
+    ValUse(u32),
+    Block { items: Vec<Expr>, result: Box<Expr> },
+    Func { args: Vec<(u32, SType)>, body: Box<Expr> },
+    Apply { func: Box<Expr>, args: Vec<Expr> },
+    /// `_1` to `_5` and `SelectField`; `field` is 1-based
+    SelectField { input: Box<Expr>, field: u8 },
+    ExtractRegister { input: Box<Expr>, register: u8, tpe: SType },
+    /// `Upcast` or `Downcast`
+    Cast { name: &'static str, input: Box<Expr>, tpe: SType },
+    /// `GetVar`, `TaggedVariable` or `DeserializeContext` of a context variable
+    ContextVar { name: &'static str, id: u8, tpe: SType },
+    DeserializeRegister { register: u8, tpe: SType, default: Option<Box<Expr>> },
+    Collection { elem: SType, items: Vec<Expr> },
+    /// `PropertyCall` (no argument list) or `MethodCall`
+    Method { type_id: u8, method_id: u8, obj: Box<Expr>, args: Option<Vec<Expr>> },
+}
+
+/// A segregated constant of the tree
+#[derive(Debug, PartialEq)]
+pub struct TreeConstantInfo {
+    pub constant: Constant,
+    pub serialized: Vec<u8>,
+}
+
+/// A fully deserialized ErgoTree
+#[derive(Debug, PartialEq)]
+pub struct DecompiledTree {
+    pub header: u8,
+    pub constants: Vec<TreeConstantInfo>,
+    pub body: Expr,
+    /// Address format of `PK(..)` literals in the rendered script
+    pub network: Network,
+}
+
+/// Deserialize an ErgoTree
+pub fn decompile(bytes: &[u8], network: Network) -> Result<DecompiledTree, String> {
+    let parsed = parse_ergo_tree(bytes).ok_or("Invalid ErgoTree header or constants")?;
+    let constants: Vec<TreeConstantInfo> = parsed
+        .constants
+        .into_iter()
+        .map(|c| TreeConstantInfo {
+            constant: c.constant,
+            serialized: c.bytes.to_vec(),
+        })
+        .collect();
+
+    let mut parser = Parser {
+        r: SigmaReader::new(parsed.template),
+        offset: bytes.len() - parsed.template.len(),
+        constant_count: constants.len(),
+        depth: 0,
+    };
+    let body = parser.expr()?;
+    if !parser.r.remaining().is_empty() {
+        return Err(parser.error("Trailing bytes after the expression"));
+    }
+
+    Ok(DecompiledTree {
+        header: parsed.header,
+        constants,
+        body,
+        network,
+    })
+}
+
+/// Deserialize a hex-encoded ErgoTree
+pub fn decompile_hex(ergo_tree: &str, network: Network) -> Result<DecompiledTree, String> {
+    let bytes = hex::decode(ergo_tree).map_err(|_| "ErgoTree is not valid hex".to_string())?;
+    decompile(&bytes, network)
+}
+
+struct Parser<'a> {
+    r: SigmaReader<'a>,
+    /// Position of the tree body in the whole tree, for error offsets
+    offset: usize,
+    constant_count: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("{} at offset {}", message, self.offset + self.r.position())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        self.r.get_u8().ok_or_else(|| self.error("Unexpected end of tree"))
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        self.r.get_u16().ok_or_else(|| self.error("Invalid count"))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        self.r.get_u32().ok_or_else(|| self.error("Invalid number"))
+    }
+
+    fn tpe(&mut self) -> Result<SType, String> {
+        read_type(&mut self.r).ok_or_else(|| self.error("Invalid type"))
+    }
+
+    fn exprs(&mut self, count: usize) -> Result<Vec<Expr>, String> {
+        (0..count).map(|_| self.expr()).collect()
+    }
+
+    fn boxed(&mut self) -> Result<Box<Expr>, String> {
+        self.expr().map(Box::new)
+    }
+
+    /// An expression preceded by a presence flag
+    fn optional(&mut self) -> Result<Option<Box<Expr>>, String> {
+        match self.u8()? {
+            0 => Ok(None),
+            1 => self.boxed().map(Some),
+            _ => Err(self.error("Invalid option flag")),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("Expression nested too deeply"));
+        }
+        self.depth += 1;
+        let expr = self.read_expr();
+        self.depth -= 1;
+        expr
+    }
+
+    fn read_expr(&mut self) -> Result<Expr, String> {
+        let code = *self
+            .r
+            .remaining()
+            .first()
+            .ok_or_else(|| self.error("Unexpected end of tree"))?;
+
+        if code != 0 && code <= LAST_CONSTANT_CODE {
+            let tpe = self.tpe()?;
+            let value = read_value(&mut self.r, &tpe).ok_or_else(|| self.error("Invalid constant"))?;
+            return Ok(Expr::Constant(Constant { tpe, value }));
+        }
+        self.r.get_u8();
+
+        let expr = match code {
+            TAGGED_VARIABLE | GET_VAR => {
+                let id = self.u8()?;
+                let tpe = self.tpe()?;
+                let name = if code == GET_VAR { "GetVar" } else { "TaggedVariable" };
+                Expr::ContextVar { name, id, tpe }
+            }
+            VAL_USE => Expr::ValUse(self.u32()?),
+            CONSTANT_PLACEHOLDER => {
+                let id = self.u32()?;
+                if id as usize >= self.constant_count {
+                    return Err(self.error("Constant placeholder out of range"));
+                }
+                Expr::Placeholder(id)
+            }
+            DOWNCAST | UPCAST => {
+                let input = self.boxed()?;
+                let tpe = self.tpe()?;
+                let name = if code == UPCAST { "Upcast" } else { "Downcast" };
+                Expr::Cast { name, input, tpe }
+            }
+            TRUE | FALSE => Expr::Constant(Constant {
+                tpe: SType::Boolean,
+                value: SigmaValue::Boolean(code == TRUE),
+            }),
+            UNIT_CONSTANT => Expr::Constant(Constant {
+                tpe: SType::Unit,
+                value: SigmaValue::Unit,
+            }),
+            CONCRETE_COLLECTION => {
+                let count = self.u16()? as usize;
+                let elem = self.tpe()?;
+                let items = self.exprs(count)?;
+                Expr::Collection { elem, items }
+            }
+            CONCRETE_COLLECTION_BOOLEAN_CONSTANT => {
+                let count = self.u16()? as usize;
+                let items = self.bits(count)?.into_iter().map(bool_constant).collect();
+                Expr::Collection {
+                    elem: SType::Boolean,
+                    items,
+                }
+            }
+            SELECT_1..=SELECT_5 => Expr::SelectField {
+                input: self.boxed()?,
+                field: code - SELECT_1 + 1,
+            },
+            SELECT_FIELD => {
+                let input = self.boxed()?;
+                let field = self.u8()?;
+                Expr::SelectField { input, field }
+            }
+            BY_INDEX => {
+                let mut args = vec![self.expr()?, self.expr()?];
+                args.extend(self.optional()?.map(|default| *default));
+                Expr::Op(op_info(code), args)
+            }
+            EXTRACT_REGISTER_AS => {
+                let input = self.boxed()?;
+                let register = self.u8()?;
+                let tpe = self.tpe()?;
+                Expr::ExtractRegister { input, register, tpe }
+            }
+            DESERIALIZE_CONTEXT => {
+                let tpe = self.tpe()?;
+                let id = self.u8()?;
+                Expr::ContextVar {
+                    name: "DeserializeContext",
+                    id,
+                    tpe,
+                }
+            }
+            DESERIALIZE_REGISTER => {
+                let register = self.u8()?;
+                let tpe = self.tpe()?;
+                let default = self.optional()?;
+                Expr::DeserializeRegister { register, tpe, default }
+            }
+            VAL_DEF | FUN_DEF => {
+                let id = self.u32()?;
+                if code == FUN_DEF {
+                    // Type parameters of a generic function; not rendered
+                    let count = self.u8()?;
+                    for _ in 0..count {
+                        self.tpe()?;
+                    }
+                }
+                Expr::ValDef { id, rhs: self.boxed()? }
+            }
+            BLOCK_VALUE => {
+                let count = self.u32()? as usize;
+                let items = self.exprs(count)?;
+                Expr::Block {
+                    items,
+                    result: self.boxed()?,
+                }
+            }
+            FUNC_VALUE => {
+                let count = self.u32()?;
+                let mut args = Vec::new();
+                for _ in 0..count {
+                    let id = self.u32()?;
+                    args.push((id, self.tpe()?));
+                }
+                Expr::Func {
+                    args,
+                    body: self.boxed()?,
+                }
+            }
+            FUNC_APPLY => {
+                let func = self.boxed()?;
+                let count = self.u32()? as usize;
+                Expr::Apply {
+                    func,
+                    args: self.exprs(count)?,
+                }
+            }
+            PROPERTY_CALL | METHOD_CALL => {
+                let type_id = self.u8()?;
+                let method_id = self.u8()?;
+                let obj = self.boxed()?;
+                let args = if code == METHOD_CALL {
+                    let count = self.u32()? as usize;
+                    Some(self.exprs(count)?)
+                } else {
+                    None
+                };
+                Expr::Method {
+                    type_id,
+                    method_id,
+                    obj,
+                    args,
+                }
+            }
+            _ => {
+                let info = OPS
+                    .iter()
+                    .find(|op| op.code == code)
+                    .ok_or_else(|| self.error(&format!("Unsupported opcode 0x{:02x}", code)))?;
+                let args = if RELATIONS.contains(&code)
+                    && self.r.remaining().first() == Some(&CONCRETE_COLLECTION_BOOLEAN_CONSTANT)
+                {
+                    self.r.get_u8();
+                    self.bits(2)?.into_iter().map(bool_constant).collect()
+                } else {
+                    let count = match info.arity {
+                        Fixed(n) => n,
+                        Counted => self.u32()? as usize,
+                        CountedU8 => self.u8()? as usize,
+                    };
+                    self.exprs(count)?
+                };
+                Expr::Op(info, args)
+            }
+        };
+        Ok(expr)
+    }
+
+    /// Booleans packed eight to a byte, lowest bit first
+    fn bits(&mut self, count: usize) -> Result<Vec<bool>, String> {
+        let bytes = self
+            .r
+            .get_bytes(count.div_ceil(8))
+            .ok_or_else(|| self.error("Unexpected end of tree"))?;
+        Ok((0..count).map(|i| (bytes[i / 8] >> (i % 8)) & 1 == 1).collect())
+    }
+}
+
+fn op_info(code: u8) -> &'static OpInfo {
+    OPS.iter().find(|op| op.code == code).expect("opcode in OPS")
+}
+
+fn bool_constant(value: bool) -> Expr {
+    Expr::Constant(Constant {
+        tpe: SType::Boolean,
+        value: SigmaValue::Boolean(value),
+    })
+}
+
+/// Names of the `PropertyCall`/`MethodCall` methods contracts commonly use,
+/// by type id and method id as in sigma-state's `SMethod` tables
+fn method_name(type_id: u8, method_id: u8) -> Option<&'static str> {
+    let name = match (type_id, method_id) {
+        // SGroupElement
+        (7, 2) => "getEncoded",
+        (7, 3) => "exp",
+        (7, 4) => "multiply",
+        (7, 5) => "negate",
+        // SSigmaProp
+        (8, 1) => "propBytes",
+        (8, 2) => "isProven",
+        // SCollection
+        (12, 1) => "size",
+        (12, 2) => "getOrElse",
+        (12, 3) => "map",
+        (12, 4) => "exists",
+        (12, 5) => "fold",
+        (12, 6) => "forall",
+        (12, 7) => "slice",
+        (12, 8) => "filter",
+        (12, 9) => "append",
+        (12, 10) => "apply",
+        (12, 14) => "indices",
+        (12, 15) => "flatMap",
+        (12, 19) => "patch",
+        (12, 20) => "updated",
+        (12, 21) => "updateMany",
+        (12, 26) => "indexOf",
+        (12, 29) => "zip",
+        // SOption
+        (36, 2) => "isDefined",
+        (36, 3) => "get",
+        (36, 4) => "getOrElse",
+        (36, 7) => "map",
+        (36, 8) => "filter",
+        // SBox
+        (99, 1) => "value",
+        (99, 2) => "propositionBytes",
+        (99, 3) => "bytes",
+        (99, 4) => "bytesWithoutRef",
+        (99, 5) => "id",
+        (99, 6) => "creationInfo",
+        (99, 7) => "getReg",
+        (99, 8) => "tokens",
+        // SAvlTree
+        (100, 1) => "digest",
+        (100, 2) => "enabledOperations",
+        (100, 3) => "keyLength",
+        (100, 4) => "valueLengthOpt",
+        (100, 5) => "isInsertAllowed",
+        (100, 6) => "isUpdateAllowed",
+        (100, 7) => "isRemoveAllowed",
+        (100, 8) => "updateOperations",
+        (100, 9) => "contains",
+        (100, 10) => "get",
+        (100, 11) => "getMany",
+        (100, 12) => "insert",
+        (100, 13) => "update",
+        (100, 14) => "remove",
+        (100, 15) => "updateDigest",
+        // SContext
+        (101, 1) => "dataInputs",
+        (101, 2) => "headers",
+        (101, 3) => "preHeader",
+        (101, 4) => "INPUTS",
+        (101, 5) => "OUTPUTS",
+        (101, 6) => "HEIGHT",
+        (101, 7) => "SELF",
+        (101, 8) => "selfBoxIndex",
+        (101, 9) => "LastBlockUtxoRootHash",
+        (101, 10) => "minerPubKey",
+        (101, 11) => "getVar",
+        // SHeader
+        (104, 1) => "id",
+        (104, 2) => "version",
+        (104, 3) => "parentId",
+        (104, 4) => "ADProofsRoot",
+        (104, 5) => "stateRoot",
+        (104, 6) => "transactionsRoot",
+        (104, 7) => "timestamp",
+        (104, 8) => "nBits",
+        (104, 9) => "height",
+        (104, 10) => "extensionRoot",
+        (104, 11) => "minerPk",
+        (104, 12) => "powOnetimePk",
+        (104, 13) => "powNonce",
+        (104, 14) => "powDistance",
+        (104, 15) => "votes",
+        // SPreHeader
+        (105, 1) => "version",
+        (105, 2) => "parentId",
+        (105, 3) => "timestamp",
+        (105, 4) => "nBits",
+        (105, 5) => "height",
+        (105, 6) => "minerPk",
+        (105, 7) => "votes",
+        // SGlobal
+        (106, 1) => "groupGenerator",
+        (106, 2) => "xor",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn method_label(type_id: u8, method_id: u8) -> String {
+    method_name(type_id, method_id)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("method_{}_{}", type_id, method_id))
+}
+
+/// Numeric conversion method an `Upcast`/`Downcast` to `tpe` is written as
+fn cast_method(tpe: &SType) -> String {
+    let name = tpe.to_string();
+    format!("to{}", name.strip_prefix('S').unwrap_or(&name))
+}
+
+/// ErgoScript literal for a constant value
+fn render_literal(value: &SigmaValue, network: Network) -> String {
+    match value {
+        SigmaValue::Long(v) => format!("{}L", v),
+        SigmaValue::BigInt(_) => format!("bigInt(\"{}\")", render_value(value)),
+        SigmaValue::Bytes(bytes) => format!("fromBase16(\"{}\")", hex::encode(bytes)),
+        SigmaValue::GroupElement(bytes) => format!("decodePoint(fromBase16(\"{}\"))", hex::encode(bytes)),
+        SigmaValue::SigmaProp(SigmaBoolean::ProveDlog(pk)) => {
+            format!("PK(\"{}\")", encode_p2pk_address(pk, network))
+        }
+        SigmaValue::Coll(items) => {
+            let parts: Vec<String> = items.iter().map(|v| render_literal(v, network)).collect();
+            format!("Coll({})", parts.join(", "))
+        }
+        SigmaValue::Tuple(items) => {
+            let parts: Vec<String> = items.iter().map(|v| render_literal(v, network)).collect();
+            format!("({})", parts.join(", "))
+        }
+        SigmaValue::Opt(Some(v)) => format!("Some({})", render_literal(v, network)),
+        _ => render_value(value),
+    }
+}
+
+fn constant_json(constant: &Constant) -> Json {
+    json!({
+        "sigmaType": constant.tpe.to_string(),
+        "renderedValue": render_value(&constant.value),
+    })
+}
+
+impl DecompiledTree {
+    pub fn version(&self) -> u8 {
+        self.header & VERSION_MASK
+    }
+
+    /// ErgoScript-like rendering of the tree body, with constants inlined
+    pub fn script(&self) -> String {
+        self.render(&self.body, 0)
+    }
+
+    /// Header, constants, expression tree and pseudocode
+    pub fn to_json(&self) -> Json {
+        let constants: Vec<Json> = self
+            .constants
+            .iter()
+            .enumerate()
+            .map(|(index, c)| {
+                let mut value = constant_json(&c.constant);
+                value["index"] = json!(index);
+                value["serializedValue"] = json!(hex::encode(&c.serialized));
+                value
+            })
+            .collect();
+
+        json!({
+            "header": {
+                "version": self.version(),
+                "sizeIncluded": self.header & SIZE_FLAG != 0,
+                "constantSegregation": self.header & CONSTANT_SEGREGATION_FLAG != 0,
+            },
+            "constants": constants,
+            "expression": expr_json(&self.body),
+            "script": self.script(),
+        })
+    }
+
+    fn render(&self, expr: &Expr, indent: usize) -> String {
+        let r = |e: &Expr| self.render(e, indent);
+        let all = |es: &[Expr]| es.iter().map(r).collect::<Vec<_>>();
+
+        match expr {
+            Expr::Constant(c) => render_literal(&c.value, self.network),
+            Expr::Placeholder(id) => render_literal(&self.constants[*id as usize].constant.value, self.network),
+            Expr::ValDef { id, rhs } => format!("val v{} = {}", id, r(rhs)),
+            Expr::ValUse(id) => format!("v{}", id),
+            Expr::Block { items, result } => {
+                let pad = "  ".repeat(indent + 1);
+                let mut lines: Vec<String> = items
+                    .iter()
+                    .chain(std::iter::once(result.as_ref()))
+                    .map(|e| format!("{}{}", pad, self.render(e, indent + 1)))
+                    .collect();
+                lines.insert(0, "{".to_string());
+                lines.push(format!("{}}}", "  ".repeat(indent)));
+                lines.join("\n")
+            }
+            Expr::Func { args, body } => {
+                let params: Vec<String> = args.iter().map(|(id, tpe)| format!("v{}: {}", id, tpe)).collect();
+                format!("{{ ({}) => {} }}", params.join(", "), r(body))
+            }
+            Expr::Apply { func, args } => format!("{}({})", r(func), all(args).join(", ")),
+            Expr::SelectField { input, field } => format!("{}._{}", r(input), field),
+            Expr::ExtractRegister { input, register, tpe } => format!("{}.R{}[{}]", r(input), register, tpe),
+            Expr::Cast { input, tpe, .. } => format!("{}.{}", r(input), cast_method(tpe)),
+            Expr::ContextVar { name, id, tpe } => match *name {
+                "DeserializeContext" => format!("executeFromVar[{}]({})", tpe, id),
+                _ => format!("getVar[{}]({})", tpe, id),
+            },
+            Expr::DeserializeRegister { register, tpe, default } => match default {
+                Some(default) => format!("executeFromSelfReg[{}](R{}, {})", tpe, register, r(default)),
+                None => format!("executeFromSelfReg[{}](R{})", tpe, register),
+            },
+            Expr::Collection { elem, items } => format!("Coll[{}]({})", elem, all(items).join(", ")),
+            Expr::Method { type_id, method_id, obj, args } => {
+                let name = method_label(*type_id, *method_id);
+                match args {
+                    Some(args) => format!("{}.{}({})", r(obj), name, all(args).join(", ")),
+                    None => format!("{}.{}", r(obj), name),
+                }
+            }
+            Expr::Op(info, args) => self.render_op(info, args, indent),
+        }
+    }
+
+    fn render_op(&self, info: &OpInfo, args: &[Expr], indent: usize) -> String {
+        let rendered: Vec<String> = args.iter().map(|e| self.render(e, indent)).collect();
+        let rest = || rendered[1..].join(", ");
+
+        match info.syntax {
+            Keyword(word) => word.to_string(),
+            Infix(sym) => format!("({} {} {})", rendered[0], sym, rendered[1]),
+            Prefix(sym) => format!("{}{}", sym, rendered[0]),
+            Property(name) => format!("{}.{}", rendered[0], name),
+            Method(name) => format!("{}.{}({})", rendered[0], name, rest()),
+            Function(name) => format!("{}({})", name, rendered.join(", ")),
+            Reduce(sym, fallback) => match &args[0] {
+                Expr::Collection { items, .. } => {
+                    let parts: Vec<String> = items.iter().map(|e| self.render(e, indent)).collect();
+                    format!("({})", parts.join(&format!(" {} ", sym)))
+                }
+                _ => format!("{}({})", fallback, rendered[0]),
+            },
+            Join(sym) => format!("({})", rendered.join(&format!(" {} ", sym))),
+            If => format!("if ({}) {} else {}", rendered[0], rendered[1], rendered[2]),
+            Index if rendered.len() > 2 => format!("{}.getOrElse({})", rendered[0], rest()),
+            Index => format!("{}({})", rendered[0], rendered[1]),
+        }
+    }
+}
+
+fn exprs_json(exprs: &[Expr]) -> Vec<Json> {
+    exprs.iter().map(expr_json).collect()
+}
+
+/// Structured view of an expression; every node has an `op` with its sigma-state name
+fn expr_json(expr: &Expr) -> Json {
+    match expr {
+        Expr::Constant(c) => {
+            let mut value = constant_json(c);
+            value["op"] = json!("Constant");
+            value
+        }
+        Expr::Placeholder(id) => json!({"op": "ConstantPlaceholder", "index": id}),
+        Expr::ValDef { id, rhs } => json!({"op": "ValDef", "id": id, "rhs": expr_json(rhs)}),
+        Expr::ValUse(id) => json!({"op": "ValUse", "id": id}),
+        Expr::Block { items, result } => json!({
+            "op": "BlockValue",
+            "items": exprs_json(items),
+            "result": expr_json(result),
+        }),
+        Expr::Func { args, body } => {
+            let args: Vec<Json> = args
+                .iter()
+                .map(|(id, tpe)| json!({"id": id, "sigmaType": tpe.to_string()}))
+                .collect();
+            json!({"op": "FuncValue", "args": args, "body": expr_json(body)})
+        }
+        Expr::Apply { func, args } => json!({
+            "op": "FuncApply",
+            "func": expr_json(func),
+            "args": exprs_json(args),
+        }),
+        Expr::SelectField { input, field } => json!({
+            "op": "SelectField",
+            "field": field,
+            "input": expr_json(input),
+        }),
+        Expr::ExtractRegister { input, register, tpe } => json!({
+            "op": "ExtractRegisterAs",
+            "register": format!("R{}", register),
+            "sigmaType": tpe.to_string(),
+            "input": expr_json(input),
+        }),
+        Expr::Cast { name, input, tpe } => json!({
+            "op": name,
+            "sigmaType": tpe.to_string(),
+            "input": expr_json(input),
+        }),
+        Expr::ContextVar { name, id, tpe } => json!({
+            "op": name,
+            "id": id,
+            "sigmaType": tpe.to_string(),
+        }),
+        Expr::DeserializeRegister { register, tpe, default } => json!({
+            "op": "DeserializeRegister",
+            "register": format!("R{}", register),
+            "sigmaType": tpe.to_string(),
+            "default": default.as_deref().map(expr_json),
+        }),
+        Expr::Collection { elem, items } => json!({
+            "op": "ConcreteCollection",
+            "sigmaType": elem.to_string(),
+            "items": exprs_json(items),
+        }),
+        Expr::Method { type_id, method_id, obj, args } => json!({
+            "op": if args.is_some() { "MethodCall" } else { "PropertyCall" },
+            "typeId": type_id,
+            "methodId": method_id,
+            "method": method_name(*type_id, *method_id),
+            "obj": expr_json(obj),
+            "args": args.as_deref().map(exprs_json),
+        }),
+        Expr::Op(info, args) => json!({"op": info.name, "args": exprs_json(args)}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ergo_tree::{ergo_tree_to_address, miner_pk_to_address, MINERS_FEE_ERGO_TREE};
+    use crate::utils::network::Network::{Mainnet, Testnet};
+
+    #[test]
+    fn test_p2pk() {
+        let ergo_tree = "0008cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let tree = decompile_hex(ergo_tree, Mainnet).unwrap();
+        assert_eq!(tree.version(), 0);
+        assert!(tree.constants.is_empty());
+        assert_eq!(tree.script(), format!("PK(\"{}\")", ergo_tree_to_address(ergo_tree, Mainnet).unwrap()));
+
+        let tree = decompile_hex(ergo_tree, Testnet).unwrap();
+        assert_eq!(tree.script(), format!("PK(\"{}\")", ergo_tree_to_address(ergo_tree, Testnet).unwrap()));
+    }
+
+    #[test]
+    fn test_miner_reward() {
+        let pk = "021dde34603426402615658f1d970cfa7c7bd92ac81a8b16eeebff264d59ce4604";
+        let tree = decompile_hex(&format!("100204a00b08cd{}ea02d192a39a8cc7a70173007301", pk), Mainnet).unwrap();
+        assert_eq!(tree.constants.len(), 2);
+        assert_eq!(
+            tree.script(),
+            format!(
+                "(sigmaProp((HEIGHT >= (SELF.creationInfo._1 + 720))) && PK(\"{}\"))",
+                miner_pk_to_address(pk, Mainnet).unwrap()
+            )
+        );
+
+        let json = tree.to_json();
+        assert_eq!(json["header"]["constantSegregation"], true);
+        assert_eq!(json["constants"][0]["renderedValue"], "720");
+        assert_eq!(json["expression"]["op"], "SigmaAnd");
+        assert_eq!(json["expression"]["args"][1]["op"], "ConstantPlaceholder");
+    }
+
+    #[test]
+    fn test_miners_fee() {
+        let tree = decompile_hex(MINERS_FEE_ERGO_TREE, Mainnet).unwrap();
+        assert_eq!(
+            tree.script(),
+            "sigmaProp(((HEIGHT == OUTPUTS(0).creationInfo._1) && \
+             (OUTPUTS(0).propositionBytes == substConstants(fromBase16(\"100204a00b08cd0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ea02d192a39a8cc7a70173007301\"), \
+             Coll(1), Coll[SSigmaProp](proveDlog(decodePoint(minerPubKey))))) && \
+             (OUTPUTS.size == 1)))"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(decompile_hex("zz", Mainnet).is_err());
+        // Placeholder without segregated constants
+        assert!(decompile_hex("007300", Mainnet).unwrap_err().contains("out of range"));
+        // Truncated sigmaProp(...)
+        assert!(decompile_hex("00d1", Mainnet).unwrap_err().contains("end of tree"));
+        // Unassigned opcode
+        assert!(decompile_hex("00b9", Mainnet).unwrap_err().contains("0xb9"));
+    }
+}
@@ -1,10 +1,17 @@
+pub mod cursor;
+pub mod emission;
 pub mod ergo_tree;
+pub mod network;
+pub mod sigma;
 
 use sha2::{Digest, Sha256};
 use blake2::{Blake2b, digest::consts::U32};
 
 type Blake2b256 = Blake2b<U32>;
 
+/// Most decimals a token can meaningfully have: an i64 amount has 19 digits
+pub const MAX_TOKEN_DECIMALS: i32 = 19;
+
 /// Compute Blake2b256 hash
 pub fn blake2b256(data: &[u8]) -> Vec<u8> {
     let mut hasher = Blake2b256::new();
@@ -19,6 +26,24 @@ pub fn sha256(data: &[u8]) -> Vec<u8> {
     hasher.finalize().to_vec()
 }
 
+/// Compute HMAC-SHA256 (RFC 2104)
+pub fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    const BLOCK_SIZE: usize = 64;
+
+    let mut block_key = if key.len() > BLOCK_SIZE { sha256(key) } else { key.to_vec() };
+    block_key.resize(BLOCK_SIZE, 0);
+
+    let mut inner = Sha256::new();
+    inner.update(block_key.iter().map(|b| b ^ 0x36).collect::<Vec<u8>>());
+    inner.update(message);
+    let inner_hash = inner.finalize();
+
+    let mut outer = Sha256::new();
+    outer.update(block_key.iter().map(|b| b ^ 0x5c).collect::<Vec<u8>>());
+    outer.update(inner_hash);
+    outer.finalize().to_vec()
+}
+
 /// Encode bytes as hex string
 pub fn to_hex(data: &[u8]) -> String {
     hex::encode(data)
@@ -29,6 +54,25 @@ pub fn from_hex(s: &str) -> Option<Vec<u8>> {
     hex::decode(s).ok()
 }
 
+/// Raw token amount as a decimal string scaled by the token's decimals,
+/// e.g. 1500 with 3 decimals is "1.5"
+pub fn format_token_amount(amount: i64, decimals: Option<i32>) -> String {
+    let decimals = decimals.unwrap_or(0).clamp(0, MAX_TOKEN_DECIMALS) as usize;
+    if decimals == 0 {
+        return amount.to_string();
+    }
+
+    let digits = format!("{:0>width$}", amount.unsigned_abs(), width = decimals + 1);
+    let (whole, fraction) = digits.split_at(digits.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    let sign = if amount < 0 { "-" } else { "" };
+    if fraction.is_empty() {
+        format!("{}{}", sign, whole)
+    } else {
+        format!("{}{}.{}", sign, whole, fraction)
+    }
+}
+
 /// Paginate a vector
 pub fn paginate<T: Clone>(items: &[T], offset: usize, limit: usize) -> Vec<T> {
     items
@@ -43,3 +87,19 @@ pub fn paginate<T: Clone>(items: &[T], offset: usize, limit: usize) -> Vec<T> {
 pub fn parse_i64(s: &str) -> Option<i64> {
     s.parse().ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_token_amount() {
+        assert_eq!(format_token_amount(1500, Some(3)), "1.5");
+        assert_eq!(format_token_amount(42, Some(4)), "0.0042");
+        assert_eq!(format_token_amount(-1_000_000_000, Some(9)), "-1");
+        assert_eq!(format_token_amount(7, None), "7");
+        assert_eq!(format_token_amount(i64::MIN, Some(2)), "-92233720368547758.08");
+        assert_eq!(format_token_amount(5, Some(i32::MAX)), "0.0000000000000000005");
+        assert_eq!(format_token_amount(5, Some(-3)), "5");
+    }
+}
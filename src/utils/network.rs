@@ -0,0 +1,78 @@
+//! Ergo network selection
+//!
+//! The network decides address prefixes and the emission schedule, and is
+//! checked against the node's genesis block at startup.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Id of mainnet block 1, as reported in the node's `/info`
+pub const MAINNET_GENESIS_BLOCK_ID: &str = "b0244dfc267baca974a4caee06120321562784303a8a688976ae56170e4d175b";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    pub fn is_mainnet(&self) -> bool {
+        *self == Network::Mainnet
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+        }
+    }
+
+    /// Whether a node reporting `genesis_block_id` is on this network. Testnet has
+    /// been relaunched with new genesis blocks, so there only mainnet's is ruled out.
+    pub fn matches_genesis(&self, genesis_block_id: &str) -> bool {
+        let is_mainnet_genesis = genesis_block_id.eq_ignore_ascii_case(MAINNET_GENESIS_BLOCK_ID);
+        match self {
+            Network::Mainnet => is_mainnet_genesis,
+            Network::Testnet => !is_mainnet_genesis,
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("mainnet") {
+            Ok(Network::Mainnet)
+        } else if s.eq_ignore_ascii_case("testnet") {
+            Ok(Network::Testnet)
+        } else {
+            Err(format!("Unknown network '{}', expected mainnet or testnet", s))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_genesis() {
+        assert_eq!("Mainnet".parse::<Network>(), Ok(Network::Mainnet));
+        assert_eq!("testnet".parse::<Network>(), Ok(Network::Testnet));
+        assert!("devnet".parse::<Network>().is_err());
+
+        assert!(Network::Mainnet.matches_genesis(MAINNET_GENESIS_BLOCK_ID));
+        assert!(!Network::Testnet.matches_genesis(MAINNET_GENESIS_BLOCK_ID));
+        let other = "0000000000000000000000000000000000000000000000000000000000000001";
+        assert!(!Network::Mainnet.matches_genesis(other));
+        assert!(Network::Testnet.matches_genesis(other));
+    }
+}
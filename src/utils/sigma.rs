@@ -0,0 +1,646 @@
+//! Sigma serialization primitives
+//!
+//! A reader for the VLQ/ZigZag encoded byte streams used by sigma-state, plus
+//! decoders for type descriptors and constant values. This is what ErgoTree
+//! constants and box registers are made of.
+
+use std::fmt;
+
+/// Type code constants from sigma-state's `TypeSerializer`
+const PRIM_RANGE: u8 = 12;
+const TUPLE_TYPE_CODE: u8 = 96;
+const ANY_TYPE_CODE: u8 = 97;
+const UNIT_TYPE_CODE: u8 = 98;
+const BOX_TYPE_CODE: u8 = 99;
+const AVL_TREE_TYPE_CODE: u8 = 100;
+const CONTEXT_TYPE_CODE: u8 = 101;
+const STRING_TYPE_CODE: u8 = 102;
+const HEADER_TYPE_CODE: u8 = 104;
+const PRE_HEADER_TYPE_CODE: u8 = 105;
+const GLOBAL_TYPE_CODE: u8 = 106;
+
+/// SigmaBoolean node codes
+const PROVE_DLOG_CODE: u8 = 0xcd;
+const PROVE_DH_TUPLE_CODE: u8 = 0xce;
+const CAND_CODE: u8 = 0x96;
+const COR_CODE: u8 = 0x97;
+const CTHRESHOLD_CODE: u8 = 0x98;
+const TRIVIAL_FALSE_CODE: u8 = 0xd2;
+const TRIVIAL_TRUE_CODE: u8 = 0xd3;
+
+/// Size of a compressed secp256k1 point
+const GROUP_ELEMENT_SIZE: usize = 33;
+
+/// Deepest nesting of types, values and SigmaBoolean nodes that is decoded.
+/// Input is untrusted, and each level is a stack frame.
+const MAX_DEPTH: usize = 64;
+
+/// Cursor over a sigma-serialized byte slice
+pub struct SigmaReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> SigmaReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Number of bytes consumed so far
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Bytes not consumed yet
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.pos..]
+    }
+
+    pub fn get_u8(&mut self) -> Option<u8> {
+        let b = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+        Some(b)
+    }
+
+    pub fn get_bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(n)?;
+        let slice = self.bytes.get(self.pos..end)?;
+        self.pos = end;
+        Some(slice)
+    }
+
+    /// Unsigned VLQ (up to 64 bits)
+    pub fn get_vlq(&mut self) -> Option<u64> {
+        let mut value: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.get_u8()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+            shift += 7;
+            if shift >= 64 {
+                return None;
+            }
+        }
+    }
+
+    /// Unsigned VLQ that must fit in 16 bits (collection lengths, counts)
+    pub fn get_u16(&mut self) -> Option<u16> {
+        u16::try_from(self.get_vlq()?).ok()
+    }
+
+    /// Unsigned VLQ that must fit in 32 bits (tree sizes, constant counts)
+    pub fn get_u32(&mut self) -> Option<u32> {
+        u32::try_from(self.get_vlq()?).ok()
+    }
+
+    /// ZigZag + VLQ encoded 32-bit integer
+    pub fn get_i32(&mut self) -> Option<i32> {
+        let raw = u32::try_from(self.get_vlq()?).ok()?;
+        Some(((raw >> 1) as i32) ^ -((raw & 1) as i32))
+    }
+
+    /// ZigZag + VLQ encoded 64-bit integer
+    pub fn get_i64(&mut self) -> Option<i64> {
+        let raw = self.get_vlq()?;
+        Some(((raw >> 1) as i64) ^ -((raw & 1) as i64))
+    }
+}
+
+/// Sigma type descriptor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SType {
+    Boolean,
+    Byte,
+    Short,
+    Int,
+    Long,
+    BigInt,
+    GroupElement,
+    SigmaProp,
+    Coll(Box<SType>),
+    Option(Box<SType>),
+    Tuple(Vec<SType>),
+    Any,
+    Unit,
+    Box,
+    AvlTree,
+    Context,
+    String,
+    Header,
+    PreHeader,
+    Global,
+}
+
+/// Decoded constant value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SigmaValue {
+    Boolean(bool),
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    /// Big-endian two's complement bytes
+    BigInt(Vec<u8>),
+    /// Compressed point bytes
+    GroupElement(Vec<u8>),
+    SigmaProp(SigmaBoolean),
+    /// `Coll[Byte]` kept as raw bytes
+    Bytes(Vec<u8>),
+    Coll(Vec<SigmaValue>),
+    Tuple(Vec<SigmaValue>),
+    Opt(Option<Box<SigmaValue>>),
+    Unit,
+}
+
+/// Sigma proposition tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SigmaBoolean {
+    TrivialTrue,
+    TrivialFalse,
+    ProveDlog(Vec<u8>),
+    ProveDhTuple(Vec<u8>, Vec<u8>, Vec<u8>, Vec<u8>),
+    And(Vec<SigmaBoolean>),
+    Or(Vec<SigmaBoolean>),
+    Threshold(u16, Vec<SigmaBoolean>),
+}
+
+/// A typed constant as stored in ErgoTrees and registers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constant {
+    pub tpe: SType,
+    pub value: SigmaValue,
+}
+
+fn embeddable_type(code: u8) -> Option<SType> {
+    match code {
+        1 => Some(SType::Boolean),
+        2 => Some(SType::Byte),
+        3 => Some(SType::Short),
+        4 => Some(SType::Int),
+        5 => Some(SType::Long),
+        6 => Some(SType::BigInt),
+        7 => Some(SType::GroupElement),
+        8 => Some(SType::SigmaProp),
+        _ => None,
+    }
+}
+
+/// Element type of a type constructor: embedded in the code or following it
+fn arg_type(r: &mut SigmaReader, prim_id: u8, depth: usize) -> Option<SType> {
+    if prim_id == 0 {
+        read_type_at(r, depth)
+    } else {
+        embeddable_type(prim_id)
+    }
+}
+
+/// Read a serialized type descriptor
+pub fn read_type(r: &mut SigmaReader) -> Option<SType> {
+    read_type_at(r, 0)
+}
+
+fn read_type_at(r: &mut SigmaReader, depth: usize) -> Option<SType> {
+    if depth >= MAX_DEPTH {
+        return None;
+    }
+    let depth = depth + 1;
+    let code = r.get_u8()?;
+    if code == 0 {
+        return None;
+    }
+
+    if code < TUPLE_TYPE_CODE {
+        let constr_id = code / PRIM_RANGE;
+        let prim_id = code % PRIM_RANGE;
+
+        return match constr_id {
+            0 => embeddable_type(code),
+            1 => Some(SType::Coll(Box::new(arg_type(r, prim_id, depth)?))),
+            2 => Some(SType::Coll(Box::new(SType::Coll(Box::new(arg_type(r, prim_id, depth)?))))),
+            3 => Some(SType::Option(Box::new(arg_type(r, prim_id, depth)?))),
+            4 => Some(SType::Option(Box::new(SType::Coll(Box::new(arg_type(r, prim_id, depth)?))))),
+            // (prim, T) or (T1, T2)
+            5 => {
+                let t1 = arg_type(r, prim_id, depth)?;
+                let t2 = read_type_at(r, depth)?;
+                Some(SType::Tuple(vec![t1, t2]))
+            }
+            // (T, prim) or a triple
+            6 => {
+                if prim_id == 0 {
+                    let t1 = read_type_at(r, depth)?;
+                    let t2 = read_type_at(r, depth)?;
+                    let t3 = read_type_at(r, depth)?;
+                    Some(SType::Tuple(vec![t1, t2, t3]))
+                } else {
+                    let t2 = embeddable_type(prim_id)?;
+                    let t1 = read_type_at(r, depth)?;
+                    Some(SType::Tuple(vec![t1, t2]))
+                }
+            }
+            // (prim, prim) or a quadruple
+            7 => {
+                if prim_id == 0 {
+                    let items = (0..4).map(|_| read_type_at(r, depth)).collect::<Option<Vec<_>>>()?;
+                    Some(SType::Tuple(items))
+                } else {
+                    let t = embeddable_type(prim_id)?;
+                    Some(SType::Tuple(vec![t.clone(), t]))
+                }
+            }
+            _ => None,
+        };
+    }
+
+    match code {
+        TUPLE_TYPE_CODE => {
+            let len = r.get_u8()?;
+            let items = (0..len).map(|_| read_type_at(r, depth)).collect::<Option<Vec<_>>>()?;
+            Some(SType::Tuple(items))
+        }
+        ANY_TYPE_CODE => Some(SType::Any),
+        UNIT_TYPE_CODE => Some(SType::Unit),
+        BOX_TYPE_CODE => Some(SType::Box),
+        AVL_TREE_TYPE_CODE => Some(SType::AvlTree),
+        CONTEXT_TYPE_CODE => Some(SType::Context),
+        STRING_TYPE_CODE => Some(SType::String),
+        HEADER_TYPE_CODE => Some(SType::Header),
+        PRE_HEADER_TYPE_CODE => Some(SType::PreHeader),
+        GLOBAL_TYPE_CODE => Some(SType::Global),
+        _ => None,
+    }
+}
+
+/// Read a value of the given type
+pub fn read_value(r: &mut SigmaReader, tpe: &SType) -> Option<SigmaValue> {
+    read_value_at(r, tpe, 0)
+}
+
+fn read_value_at(r: &mut SigmaReader, tpe: &SType, depth: usize) -> Option<SigmaValue> {
+    if depth >= MAX_DEPTH {
+        return None;
+    }
+    let depth = depth + 1;
+    match tpe {
+        SType::Boolean => Some(SigmaValue::Boolean(r.get_u8()? != 0)),
+        SType::Byte => Some(SigmaValue::Byte(r.get_u8()? as i8)),
+        SType::Short => Some(SigmaValue::Short(i16::try_from(r.get_i32()?).ok()?)),
+        SType::Int => Some(SigmaValue::Int(r.get_i32()?)),
+        SType::Long => Some(SigmaValue::Long(r.get_i64()?)),
+        SType::BigInt => {
+            let len = r.get_u16()? as usize;
+            Some(SigmaValue::BigInt(r.get_bytes(len)?.to_vec()))
+        }
+        SType::GroupElement => Some(SigmaValue::GroupElement(r.get_bytes(GROUP_ELEMENT_SIZE)?.to_vec())),
+        SType::SigmaProp => Some(SigmaValue::SigmaProp(read_sigma_boolean_at(r, depth)?)),
+        SType::Coll(elem) => {
+            let len = r.get_u16()? as usize;
+            match elem.as_ref() {
+                SType::Byte => Some(SigmaValue::Bytes(r.get_bytes(len)?.to_vec())),
+                SType::Boolean => {
+                    // Booleans are bit-packed, least significant bit first
+                    let packed = r.get_bytes(len.div_ceil(8))?;
+                    let bits = (0..len)
+                        .map(|i| SigmaValue::Boolean(packed[i / 8] & (1 << (i % 8)) != 0))
+                        .collect();
+                    Some(SigmaValue::Coll(bits))
+                }
+                other => {
+                    let items = (0..len).map(|_| read_value_at(r, other, depth)).collect::<Option<Vec<_>>>()?;
+                    Some(SigmaValue::Coll(items))
+                }
+            }
+        }
+        SType::Tuple(items) => {
+            let values = items.iter().map(|t| read_value_at(r, t, depth)).collect::<Option<Vec<_>>>()?;
+            Some(SigmaValue::Tuple(values))
+        }
+        // Flag byte, then the value when present
+        SType::Option(elem) => match r.get_u8()? {
+            0 => Some(SigmaValue::Opt(None)),
+            1 => Some(SigmaValue::Opt(Some(Box::new(read_value_at(r, elem, depth)?)))),
+            _ => None,
+        },
+        SType::Unit => Some(SigmaValue::Unit),
+        _ => None,
+    }
+}
+
+/// Read a serialized SigmaBoolean (the payload of a SigmaProp)
+fn read_sigma_boolean_at(r: &mut SigmaReader, depth: usize) -> Option<SigmaBoolean> {
+    if depth >= MAX_DEPTH {
+        return None;
+    }
+    let depth = depth + 1;
+    match r.get_u8()? {
+        PROVE_DLOG_CODE => Some(SigmaBoolean::ProveDlog(r.get_bytes(GROUP_ELEMENT_SIZE)?.to_vec())),
+        PROVE_DH_TUPLE_CODE => {
+            let g = r.get_bytes(GROUP_ELEMENT_SIZE)?.to_vec();
+            let h = r.get_bytes(GROUP_ELEMENT_SIZE)?.to_vec();
+            let u = r.get_bytes(GROUP_ELEMENT_SIZE)?.to_vec();
+            let v = r.get_bytes(GROUP_ELEMENT_SIZE)?.to_vec();
+            Some(SigmaBoolean::ProveDhTuple(g, h, u, v))
+        }
+        CAND_CODE => {
+            let n = r.get_u16()?;
+            let children = (0..n).map(|_| read_sigma_boolean_at(r, depth)).collect::<Option<Vec<_>>>()?;
+            Some(SigmaBoolean::And(children))
+        }
+        COR_CODE => {
+            let n = r.get_u16()?;
+            let children = (0..n).map(|_| read_sigma_boolean_at(r, depth)).collect::<Option<Vec<_>>>()?;
+            Some(SigmaBoolean::Or(children))
+        }
+        CTHRESHOLD_CODE => {
+            let k = r.get_u16()?;
+            let n = r.get_u16()?;
+            let children = (0..n).map(|_| read_sigma_boolean_at(r, depth)).collect::<Option<Vec<_>>>()?;
+            Some(SigmaBoolean::Threshold(k, children))
+        }
+        TRIVIAL_TRUE_CODE => Some(SigmaBoolean::TrivialTrue),
+        TRIVIAL_FALSE_CODE => Some(SigmaBoolean::TrivialFalse),
+        _ => None,
+    }
+}
+
+/// Read a type descriptor followed by a value of that type
+pub fn read_constant(r: &mut SigmaReader) -> Option<Constant> {
+    let tpe = read_type(r)?;
+    let value = read_value(r, &tpe)?;
+    Some(Constant { tpe, value })
+}
+
+/// Decode a hex-encoded constant, e.g. a box register value
+pub fn decode_constant_hex(hex: &str) -> Option<Constant> {
+    let bytes = hex::decode(hex).ok()?;
+    let mut r = SigmaReader::new(&bytes);
+    read_constant(&mut r)
+}
+
+impl fmt::Display for SType {
+    /// Type names as rendered by the Explorer, e.g. `SInt`, `Coll[SByte]`, `(SInt, SLong)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SType::Boolean => write!(f, "SBoolean"),
+            SType::Byte => write!(f, "SByte"),
+            SType::Short => write!(f, "SShort"),
+            SType::Int => write!(f, "SInt"),
+            SType::Long => write!(f, "SLong"),
+            SType::BigInt => write!(f, "SBigInt"),
+            SType::GroupElement => write!(f, "SGroupElement"),
+            SType::SigmaProp => write!(f, "SSigmaProp"),
+            SType::Coll(elem) => write!(f, "Coll[{}]", elem),
+            SType::Option(elem) => write!(f, "Option[{}]", elem),
+            SType::Tuple(items) => {
+                let names: Vec<String> = items.iter().map(|t| t.to_string()).collect();
+                write!(f, "({})", names.join(", "))
+            }
+            SType::Any => write!(f, "SAny"),
+            SType::Unit => write!(f, "SUnit"),
+            SType::Box => write!(f, "SBox"),
+            SType::AvlTree => write!(f, "SAvlTree"),
+            SType::Context => write!(f, "SContext"),
+            SType::String => write!(f, "SString"),
+            SType::Header => write!(f, "SHeader"),
+            SType::PreHeader => write!(f, "SPreHeader"),
+            SType::Global => write!(f, "SGlobal"),
+        }
+    }
+}
+
+/// Serialize a SigmaBoolean back to its wire format
+pub fn serialize_sigma_boolean(sb: &SigmaBoolean) -> Vec<u8> {
+    fn put_vlq(out: &mut Vec<u8>, mut v: u64) {
+        while v >= 0x80 {
+            out.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        out.push(v as u8);
+    }
+
+    fn write(sb: &SigmaBoolean, out: &mut Vec<u8>) {
+        match sb {
+            SigmaBoolean::TrivialTrue => out.push(TRIVIAL_TRUE_CODE),
+            SigmaBoolean::TrivialFalse => out.push(TRIVIAL_FALSE_CODE),
+            SigmaBoolean::ProveDlog(pk) => {
+                out.push(PROVE_DLOG_CODE);
+                out.extend_from_slice(pk);
+            }
+            SigmaBoolean::ProveDhTuple(g, h, u, v) => {
+                out.push(PROVE_DH_TUPLE_CODE);
+                for point in [g, h, u, v] {
+                    out.extend_from_slice(point);
+                }
+            }
+            SigmaBoolean::And(children) | SigmaBoolean::Or(children) => {
+                out.push(if matches!(sb, SigmaBoolean::And(_)) { CAND_CODE } else { COR_CODE });
+                put_vlq(out, children.len() as u64);
+                children.iter().for_each(|c| write(c, out));
+            }
+            SigmaBoolean::Threshold(k, children) => {
+                out.push(CTHRESHOLD_CODE);
+                put_vlq(out, *k as u64);
+                put_vlq(out, children.len() as u64);
+                children.iter().for_each(|c| write(c, out));
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    write(sb, &mut out);
+    out
+}
+
+/// Human-readable rendering of a value, following the Explorer:
+/// byte collections and points as hex, numbers in decimal, a ProveDlog as its
+/// public key, collections as `[a,b]` and tuples as `(a,b)`
+pub fn render_value(value: &SigmaValue) -> String {
+    match value {
+        SigmaValue::Boolean(b) => b.to_string(),
+        SigmaValue::Byte(v) => v.to_string(),
+        SigmaValue::Short(v) => v.to_string(),
+        SigmaValue::Int(v) => v.to_string(),
+        SigmaValue::Long(v) => v.to_string(),
+        SigmaValue::BigInt(bytes) => render_big_int(bytes),
+        SigmaValue::GroupElement(bytes) | SigmaValue::Bytes(bytes) => hex::encode(bytes),
+        SigmaValue::SigmaProp(SigmaBoolean::ProveDlog(pk)) => hex::encode(pk),
+        SigmaValue::SigmaProp(sb) => hex::encode(serialize_sigma_boolean(sb)),
+        SigmaValue::Coll(items) => {
+            let parts: Vec<String> = items.iter().map(render_value).collect();
+            format!("[{}]", parts.join(","))
+        }
+        SigmaValue::Tuple(items) => {
+            let parts: Vec<String> = items.iter().map(render_value).collect();
+            format!("({})", parts.join(","))
+        }
+        SigmaValue::Opt(Some(v)) => format!("Some({})", render_value(v)),
+        SigmaValue::Opt(None) => "None".to_string(),
+        SigmaValue::Unit => "()".to_string(),
+    }
+}
+
+/// Explorer-style view of a box's registers: each `"R4": "<hex>"` of the node's
+/// map becomes `"R4": {"serializedValue", "sigmaType", "renderedValue"}`. Values
+/// that don't decode keep only `serializedValue`.
+pub fn render_registers(registers: &serde_json::Value) -> serde_json::Value {
+    let Some(registers) = registers.as_object() else {
+        return registers.clone();
+    };
+
+    let rendered = registers
+        .iter()
+        .map(|(id, value)| {
+            let serialized = value
+                .as_str()
+                .or_else(|| value.get("serializedValue").and_then(|v| v.as_str()))
+                .unwrap_or_default();
+            let mut entry = serde_json::json!({ "serializedValue": serialized });
+            if let Some(constant) = decode_constant_hex(serialized) {
+                entry["sigmaType"] = constant.tpe.to_string().into();
+                entry["renderedValue"] = render_value(&constant.value).into();
+            }
+            (id.clone(), entry)
+        })
+        .collect();
+    serde_json::Value::Object(rendered)
+}
+
+/// Decimal for values that fit in 128 bits, hex otherwise
+fn render_big_int(bytes: &[u8]) -> String {
+    if bytes.is_empty() || bytes.len() > 16 {
+        return hex::encode(bytes);
+    }
+    let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0x00 };
+    let mut buf = [fill; 16];
+    buf[16 - bytes.len()..].copy_from_slice(bytes);
+    i128::from_be_bytes(buf).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_coll_byte() {
+        // "Test" as Coll[Byte]
+        let c = decode_constant_hex("0e0454657374").unwrap();
+        assert_eq!(c.tpe, SType::Coll(Box::new(SType::Byte)));
+        assert_eq!(c.value, SigmaValue::Bytes(b"Test".to_vec()));
+    }
+
+    #[test]
+    fn test_decode_numbers() {
+        assert_eq!(decode_constant_hex("0400").unwrap().value, SigmaValue::Int(0));
+        assert_eq!(decode_constant_hex("04a00b").unwrap().value, SigmaValue::Int(720));
+        assert_eq!(decode_constant_hex("0401").unwrap().value, SigmaValue::Int(-1));
+        assert_eq!(decode_constant_hex("0580dac409").unwrap().value, SigmaValue::Long(10_000_000));
+    }
+
+    #[test]
+    fn test_decode_coll_int_and_sigma_prop() {
+        let c = decode_constant_hex("10020204").unwrap();
+        assert_eq!(c.tpe, SType::Coll(Box::new(SType::Int)));
+        assert_eq!(c.value, SigmaValue::Coll(vec![SigmaValue::Int(1), SigmaValue::Int(2)]));
+
+        let pk = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let c = decode_constant_hex(&format!("08cd{}", pk)).unwrap();
+        assert_eq!(c.tpe, SType::SigmaProp);
+        assert_eq!(
+            c.value,
+            SigmaValue::SigmaProp(SigmaBoolean::ProveDlog(hex::decode(pk).unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_decode_tuple_type() {
+        // (Coll[Byte], Int) encoded as Pair2 with embedded Int
+        let mut bytes = vec![0x4c, 0x0e];
+        bytes.extend_from_slice(&[0x01, 0xff, 0x06]);
+        let mut r = SigmaReader::new(&bytes);
+        let c = read_constant(&mut r).unwrap();
+        assert_eq!(
+            c.tpe,
+            SType::Tuple(vec![SType::Coll(Box::new(SType::Byte)), SType::Int])
+        );
+        assert_eq!(
+            c.value,
+            SigmaValue::Tuple(vec![SigmaValue::Bytes(vec![0xff]), SigmaValue::Int(3)])
+        );
+    }
+
+    #[test]
+    fn test_type_names_and_rendering() {
+        let c = decode_constant_hex("10020204").unwrap();
+        assert_eq!(c.tpe.to_string(), "Coll[SInt]");
+        assert_eq!(render_value(&c.value), "[1,2]");
+
+        let c = decode_constant_hex("0e0454657374").unwrap();
+        assert_eq!(c.tpe.to_string(), "Coll[SByte]");
+        assert_eq!(render_value(&c.value), "54657374");
+
+        let c = decode_constant_hex("0580dac409").unwrap();
+        assert_eq!(c.tpe.to_string(), "SLong");
+        assert_eq!(render_value(&c.value), "10000000");
+    }
+
+    #[test]
+    fn test_decode_long_bigint_group_element_and_option() {
+        assert_eq!(render_value(&decode_constant_hex("05feffffffffffffffff01").unwrap().value), "9223372036854775807");
+        assert_eq!(render_value(&decode_constant_hex("060201f4").unwrap().value), "500");
+
+        let point = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let c = decode_constant_hex(&format!("07{}", point)).unwrap();
+        assert_eq!(c.tpe.to_string(), "SGroupElement");
+        assert_eq!(render_value(&c.value), point);
+
+        // Option[Int]: Some(3), None
+        let c = decode_constant_hex("280106").unwrap();
+        assert_eq!(c.tpe.to_string(), "Option[SInt]");
+        assert_eq!(render_value(&c.value), "Some(3)");
+        assert_eq!(render_value(&decode_constant_hex("2800").unwrap().value), "None");
+
+        // Coll[Coll[Byte]]
+        let c = decode_constant_hex("1a0201aa02bbcc").unwrap();
+        assert_eq!(c.tpe.to_string(), "Coll[Coll[SByte]]");
+        assert_eq!(render_value(&c.value), "[aa,bbcc]");
+    }
+
+    #[test]
+    fn test_render_registers() {
+        let registers = serde_json::json!({ "R4": "0e0454657374", "R5": "ff" });
+        let rendered = render_registers(&registers);
+        assert_eq!(
+            rendered["R4"],
+            serde_json::json!({
+                "serializedValue": "0e0454657374",
+                "sigmaType": "Coll[SByte]",
+                "renderedValue": "54657374"
+            })
+        );
+        assert_eq!(rendered["R5"], serde_json::json!({ "serializedValue": "ff" }));
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        assert!(decode_constant_hex("0e05aabb").is_none());
+        assert!(decode_constant_hex("").is_none());
+    }
+
+    #[test]
+    fn test_nesting_depth_is_bounded() {
+        // Coll[Coll[...[Byte]]] nested far past MAX_DEPTH must fail, not overflow the stack
+        assert!(decode_constant_hex(&("0c".repeat(10_000) + "02")).is_none());
+        // Nested AND nodes inside a SigmaProp
+        assert!(decode_constant_hex(&("08".to_string() + &"9601".repeat(10_000) + "d3")).is_none());
+
+        // Shallow nesting still decodes: Coll[Coll[Coll[Byte]]] holding one empty Coll[Byte]
+        let nested = decode_constant_hex("0c0c0e010100").unwrap();
+        assert_eq!(
+            nested.value,
+            SigmaValue::Coll(vec![SigmaValue::Coll(vec![SigmaValue::Bytes(vec![])])])
+        );
+    }
+}
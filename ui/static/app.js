@@ -872,8 +872,9 @@ async function performSearch() {
     const query = document.getElementById('searchInput').value.trim();
     if (!query) return;
 
-    const results = await fetchApi(`/search?query=${encodeURIComponent(query)}`);
-    if (!results || results.length === 0) {
+    const response = await fetchApi(`/search?query=${encodeURIComponent(query)}&limit=20`);
+    const results = response?.items || [];
+    if (results.length === 0) {
         alert('No results found');
         return;
     }
@@ -893,6 +894,8 @@ async function performSearch() {
             display = `Block #${r.block.height}`;
         } else if (r.transaction) {
             display = `Height: ${r.transaction.inclusionHeight}`;
+        } else if (r.label) {
+            display = r.label;
         } else if (r.address) {
             display = `Balance: ${nanoErgToErg(r.address.balance.nanoErgs)} ERG`;
         } else if (r.token) {
@@ -1057,6 +1060,7 @@ async function showTxDetail(txId) {
                 <div class="detail-row">
                     <span class="detail-value" style="font-size:0.75rem">
                         <a href="#" onclick="showAddressDetail('${out.address}')">${truncateId(out.address)}</a>
+                        <a href="#" onclick="showScriptDetail('${out.ergoTree}')" title="Decompile ErgoTree">{ }</a>
                     </span>
                     <span class="detail-label">${nanoErgToErg(out.value)} ERG</span>
                 </div>
@@ -1071,6 +1075,54 @@ async function showTxDetail(txId) {
     modal.classList.remove('hidden');
 }
 
+async function showScriptDetail(ergoTree) {
+    const tree = await postApi('/utils/ergoTree/decompile', { ergoTree });
+
+    const modal = document.getElementById('detail-modal');
+    document.getElementById('detail-title').textContent = 'Script';
+
+    if (!tree?.script) {
+        document.getElementById('detail-content').innerHTML = `
+            <div class="detail-section">
+                <h4>ErgoTree</h4>
+                <pre class="script-code">${ergoTree}</pre>
+                <p>This script could not be decompiled.</p>
+            </div>
+        `;
+        modal.classList.remove('hidden');
+        return;
+    }
+
+    document.getElementById('detail-content').innerHTML = `
+        <div class="detail-section">
+            <h4>Script (v${tree.header.version})</h4>
+            <pre class="script-code">${escapeHtml(tree.script)}</pre>
+        </div>
+        ${tree.constants.length > 0 ? `
+        <div class="detail-section">
+            <h4>Constants (${tree.constants.length})</h4>
+            ${tree.constants.map(c => `
+                <div class="detail-row">
+                    <span class="detail-label">#${c.index} ${escapeHtml(c.sigmaType)}</span>
+                    <span class="detail-value" style="font-size:0.75rem">${escapeHtml(c.renderedValue)}</span>
+                </div>
+            `).join('')}
+        </div>
+        ` : ''}
+        <div class="detail-section">
+            <h4>ErgoTree</h4>
+            <div class="detail-row">
+                <span class="detail-value" style="max-width:100%; font-size:0.75rem">
+                    ${ergoTree}
+                    <button class="copy-btn" onclick="copyToClipboard('${ergoTree}')">📋</button>
+                </span>
+            </div>
+        </div>
+    `;
+
+    modal.classList.remove('hidden');
+}
+
 async function showAddressDetail(address) {
     const info = await fetchApi(`/addresses/${address}`);
     if (!info) return;
@@ -1276,7 +1328,7 @@ async function showTokenDetail(tokenId) {
         </div>
 
         <div class="detail-section">
-            <h4>Top Holders</h4>
+            <h4>Top Holders${holders?.holders ? ` (${formatNumber(holders.holders)} total, top 10 hold ${holders.distribution.top10.toFixed(1)}%)` : ''}</h4>
             ${holders?.items?.map(h => `
                 <div class="detail-row">
                     <span class="detail-value" style="font-size:0.75rem">